@@ -18,7 +18,7 @@ struct Fixture<'a> {
 
 fn test_key_derivation<D>(fixtures: &[Fixture])
 where
-    D: Digest + FixedOutputReset,
+    D: Digest + FixedOutputReset + ansi_x963_kdf::WipeOnDrop,
 {
     for f in fixtures.iter() {
         let mut buf = [0; 256];
@@ -191,3 +191,14 @@ fn test_no_output() {
         Err(ansi_x963_kdf::Error::NoOutput)
     );
 }
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn test_zeroize_on_drop() {
+    fn assert_zeroize_on_drop<D: digest::zeroize::ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<Sha256>();
+
+    let mut key = [0u8; 16];
+    ansi_x963_kdf::derive_key_into::<Sha256>(b"secret", b"shared-info", &mut key).unwrap();
+    assert_ne!(key, [0u8; 16]);
+}
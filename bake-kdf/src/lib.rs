@@ -7,9 +7,12 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![allow(clippy::unwrap_used, reason = "todo")]
 
-use belt_hash::digest::FixedOutput;
+use belt_hash::digest::{FixedOutput, Output};
 use belt_hash::{BeltHash, Digest, block_api::belt_compress};
 
+#[cfg(feature = "zeroize")]
+use belt_hash::digest::zeroize::Zeroize;
+
 /// `belt-keyexpand` key expansion algorithm described in STB 34.101.31-2020 8.1.2.
 ///
 /// # Panics
@@ -81,7 +84,7 @@ pub fn belt_keyrep<const N: usize, const M: usize>(
         _ => panic!("belt-keyrep: invalid combination of N ({N}) and M ({M})"),
     };
 
-    let s = belt_keyexpand(x);
+    let mut s = belt_keyexpand(x);
 
     let d = [
         u32::from_le_bytes(d[..4].try_into().unwrap()),
@@ -96,12 +99,16 @@ pub fn belt_keyrep<const N: usize, const M: usize>(
         u32::from_le_bytes(i[12..][..4].try_into().unwrap()),
     ];
 
-    let (_, s) = belt_compress([r, d[0], d[1], d[2]], i, s);
+    (_, s) = belt_compress([r, d[0], d[1], d[2]], i, s);
 
     let mut y = [0u8; M];
     for (src, dst) in s.iter().zip(y.chunks_exact_mut(4)) {
         dst.copy_from_slice(&src.to_le_bytes());
     }
+
+    #[cfg(feature = "zeroize")]
+    s.zeroize();
+
     y
 }
 
@@ -112,7 +119,13 @@ pub fn bake_kdf(x: &[u8], s: &[u8], c: u128) -> [u8; 32] {
     let mut hasher = BeltHash::default();
     hasher.update(x);
     hasher.update(s);
-    let y = hasher.finalize_fixed().0;
+    let mut y = Output::<BeltHash>::default();
+    FixedOutput::finalize_into(hasher, &mut y);
+
+    let key = belt_keyrep(&y.0, &[0xFF; 12], &c.to_le_bytes());
+
+    #[cfg(feature = "zeroize")]
+    y.as_mut_slice().zeroize();
 
-    belt_keyrep(&y, &[0xFF; 12], &c.to_le_bytes())
+    key
 }
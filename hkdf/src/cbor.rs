@@ -0,0 +1,134 @@
+//! Minimal CBOR ([RFC 8949]) encoder producing the parts of an encoding as slices.
+//!
+//! The heads of the data items are written into fixed-size buffers, so the encoding can
+//! be fed into [`GenericHkdf::expand_multi_info`][crate::GenericHkdf::expand_multi_info]
+//! without allocating.
+//!
+//! [RFC 8949]: https://www.rfc-editor.org/rfc/rfc8949
+
+use core::slice::IterMut;
+
+/// Maximum length of the head of a data item.
+const MAX_HEAD_LEN: usize = 9;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+
+/// Encoding of the CBOR `null` value.
+const NULL: &[u8] = &[0xF6];
+
+/// Buffers for the heads of the data items of an encoding.
+pub(crate) type Heads<const N: usize> = [[u8; MAX_HEAD_LEN]; N];
+
+/// Encoder of up to `N` parts using the given head buffers.
+pub(crate) struct Encoder<'a, const N: usize> {
+    heads: IterMut<'a, [u8; MAX_HEAD_LEN]>,
+    parts: [&'a [u8]; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> Encoder<'a, N> {
+    pub(crate) fn new<const M: usize>(heads: &'a mut Heads<M>) -> Self {
+        Self {
+            heads: heads.iter_mut(),
+            parts: [&[]; N],
+            len: 0,
+        }
+    }
+
+    /// Encoded parts, whose concatenation is the encoding.
+    pub(crate) fn parts(&self) -> &[&'a [u8]] {
+        &self.parts[..self.len]
+    }
+
+    fn push(&mut self, part: &'a [u8]) {
+        self.parts[self.len] = part;
+        self.len += 1;
+    }
+
+    fn head(&mut self, major: u8, value: u64) {
+        let buf = self.heads.next().expect("number of heads is sufficient");
+        let len = match value {
+            0..24 => {
+                buf[0] = u8::try_from(value).expect("value is smaller than 24");
+                1
+            }
+            24..0x100 => {
+                buf[0] = 24;
+                buf[1..2].copy_from_slice(&value.to_be_bytes()[7..]);
+                2
+            }
+            0x100..0x1_0000 => {
+                buf[0] = 25;
+                buf[1..3].copy_from_slice(&value.to_be_bytes()[6..]);
+                3
+            }
+            0x1_0000..0x1_0000_0000 => {
+                buf[0] = 26;
+                buf[1..5].copy_from_slice(&value.to_be_bytes()[4..]);
+                5
+            }
+            _ => {
+                buf[0] = 27;
+                buf[1..9].copy_from_slice(&value.to_be_bytes());
+                9
+            }
+        };
+        buf[0] |= major << 5;
+        let buf: &'a [u8; MAX_HEAD_LEN] = buf;
+        self.push(&buf[..len]);
+    }
+
+    /// Encode an unsigned integer.
+    pub(crate) fn uint(&mut self, value: u64) {
+        self.head(UNSIGNED, value);
+    }
+
+    /// Encode a signed integer.
+    pub(crate) fn int(&mut self, value: i64) {
+        match u64::try_from(value) {
+            Ok(value) => self.head(UNSIGNED, value),
+            Err(_) => self.head(NEGATIVE, value.unsigned_abs() - 1),
+        }
+    }
+
+    /// Encode a byte string.
+    pub(crate) fn bytes(&mut self, value: &'a [u8]) {
+        self.head(BYTES, value.len() as u64);
+        self.push(value);
+    }
+
+    /// Encode the concatenation of `a` and `b` as a single byte string.
+    pub(crate) fn bytes_concat(&mut self, a: &'a [u8], b: &'a [u8]) {
+        self.head(BYTES, (a.len() + b.len()) as u64);
+        self.push(a);
+        self.push(b);
+    }
+
+    /// Encode a text string.
+    pub(crate) fn text(&mut self, value: &'a str) {
+        self.head(TEXT, value.len() as u64);
+        self.push(value.as_bytes());
+    }
+
+    /// Encode the head of an array of `len` data items.
+    pub(crate) fn array(&mut self, len: u64) {
+        self.head(ARRAY, len);
+    }
+
+    /// Encode `null`.
+    pub(crate) fn null(&mut self) {
+        self.push(NULL);
+    }
+
+    /// Encode a byte string or `null`.
+    pub(crate) fn bytes_or_null(&mut self, value: Option<&'a [u8]>) {
+        match value {
+            Some(value) => self.bytes(value),
+            None => self.null(),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! COSE `COSE_KDF_Context` structure used as the HKDF `info` of COSE key agreement.
+//!
+//! See [RFC 9053 Section 5.2](https://www.rfc-editor.org/rfc/rfc9053#section-5.2).
+//!
+//! The context is encoded without allocating and can be used with the direct key
+//! agreement algorithms (`direct+HKDF-SHA-256`, ...) and the ECDH algorithms
+//! (`ECDH-ES + HKDF-256`, ...) of [RFC 9053 Section 6]:
+//!
+//! ```
+//! use hkdf::cose::{AlgorithmId, KdfContext, PartyInfo};
+//! use hkdf::hmac::Hmac;
+//! use sha2::Sha256;
+//!
+//! let context = KdfContext {
+//!     // A128GCM
+//!     algorithm_id: AlgorithmId::Int(1),
+//!     party_u: PartyInfo::default(),
+//!     party_v: PartyInfo::default(),
+//!     // protected header map of the recipient: {1: -25} (ECDH-ES + HKDF-256)
+//!     protected: &[0xA1, 0x01, 0x38, 0x18],
+//!     supp_pub_other: None,
+//!     supp_priv: None,
+//! };
+//!
+//! let mut key = [0u8; 16];
+//! context
+//!     .derive::<Hmac<Sha256>>(None, b"ECDH shared secret", &mut key)
+//!     .unwrap();
+//! ```
+//!
+//! [RFC 9053 Section 6]: https://www.rfc-editor.org/rfc/rfc9053#section-6
+
+use crate::{GenericHkdf, HmacImpl, InvalidLength, cbor};
+
+/// Maximum number of data item heads of the encoding.
+const MAX_HEADS: usize = 16;
+/// Maximum number of parts of the encoding.
+const MAX_PARTS: usize = 26;
+
+/// The `AlgorithmID` field: the algorithm the derived key is used with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlgorithmId<'a> {
+    /// Algorithm registered with an integer identifier.
+    Int(i64),
+    /// Algorithm registered with a text string identifier.
+    Text(&'a str),
+}
+
+/// The `nonce` field of [`PartyInfo`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Nonce<'a> {
+    /// No nonce, encoded as `nil`.
+    #[default]
+    Nil,
+    /// Byte string nonce.
+    Bytes(&'a [u8]),
+    /// Integer nonce, e.g. a counter.
+    Int(i64),
+}
+
+/// The `PartyUInfo` and `PartyVInfo` fields.
+///
+/// `None` fields are encoded as `nil`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartyInfo<'a> {
+    /// Identity of the party, e.g. the `PartyU identity` header parameter.
+    pub identity: Option<&'a [u8]>,
+    /// Nonce of the party, e.g. the `PartyU nonce` header parameter.
+    pub nonce: Nonce<'a>,
+    /// Other information about the party, e.g. the `PartyU other` header parameter.
+    pub other: Option<&'a [u8]>,
+}
+
+/// The `COSE_KDF_Context` structure.
+///
+/// The `keyDataLength` field is not part of the struct, but is set to the length of the
+/// derived key in bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfContext<'a> {
+    /// The `AlgorithmID` field.
+    pub algorithm_id: AlgorithmId<'a>,
+    /// The `PartyUInfo` field.
+    pub party_u: PartyInfo<'a>,
+    /// The `PartyVInfo` field.
+    pub party_v: PartyInfo<'a>,
+    /// The serialized protected header map of the recipient structure of the `protected`
+    /// field of `SuppPubInfo`, or an empty slice if there is none.
+    pub protected: &'a [u8],
+    /// The optional `other` field of `SuppPubInfo`.
+    pub supp_pub_other: Option<&'a [u8]>,
+    /// The optional `SuppPrivInfo` field.
+    pub supp_priv: Option<&'a [u8]>,
+}
+
+impl<'a> KdfContext<'a> {
+    /// Context without party information and protected header map.
+    #[must_use]
+    pub const fn new(algorithm_id: AlgorithmId<'a>) -> Self {
+        Self {
+            algorithm_id,
+            party_u: PartyInfo {
+                identity: None,
+                nonce: Nonce::Nil,
+                other: None,
+            },
+            party_v: PartyInfo {
+                identity: None,
+                nonce: Nonce::Nil,
+                other: None,
+            },
+            protected: &[],
+            supp_pub_other: None,
+            supp_priv: None,
+        }
+    }
+
+    /// Call `f` with the parts of the encoding of the context for a `key_len` bytes key.
+    fn with_parts<R>(
+        &self,
+        key_len: usize,
+        f: impl FnOnce(&[&[u8]]) -> R,
+    ) -> Result<R, InvalidLength> {
+        let key_data_length = u64::try_from(key_len)
+            .ok()
+            .and_then(|len| len.checked_mul(8))
+            .ok_or(InvalidLength)?;
+
+        let mut heads = cbor::Heads::<MAX_HEADS>::default();
+        let mut enc = cbor::Encoder::<MAX_PARTS>::new(&mut heads);
+        enc.array(if self.supp_priv.is_some() { 5 } else { 4 });
+        match self.algorithm_id {
+            AlgorithmId::Int(id) => enc.int(id),
+            AlgorithmId::Text(id) => enc.text(id),
+        }
+        for party in [&self.party_u, &self.party_v] {
+            enc.array(3);
+            enc.bytes_or_null(party.identity);
+            match party.nonce {
+                Nonce::Nil => enc.null(),
+                Nonce::Bytes(nonce) => enc.bytes(nonce),
+                Nonce::Int(nonce) => enc.int(nonce),
+            }
+            enc.bytes_or_null(party.other);
+        }
+        enc.array(if self.supp_pub_other.is_some() { 3 } else { 2 });
+        enc.uint(key_data_length);
+        enc.bytes(self.protected);
+        if let Some(other) = self.supp_pub_other {
+            enc.bytes(other);
+        }
+        if let Some(supp_priv) = self.supp_priv {
+            enc.bytes(supp_priv);
+        }
+        Ok(f(enc.parts()))
+    }
+
+    /// Length of the encoding of the context for a `key_len` bytes key.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key_len` in bits does not fit into a `u64`.
+    pub fn encoded_len(&self, key_len: usize) -> Result<usize, InvalidLength> {
+        self.with_parts(key_len, |parts| parts.iter().map(|part| part.len()).sum())
+    }
+
+    /// Encode the context for a `key_len` bytes key into `buf`, returning the length
+    /// of the encoding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `buf` is too small.
+    pub fn encode(&self, key_len: usize, buf: &mut [u8]) -> Result<usize, InvalidLength> {
+        self.with_parts(key_len, |parts| {
+            let mut len = 0;
+            for part in parts {
+                buf.get_mut(len..len + part.len())
+                    .ok_or(InvalidLength)?
+                    .copy_from_slice(part);
+                len += part.len();
+            }
+            Ok(len)
+        })?
+    }
+
+    /// HKDF-Expand with the context as `info`.
+    ///
+    /// The length of `okm` is used as the `keyDataLength` field.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large.
+    pub fn expand<H: HmacImpl>(
+        &self,
+        hkdf: &GenericHkdf<H>,
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        self.with_parts(okm.len(), |parts| hkdf.expand_multi_info(parts, okm))?
+    }
+
+    /// HKDF with the context as `info`, e.g. deriving the key of a COSE key agreement
+    /// algorithm from the shared secret.
+    ///
+    /// `salt` is the `salt` header parameter, if any. The length of `okm` is used as the
+    /// `keyDataLength` field.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large.
+    pub fn derive<H: HmacImpl>(
+        &self,
+        salt: Option<&[u8]>,
+        secret: &[u8],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        self.expand(&GenericHkdf::<H>::new(salt, secret), okm)
+    }
+}
@@ -0,0 +1,132 @@
+//! EDHOC `EDHOC_Extract`, `EDHOC_Expand` and `EDHOC_KDF` functions and key schedule.
+//!
+//! See [RFC 9528 Section 4](https://www.rfc-editor.org/rfc/rfc9528#section-4).
+//!
+//! Only cipher suites using HKDF as EDHOC hash algorithm are supported. The key schedule
+//! is represented by the [`Prk2e`], [`Prk3e2m`], [`Prk4e3m`], [`PrkOut`] and
+//! [`PrkExporter`] pseudorandom keys. The DH outputs and the transcript hashes are
+//! computed by the caller:
+//!
+//! ```
+//! use hkdf::edhoc::Prk2e;
+//! use sha2::Sha256;
+//!
+//! let (th_2, th_3, th_4) = ([0x02; 32], [0x03; 32], [0x04; 32]);
+//!
+//! let prk_2e = Prk2e::<Sha256>::derive(&th_2, b"G_XY");
+//! let mut keystream_2 = [0u8; 11];
+//! prk_2e.keystream_2(&th_2, &mut keystream_2).unwrap();
+//!
+//! // the responder authenticates with a signature, the initiator with a static DH key
+//! let prk_3e2m = prk_2e.prk_3e2m(&th_2, None);
+//! let mut mac_2 = [0u8; 8];
+//! prk_3e2m.mac_2(b"<< C_R, ID_CRED_R, TH_2, CRED_R >>", &mut mac_2).unwrap();
+//! let prk_4e3m = prk_3e2m.prk_4e3m(&th_3, Some(b"G_IY"));
+//!
+//! let prk_out = prk_4e3m.prk_out(&th_4);
+//! let mut master_secret = [0u8; 16];
+//! let mut master_salt = [0u8; 8];
+//! let exporter = prk_out.prk_exporter();
+//! exporter.oscore_master_secret(&mut master_secret).unwrap();
+//! exporter.oscore_master_salt(&mut master_salt).unwrap();
+//!
+//! let prk_out = prk_out.key_update(b"nonce");
+//! ```
+
+use crate::{GenericHkdf, HmacImpl, InvalidLength, cbor};
+use hmac::digest::Output;
+
+mod key_schedule;
+
+pub use key_schedule::{Prk2e, Prk3e2m, Prk4e3m, PrkExporter, PrkOut};
+
+/// Exporter label of the OSCORE Master Secret ([RFC 9528 Appendix A.1]).
+///
+/// [RFC 9528 Appendix A.1]: https://www.rfc-editor.org/rfc/rfc9528#appendix-A.1
+pub const OSCORE_MASTER_SECRET_LABEL: u32 = 0;
+
+/// Exporter label of the OSCORE Master Salt ([RFC 9528 Appendix A.1]).
+///
+/// [RFC 9528 Appendix A.1]: https://www.rfc-editor.org/rfc/rfc9528#appendix-A.1
+pub const OSCORE_MASTER_SALT_LABEL: u32 = 1;
+
+/// The `info` parameter of `EDHOC_Expand` without its `length` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Info<'a> {
+    /// The `info_label` field.
+    pub label: i64,
+    /// The `context` field.
+    pub context: &'a [u8],
+}
+
+impl<'a> Info<'a> {
+    /// Create the `info` parameter from its `info_label` and `context` fields.
+    #[must_use]
+    pub const fn new(label: i64, context: &'a [u8]) -> Self {
+        Self { label, context }
+    }
+
+    /// Call `f` with the parts of the encoding of `info` with the `length` field.
+    fn with_parts<R>(&self, length: usize, f: impl FnOnce(&[&[u8]]) -> R) -> R {
+        let mut heads = cbor::Heads::<3>::default();
+        let mut enc = cbor::Encoder::<4>::new(&mut heads);
+        enc.int(self.label);
+        enc.bytes(self.context);
+        enc.uint(length as u64);
+        f(enc.parts())
+    }
+
+    /// Encode `info` as the CBOR sequence `(info_label, context, length)` into `buf`,
+    /// returning the length of the encoding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `buf` is too small.
+    pub fn encode(&self, length: usize, buf: &mut [u8]) -> Result<usize, InvalidLength> {
+        self.with_parts(length, |parts| {
+            let mut len = 0;
+            for part in parts {
+                buf.get_mut(len..len + part.len())
+                    .ok_or(InvalidLength)?
+                    .copy_from_slice(part);
+                len += part.len();
+            }
+            Ok(len)
+        })
+    }
+}
+
+/// The `EDHOC_Extract` function of cipher suites using HKDF, i.e. HKDF-Extract,
+/// returning both the generated pseudorandom key and `Hkdf` struct for expanding.
+#[must_use]
+pub fn edhoc_extract<H: HmacImpl>(salt: &[u8], ikm: &[u8]) -> (Output<H>, GenericHkdf<H>) {
+    GenericHkdf::<H>::extract(Some(salt), ikm)
+}
+
+/// The `EDHOC_Expand` function of cipher suites using HKDF.
+///
+/// The length of `okm` is used as the `length` field of `info`.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `okm` is too large.
+pub fn edhoc_expand<H: HmacImpl>(
+    prk: &GenericHkdf<H>,
+    info: &Info<'_>,
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    info.with_parts(okm.len(), |parts| prk.expand_multi_info(parts, okm))
+}
+
+/// The `EDHOC_KDF` function of cipher suites using HKDF.
+///
+/// The length of `okm` is used as the `length` field of `info`.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `okm` is too large.
+pub fn edhoc_kdf<H: HmacImpl>(
+    prk: &GenericHkdf<H>,
+    info_label: i64,
+    context: &[u8],
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    edhoc_expand(prk, &Info::new(info_label, context), okm)
+}
@@ -0,0 +1,273 @@
+use super::{OSCORE_MASTER_SALT_LABEL, OSCORE_MASTER_SECRET_LABEL, edhoc_kdf};
+use crate::{Hkdf, InvalidLength, Secret};
+use core::fmt;
+use hmac::{EagerHash, Hmac, digest::Output};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+const KEYSTREAM_2: i64 = 0;
+const SALT_3E2M: i64 = 1;
+const MAC_2: i64 = 2;
+const K_3: i64 = 3;
+const IV_3: i64 = 4;
+const SALT_4E3M: i64 = 5;
+const MAC_3: i64 = 6;
+const PRK_OUT: i64 = 7;
+const K_4: i64 = 8;
+const IV_4: i64 = 9;
+const PRK_EXPORTER: i64 = 10;
+const KEY_UPDATE: i64 = 11;
+
+fn hkdf<D: EagerHash>(prk: &Output<Hmac<D>>) -> Hkdf<D> {
+    Hkdf::<D>::from_prk(prk).expect("PRK has the hash output size")
+}
+
+/// `EDHOC_KDF` with a `hash_length` output.
+fn derive<D: EagerHash>(prk: &Output<Hmac<D>>, label: i64, context: &[u8]) -> Output<Hmac<D>> {
+    let mut out = Output::<Hmac<D>>::default();
+    edhoc_kdf(&hkdf::<D>(prk), label, context, &mut out).expect("hash length is valid");
+    out
+}
+
+/// `EDHOC_Extract(salt, ikm)`, or `prk` if `ikm` is `None`.
+fn extract<D: EagerHash>(
+    prk: &Output<Hmac<D>>,
+    salt: &[u8],
+    ikm: Option<&[u8]>,
+) -> Output<Hmac<D>> {
+    match ikm {
+        Some(ikm) => {
+            let (prk, _) = Hkdf::<D>::extract(Some(salt), ikm);
+            Output::<Hmac<D>>::clone(&prk)
+        }
+        None => prk.clone(),
+    }
+}
+
+macro_rules! prk {
+    ($name:ident, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name<D: EagerHash> {
+            prk: Output<Hmac<D>>,
+        }
+
+        impl<D: EagerHash> $name<D> {
+            /// Wrap an existing pseudorandom key.
+            #[must_use]
+            pub fn new(prk: Output<Hmac<D>>) -> Self {
+                Self { prk }
+            }
+
+            /// Get the raw pseudorandom key.
+            #[must_use]
+            pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+                &self.prk
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        impl<D: EagerHash> Drop for $name<D> {
+            fn drop(&mut self) {
+                self.prk.zeroize();
+            }
+        }
+
+        impl<D: EagerHash> fmt::Debug for $name<D> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+prk!(
+    Prk2e,
+    "The `PRK_2e` pseudorandom key used to encrypt `message_2`."
+);
+prk!(
+    Prk3e2m,
+    "The `PRK_3e2m` pseudorandom key used to compute `MAC_2` and to protect `message_3`."
+);
+prk!(
+    Prk4e3m,
+    "The `PRK_4e3m` pseudorandom key used to compute `MAC_3` and to protect `message_4`."
+);
+prk!(
+    PrkOut,
+    "The `PRK_out` pseudorandom key, the output of EDHOC."
+);
+prk!(
+    PrkExporter,
+    "The `PRK_exporter` pseudorandom key used by `EDHOC_Exporter`."
+);
+
+impl<D: EagerHash> Prk2e<D> {
+    /// Derive `PRK_2e = EDHOC_Extract(TH_2, G_XY)`.
+    #[must_use]
+    pub fn derive(th_2: &[u8], g_xy: &[u8]) -> Self {
+        let (prk, _) = Hkdf::<D>::extract(Some(th_2), g_xy);
+        Self::new(Output::<Hmac<D>>::clone(&prk))
+    }
+
+    /// Derive `KEYSTREAM_2` used to encrypt `PLAINTEXT_2`.
+    ///
+    /// The length of `keystream` is used as the plaintext length.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `keystream` is too large.
+    pub fn keystream_2(&self, th_2: &[u8], keystream: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), KEYSTREAM_2, th_2, keystream)
+    }
+
+    /// Derive `SALT_3e2m`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "hash length is valid")]
+    pub fn salt_3e2m(&self, th_2: &[u8]) -> Output<Hmac<D>> {
+        derive::<D>(&self.prk, SALT_3E2M, th_2)
+    }
+
+    /// Derive `PRK_3e2m = EDHOC_Extract(SALT_3e2m, G_RX)`.
+    ///
+    /// `g_rx` is `None` if the responder authenticates with a signature, in which case
+    /// `PRK_3e2m = PRK_2e`.
+    #[must_use]
+    pub fn prk_3e2m(&self, th_2: &[u8], g_rx: Option<&[u8]>) -> Prk3e2m<D> {
+        let salt = Secret::<Output<Hmac<D>>>::from(self.salt_3e2m(th_2));
+        Prk3e2m::new(extract::<D>(&self.prk, &salt, g_rx))
+    }
+}
+
+impl<D: EagerHash> Prk3e2m<D> {
+    /// Derive `MAC_2` from `context_2 = << C_R, ID_CRED_R, TH_2, CRED_R, ? EAD_2 >>`.
+    ///
+    /// The length of `mac` is used as `mac_length_2`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `mac` is too large.
+    pub fn mac_2(&self, context_2: &[u8], mac: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), MAC_2, context_2, mac)
+    }
+
+    /// Derive the key `K_3` used to protect `message_3`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key` is too large.
+    pub fn k_3(&self, th_3: &[u8], key: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), K_3, th_3, key)
+    }
+
+    /// Derive the nonce `IV_3` used to protect `message_3`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `iv` is too large.
+    pub fn iv_3(&self, th_3: &[u8], iv: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), IV_3, th_3, iv)
+    }
+
+    /// Derive `SALT_4e3m`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "hash length is valid")]
+    pub fn salt_4e3m(&self, th_3: &[u8]) -> Output<Hmac<D>> {
+        derive::<D>(&self.prk, SALT_4E3M, th_3)
+    }
+
+    /// Derive `PRK_4e3m = EDHOC_Extract(SALT_4e3m, G_IY)`.
+    ///
+    /// `g_iy` is `None` if the initiator authenticates with a signature, in which case
+    /// `PRK_4e3m = PRK_3e2m`.
+    #[must_use]
+    pub fn prk_4e3m(&self, th_3: &[u8], g_iy: Option<&[u8]>) -> Prk4e3m<D> {
+        let salt = Secret::<Output<Hmac<D>>>::from(self.salt_4e3m(th_3));
+        Prk4e3m::new(extract::<D>(&self.prk, &salt, g_iy))
+    }
+}
+
+impl<D: EagerHash> Prk4e3m<D> {
+    /// Derive `MAC_3` from `context_3 = << ID_CRED_I, TH_3, CRED_I, ? EAD_3 >>`.
+    ///
+    /// The length of `mac` is used as `mac_length_3`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `mac` is too large.
+    pub fn mac_3(&self, context_3: &[u8], mac: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), MAC_3, context_3, mac)
+    }
+
+    /// Derive the key `K_4` used to protect `message_4`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key` is too large.
+    pub fn k_4(&self, th_4: &[u8], key: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), K_4, th_4, key)
+    }
+
+    /// Derive the nonce `IV_4` used to protect `message_4`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `iv` is too large.
+    pub fn iv_4(&self, th_4: &[u8], iv: &mut [u8]) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), IV_4, th_4, iv)
+    }
+
+    /// Derive `PRK_out`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "hash length is valid")]
+    pub fn prk_out(&self, th_4: &[u8]) -> PrkOut<D> {
+        PrkOut::new(derive::<D>(&self.prk, PRK_OUT, th_4))
+    }
+}
+
+impl<D: EagerHash> PrkOut<D> {
+    /// Derive a new `PRK_out` with the `EDHOC_KeyUpdate` function ([RFC 9528 Appendix H]).
+    ///
+    /// `context` should contain nonces exchanged by the peers.
+    ///
+    /// [RFC 9528 Appendix H]: https://www.rfc-editor.org/rfc/rfc9528#appendix-H
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "hash length is valid")]
+    pub fn key_update(&self, context: &[u8]) -> Self {
+        Self::new(derive::<D>(&self.prk, KEY_UPDATE, context))
+    }
+
+    /// Derive `PRK_exporter`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "hash length is valid")]
+    pub fn prk_exporter(&self) -> PrkExporter<D> {
+        PrkExporter::new(derive::<D>(&self.prk, PRK_EXPORTER, &[]))
+    }
+}
+
+impl<D: EagerHash> PrkExporter<D> {
+    /// The `EDHOC_Exporter(exporter_label, context, length)` function.
+    ///
+    /// The length of `out` is used as `length`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `out` is too large.
+    pub fn exporter(
+        &self,
+        label: u32,
+        context: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        edhoc_kdf(&hkdf::<D>(&self.prk), label.into(), context, out)
+    }
+
+    /// Export the OSCORE Master Secret.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `secret` is too large.
+    pub fn oscore_master_secret(&self, secret: &mut [u8]) -> Result<(), InvalidLength> {
+        self.exporter(OSCORE_MASTER_SECRET_LABEL, &[], secret)
+    }
+
+    /// Export the OSCORE Master Salt.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `salt` is too large.
+    pub fn oscore_master_salt(&self, salt: &mut [u8]) -> Result<(), InvalidLength> {
+        self.exporter(OSCORE_MASTER_SALT_LABEL, &[], salt)
+    }
+}
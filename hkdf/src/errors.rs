@@ -1,12 +1,13 @@
 use core::fmt;
 
-/// Error that is returned when supplied pseudorandom key (PRK) is not long enough.
+/// Error that is returned when supplied pseudorandom key (PRK) is not long enough
+/// or is rejected by the underlying PRF.
 #[derive(Copy, Clone, Debug)]
 pub struct InvalidPrkLength;
 
 impl fmt::Display for InvalidPrkLength {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        f.write_str("invalid pseudorandom key length, too short")
+        f.write_str("invalid pseudorandom key length")
     }
 }
 
@@ -23,3 +24,15 @@ impl fmt::Display for InvalidLength {
 }
 
 impl core::error::Error for InvalidLength {}
+
+/// Error that is returned when a key (e.g. the salt) is rejected by the underlying PRF.
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidKeyLength;
+
+impl fmt::Display for InvalidKeyLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str("invalid key length for the underlying PRF")
+    }
+}
+
+impl core::error::Error for InvalidKeyLength {}
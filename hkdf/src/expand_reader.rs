@@ -0,0 +1,97 @@
+use crate::{GenericHkdf, HmacImpl, InvalidLength};
+use core::fmt;
+use hmac::digest::{Output, OutputSizeUser, array::typenum::Unsigned};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Reader over the output of an HKDF-Expand operation.
+///
+/// Created by [`GenericHkdf::expand_reader`]. Only the previous output block `T(i-1)`
+/// is stored, so the OKM can be read in pieces of any size into small buffers.
+#[derive(Clone)]
+pub struct ExpandReader<'a, H: HmacImpl> {
+    hmac: H,
+    info: &'a [u8],
+    /// Previous output block `T(i-1)`.
+    prev: Output<H>,
+    /// Index `i` of the previous output block, zero if no block was produced yet.
+    block_n: u8,
+    /// Number of bytes of `prev` which were already read.
+    pos: usize,
+    /// Number of bytes which can still be read.
+    remaining: usize,
+}
+
+impl<'a, H: HmacImpl> ExpandReader<'a, H> {
+    pub(crate) fn new(hkdf: &GenericHkdf<H>, info: &'a [u8]) -> Self {
+        let chunk_len = <H as OutputSizeUser>::OutputSize::USIZE;
+        Self {
+            hmac: hkdf.hmac.clone(),
+            info,
+            prev: Output::<H>::default(),
+            block_n: 0,
+            pos: chunk_len,
+            remaining: chunk_len * 255,
+        }
+    }
+
+    /// Number of OKM bytes which can still be read.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Fill `buf` with the next bytes of the OKM.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `buf` is longer than the remaining OKM. In this case
+    /// nothing is read.
+    #[allow(clippy::missing_panics_doc, reason = "block counter can not overflow")]
+    pub fn read(&mut self, mut buf: &mut [u8]) -> Result<(), InvalidLength> {
+        if buf.len() > self.remaining {
+            return Err(InvalidLength);
+        }
+        self.remaining -= buf.len();
+
+        while !buf.is_empty() {
+            if self.pos == self.prev.len() {
+                let mut hmac = self.hmac.clone();
+                if self.block_n != 0 {
+                    hmac.update(&self.prev);
+                }
+                hmac.update(self.info);
+                self.block_n = self
+                    .block_n
+                    .checked_add(1)
+                    .expect("block counter can not overflow");
+                hmac.update(&[self.block_n]);
+                self.prev = hmac.finalize();
+                self.pos = 0;
+            }
+
+            let n = buf.len().min(self.prev.len() - self.pos);
+            let (head, tail) = buf.split_at_mut(n);
+            head.copy_from_slice(&self.prev[self.pos..][..n]);
+            self.pos += n;
+            buf = tail;
+        }
+
+        Ok(())
+    }
+}
+
+impl<H: HmacImpl> fmt::Debug for ExpandReader<'_, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpandReader")
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<H: HmacImpl> Drop for ExpandReader<'_, H> {
+    fn drop(&mut self) {
+        self.prev.zeroize();
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{GenericHkdf, HmacImpl, InvalidLength};
+
+/// Big-endian `u32` length prefix of a framed `info` component.
+///
+/// The length must have been checked to fit into a `u32`.
+pub(crate) fn framed_len(component: &[u8]) -> [u8; 4] {
+    u32::try_from(component.len())
+        .expect("component length is checked")
+        .to_be_bytes()
+}
+
+/// HKDF `info` made of a label and a context, encoded unambiguously.
+///
+/// The label and the context are prefixed with their lengths as big-endian `u32`s,
+/// the framing used by [`GenericHkdf::expand_framed_info`]:
+///
+/// ```text
+/// info = u32be(len(label)) || label || u32be(len(context)) || context
+/// ```
+///
+/// The encoding is injective: two different `(label, context)` pairs never produce the
+/// same `info`, even if the label contains the bytes of a length prefix or ends with
+/// a prefix of the context. Keys derived from the same PRK with different labels are
+/// thus domain separated, as long as all `info` values used with the PRK are framed.
+///
+/// ```
+/// use hkdf::{FramedInfo, Hkdf};
+/// use sha2::Sha256;
+///
+/// let hk = Hkdf::<Sha256>::new(None, b"input key material");
+/// let info = FramedInfo::builder(b"encryption key")
+///     .with_context(b"session 42")
+///     .build();
+///
+/// let mut okm = [0u8; 32];
+/// info.expand(&hk, &mut okm).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FramedInfo<'l, 'c> {
+    /// Label identifying the purpose of the derived key.
+    pub label: &'l [u8],
+    /// Context the key is derived for, e.g. identities of the parties or a nonce.
+    pub context: &'c [u8],
+}
+
+impl<'l, 'c> FramedInfo<'l, 'c> {
+    /// Create a new builder for [`FramedInfo`] with an empty context.
+    #[must_use]
+    pub fn builder(label: &'l [u8]) -> FramedInfoBuilder<'l, 'c> {
+        FramedInfoBuilder(Self {
+            label,
+            context: &[],
+        })
+    }
+
+    /// Length of the encoding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the label or the context is longer than `u32::MAX`
+    /// bytes.
+    pub fn encoded_len(&self) -> Result<usize, InvalidLength> {
+        let mut len = 0usize;
+        for component in [self.label, self.context] {
+            u32::try_from(component.len()).map_err(|_| InvalidLength)?;
+            len = len.checked_add(4 + component.len()).ok_or(InvalidLength)?;
+        }
+        Ok(len)
+    }
+
+    /// Encode the `info` into `buf`, returning the length of the encoding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `buf` is too small or the label or the context is
+    /// longer than `u32::MAX` bytes.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, InvalidLength> {
+        let len = self.encoded_len()?;
+        let mut pos = 0;
+        let buf = buf.get_mut(..len).ok_or(InvalidLength)?;
+        for component in [self.label, self.context] {
+            buf[pos..pos + 4].copy_from_slice(&framed_len(component));
+            buf[pos + 4..pos + 4 + component.len()].copy_from_slice(component);
+            pos += 4 + component.len();
+        }
+        Ok(len)
+    }
+
+    /// HKDF-Expand with the encoding as `info`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large or the label or the context is
+    /// longer than `u32::MAX` bytes.
+    pub fn expand<H: HmacImpl>(
+        &self,
+        hkdf: &GenericHkdf<H>,
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        hkdf.expand_framed_info(&[self.label, self.context], okm)
+    }
+}
+
+/// Builder for [`FramedInfo`].
+#[derive(Clone, Debug)]
+pub struct FramedInfoBuilder<'l, 'c>(FramedInfo<'l, 'c>);
+
+impl<'l, 'c> FramedInfoBuilder<'l, 'c> {
+    /// Return the built [`FramedInfo`].
+    #[must_use]
+    pub fn build(self) -> FramedInfo<'l, 'c> {
+        self.0
+    }
+
+    /// Set the context.
+    #[must_use]
+    pub fn with_context(mut self, context: &'c [u8]) -> Self {
+        self.0.context = context;
+        self
+    }
+}
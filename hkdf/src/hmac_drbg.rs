@@ -0,0 +1,208 @@
+//! `HMAC_DRBG` deterministic random bit generator.
+//!
+//! See [NIST SP 800-90A Rev. 1 Section 10.1.2](https://doi.org/10.6028/NIST.SP.800-90Ar1).
+//! Prediction resistance is not supported, the DRBG is only reseeded with entropy input
+//! provided by the caller.
+//!
+//! [`HmacDrbg`] implements [`TryRng`] and [`TryCryptoRng`], failing once a reseed is
+//! required. Wrap it in [`UnwrapErr`][rand_core::UnwrapErr] to get an infallible
+//! [`CryptoRng`][rand_core::CryptoRng]:
+//!
+//! ```
+//! use hkdf::hmac_drbg::HmacDrbg;
+//! use hkdf::rand_core::{Rng, UnwrapErr};
+//! use hmac::Hmac;
+//! use sha2::Sha256;
+//!
+//! let (entropy_input, nonce) = ([0x01; 32], [0x02; 16]);
+//! let mut drbg = HmacDrbg::<Hmac<Sha256>>::new(&entropy_input, &nonce, b"personalization");
+//!
+//! let mut key = [0u8; 32];
+//! drbg.generate(&mut key, b"additional input").unwrap();
+//! drbg.reseed(&[0x03; 32], &[]);
+//!
+//! let mut rng = UnwrapErr(drbg);
+//! let value = rng.next_u64();
+//! ```
+//!
+//! For reproducible output, e.g. in tests, [`SeedableRng`] instantiates the DRBG with the
+//! seed as entropy input and an empty nonce and personalization string.
+
+use crate::HmacImpl;
+use core::fmt;
+use hmac::digest::{Output, OutputSizeUser, array::typenum::Unsigned};
+use rand_core::{SeedableRng, TryCryptoRng, TryRng, utils};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Maximum number of requests between reseeds (`reseed_interval`).
+pub const MAX_RESEED_INTERVAL: u64 = 1 << 48;
+/// Maximum number of bytes per request (`max_number_of_bits_per_request`).
+pub const MAX_REQUEST_SIZE: usize = 1 << 16;
+
+/// `HMAC_DRBG` error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The reseed interval is exhausted and [`HmacDrbg::reseed`] has to be called.
+    ReseedRequired,
+    /// More than [`MAX_REQUEST_SIZE`] bytes were requested.
+    RequestTooLong,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(match self {
+            Error::ReseedRequired => "HMAC_DRBG reseed required",
+            Error::RequestTooLong => "HMAC_DRBG request too long",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// `HMAC_DRBG` instance.
+///
+/// The caller is responsible for providing entropy input of at least the security
+/// strength of `H` and a nonce of at least half of the security strength.
+pub struct HmacDrbg<H: HmacImpl> {
+    k: Output<H>,
+    v: Output<H>,
+    reseed_counter: u64,
+    reseed_interval: u64,
+}
+
+impl<H: HmacImpl> HmacDrbg<H> {
+    /// Instantiate the DRBG.
+    #[must_use]
+    pub fn new(entropy_input: &[u8], nonce: &[u8], personalization_string: &[u8]) -> Self {
+        let mut v = Output::<H>::default();
+        v.fill(0x01);
+        let mut drbg = Self {
+            k: Output::<H>::default(),
+            v,
+            reseed_counter: 1,
+            reseed_interval: MAX_RESEED_INTERVAL,
+        };
+        drbg.update(&[entropy_input, nonce, personalization_string]);
+        drbg
+    }
+
+    /// Set the number of requests after which a reseed is required.
+    ///
+    /// The interval is clamped to `1..=MAX_RESEED_INTERVAL`.
+    #[must_use]
+    pub fn with_reseed_interval(mut self, reseed_interval: u64) -> Self {
+        self.reseed_interval = reseed_interval.clamp(1, MAX_RESEED_INTERVAL);
+        self
+    }
+
+    /// Number of requests since the DRBG was instantiated or reseeded, plus one.
+    #[must_use]
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    /// Reseed the DRBG with fresh entropy input.
+    pub fn reseed(&mut self, entropy_input: &[u8], additional_input: &[u8]) {
+        self.update(&[entropy_input, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fill `out` with pseudorandom bytes.
+    ///
+    /// # Errors
+    /// Returns [`Error::RequestTooLong`] if `out` is longer than [`MAX_REQUEST_SIZE`] and
+    /// [`Error::ReseedRequired`] if the reseed interval is exhausted.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        if out.len() > MAX_REQUEST_SIZE {
+            return Err(Error::RequestTooLong);
+        }
+        if self.reseed_counter > self.reseed_interval {
+            return Err(Error::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for chunk in out.chunks_mut(<H as OutputSizeUser>::OutputSize::USIZE) {
+            self.v = self.hmac_v();
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// `HMAC(K, V)`.
+    fn hmac_v(&self) -> Output<H> {
+        let mut hmac = H::new_from_slice(&self.k);
+        hmac.update(&self.v);
+        hmac.finalize()
+    }
+
+    /// The `HMAC_DRBG` update function with the concatenation of `provided_data`.
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        for separator in [0x00, 0x01] {
+            let mut hmac = H::new_from_slice(&self.k);
+            hmac.update(&self.v);
+            hmac.update(&[separator]);
+            for data in provided_data {
+                hmac.update(data);
+            }
+            self.k = hmac.finalize();
+            self.v = self.hmac_v();
+
+            if provided_data.iter().all(|data| data.is_empty()) {
+                break;
+            }
+        }
+    }
+}
+
+impl<H: HmacImpl> TryRng for HmacDrbg<H> {
+    type Error = Error;
+
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        utils::next_word_via_fill(self)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        utils::next_word_via_fill(self)
+    }
+
+    /// Fill `dst` with one request per [`MAX_REQUEST_SIZE`] bytes.
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Error> {
+        for chunk in dst.chunks_mut(MAX_REQUEST_SIZE) {
+            self.generate(chunk, &[])?;
+        }
+        Ok(())
+    }
+}
+
+impl<H: HmacImpl> TryCryptoRng for HmacDrbg<H> {}
+
+impl<H: HmacImpl> SeedableRng for HmacDrbg<H> {
+    type Seed = Output<H>;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(&seed, &[], &[])
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<H: HmacImpl> Drop for HmacDrbg<H> {
+    fn drop(&mut self) {
+        self.k.zeroize();
+        self.v.zeroize();
+    }
+}
+
+impl<H: HmacImpl> fmt::Debug for HmacDrbg<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacDrbg")
+            .field("reseed_counter", &self.reseed_counter)
+            .field("reseed_interval", &self.reseed_interval)
+            .finish_non_exhaustive()
+    }
+}
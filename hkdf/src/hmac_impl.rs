@@ -1,16 +1,28 @@
+use crate::InvalidKeyLength;
 use hmac::digest::{
-    Digest, FixedOutput, KeyInit, Output, Update,
+    Digest, FixedOutput, KeyInit, Mac, Output, Update,
     block_api::{BlockSizeUser, OutputSizeUser},
 };
 use hmac::{EagerHash, Hmac, SimpleHmac};
 
 /// Trait representing a HMAC implementation.
 ///
-/// Most users should use [`Hmac`] or [`SimpleHmac`].
+/// Most users should use [`Hmac`] or [`SimpleHmac`]. Other MACs can be used
+/// as the PRF with the [`MacPrf`] adapter.
 pub trait HmacImpl: Clone + OutputSizeUser {
     /// Create new HMAC state with the given key.
     fn new_from_slice(key: &[u8]) -> Self;
 
+    /// Create new HMAC state with the given key, returning an error if the key is rejected.
+    ///
+    /// The default implementation never fails.
+    ///
+    /// # Errors
+    /// Returns [`InvalidKeyLength`] if the length of `key` is not supported.
+    fn try_new_from_slice(key: &[u8]) -> Result<Self, InvalidKeyLength> {
+        Ok(Self::new_from_slice(key))
+    }
+
     /// Update HMAC state.
     fn update(&mut self, data: &[u8]);
 
@@ -54,3 +66,42 @@ where
         self.finalize_fixed()
     }
 }
+
+/// Adapter which allows to use any [`Mac`] implementing [`KeyInit`] as the PRF of HKDF,
+/// e.g. keyed BLAKE2.
+///
+/// Unlike HMAC, such PRFs may reject keys of some lengths. [`HmacImpl::new_from_slice`]
+/// panics in this case, so salts which may be rejected should be passed to the fallible
+/// [`GenericHkdf::try_new`][crate::GenericHkdf::try_new] and
+/// [`GenericHkdfExtract::try_new`][crate::GenericHkdfExtract::try_new] constructors.
+/// Note that the PRF must accept keys of its output size, which are used as PRK.
+#[derive(Clone, Debug)]
+pub struct MacPrf<M>(M);
+
+impl<M: OutputSizeUser> OutputSizeUser for MacPrf<M> {
+    type OutputSize = M::OutputSize;
+}
+
+impl<M: Mac + KeyInit + Clone> HmacImpl for MacPrf<M> {
+    #[inline(always)]
+    fn new_from_slice(key: &[u8]) -> Self {
+        Self::try_new_from_slice(key).expect("key length is supported by the MAC")
+    }
+
+    #[inline(always)]
+    fn try_new_from_slice(key: &[u8]) -> Result<Self, InvalidKeyLength> {
+        KeyInit::new_from_slice(key)
+            .map(Self)
+            .map_err(|_| InvalidKeyLength)
+    }
+
+    #[inline(always)]
+    fn update(&mut self, data: &[u8]) {
+        Mac::update(&mut self.0, data);
+    }
+
+    #[inline(always)]
+    fn finalize(self) -> Output<Self> {
+        Mac::finalize(self.0).into_bytes()
+    }
+}
@@ -0,0 +1,397 @@
+//! HPKE `LabeledExtract`/`LabeledExpand` functions, DHKEM shared secret derivation and
+//! key schedule.
+//!
+//! See [RFC 9180 Section 4](https://www.rfc-editor.org/rfc/rfc9180#section-4) and
+//! [Section 5.1](https://www.rfc-editor.org/rfc/rfc9180#section-5.1).
+//!
+//! This module only implements the key derivation parts of HPKE, so it can be combined
+//! with any KEM and AEAD implementation:
+//!
+//! ```
+//! use hkdf::hpke::{KeySchedule, Mode, Suite};
+//! use hkdf::hmac::Hmac;
+//! use sha2::Sha256;
+//!
+//! // DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM
+//! let suite = Suite::new(0x0020, 0x0001, 0x0001);
+//! let shared_secret = [0x42; 32]; // output of the KEM
+//!
+//! let ks = KeySchedule::<Hmac<Sha256>>::new(suite, Mode::Base, &shared_secret, b"info", None)
+//!     .unwrap();
+//! let mut key = [0u8; 16];
+//! let mut base_nonce = [0u8; 12];
+//! ks.key(&mut key).unwrap();
+//! ks.base_nonce(&mut base_nonce).unwrap();
+//!
+//! let mut exported = [0u8; 32];
+//! ks.exporter().export(b"exporter context", &mut exported).unwrap();
+//! ```
+
+use crate::{GenericHkdf, GenericHkdfExtract, HmacImpl, InvalidLength, InvalidPrkLength, Secret};
+use core::fmt;
+use hmac::digest::Output;
+
+/// Version label prepended to all labels ([RFC 9180 Section 4]).
+///
+/// [RFC 9180 Section 4]: https://www.rfc-editor.org/rfc/rfc9180#section-4
+pub const HPKE_VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+impl<H: HmacImpl> GenericHkdf<H> {
+    /// The HPKE `LabeledExtract` function returning both the generated pseudorandom key
+    /// and `Hkdf` struct for expanding.
+    ///
+    /// An empty `salt` is equivalent to the default all-zero salt.
+    #[must_use]
+    pub fn labeled_extract(
+        suite_id: &[u8],
+        salt: &[u8],
+        label: &[u8],
+        ikm: &[u8],
+    ) -> (Output<H>, Self) {
+        labeled_extract_multi_ikm(suite_id, salt, label, &[ikm])
+    }
+
+    /// The HPKE `LabeledExpand` function.
+    ///
+    /// The length of `okm` is used as the `L` parameter.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is longer than `u16::MAX` or too large.
+    pub fn labeled_expand(
+        &self,
+        suite_id: &[u8],
+        label: &[u8],
+        info: &[u8],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        labeled_expand_multi_info(self, suite_id, label, &[info], okm)
+    }
+}
+
+/// HPKE mode ([RFC 9180 Section 5]).
+///
+/// [RFC 9180 Section 5]: https://www.rfc-editor.org/rfc/rfc9180#section-5
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Mode {
+    /// `mode_base`
+    Base = 0x00,
+    /// `mode_psk`
+    Psk = 0x01,
+    /// `mode_auth`
+    Auth = 0x02,
+    /// `mode_auth_psk`
+    AuthPsk = 0x03,
+}
+
+impl Mode {
+    /// Whether this mode requires a pre-shared key.
+    #[must_use]
+    pub fn uses_psk(self) -> bool {
+        matches!(self, Mode::Psk | Mode::AuthPsk)
+    }
+}
+
+/// Identifiers of the KEM, KDF and AEAD algorithms used by an HPKE context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Suite {
+    /// KEM identifier.
+    pub kem_id: u16,
+    /// KDF identifier.
+    pub kdf_id: u16,
+    /// AEAD identifier.
+    pub aead_id: u16,
+}
+
+impl Suite {
+    /// Create a new suite from the algorithm identifiers.
+    #[must_use]
+    pub const fn new(kem_id: u16, kdf_id: u16, aead_id: u16) -> Self {
+        Self {
+            kem_id,
+            kdf_id,
+            aead_id,
+        }
+    }
+
+    /// The `suite_id` used by the key schedule: `concat("HPKE", I2OSP(kem_id, 2),
+    /// I2OSP(kdf_id, 2), I2OSP(aead_id, 2))`.
+    #[must_use]
+    pub fn suite_id(&self) -> [u8; 10] {
+        let mut suite_id = [0u8; 10];
+        suite_id[..4].copy_from_slice(b"HPKE");
+        suite_id[4..6].copy_from_slice(&self.kem_id.to_be_bytes());
+        suite_id[6..8].copy_from_slice(&self.kdf_id.to_be_bytes());
+        suite_id[8..].copy_from_slice(&self.aead_id.to_be_bytes());
+        suite_id
+    }
+}
+
+/// The `suite_id` used by a KEM: `concat("KEM", I2OSP(kem_id, 2))`.
+#[must_use]
+pub fn kem_suite_id(kem_id: u16) -> [u8; 5] {
+    let [hi, lo] = kem_id.to_be_bytes();
+    [b'K', b'E', b'M', hi, lo]
+}
+
+/// The DHKEM `ExtractAndExpand` function ([RFC 9180 Section 4.1]).
+///
+/// `dh` and `kem_context` are passed as components which are concatenated, so that
+/// `Encap`/`AuthEncap` outputs can be used without copying, e.g. `&[enc, pk_r]` or
+/// `&[enc, pk_r, pk_s]` for `kem_context`. The length of `shared_secret` is used as
+/// `Nsecret`.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `shared_secret` is too large.
+///
+/// [RFC 9180 Section 4.1]: https://www.rfc-editor.org/rfc/rfc9180#section-4.1
+pub fn extract_and_expand<H: HmacImpl>(
+    kem_id: u16,
+    dh: &[&[u8]],
+    kem_context: &[&[u8]],
+    shared_secret: &mut [u8],
+) -> Result<(), InvalidLength> {
+    let suite_id = kem_suite_id(kem_id);
+    let (_, eae_prk) = labeled_extract_multi_ikm::<H>(&suite_id, &[], b"eae_prk", dh);
+    labeled_expand_multi_info(
+        &eae_prk,
+        &suite_id,
+        b"shared_secret",
+        kem_context,
+        shared_secret,
+    )
+}
+
+/// Pre-shared key and its identifier used in the [`Mode::Psk`] and [`Mode::AuthPsk`]
+/// modes.
+#[derive(Clone, Copy)]
+pub struct Psk<'a> {
+    /// Pre-shared key.
+    pub psk: &'a [u8],
+    /// Pre-shared key identifier.
+    pub psk_id: &'a [u8],
+}
+
+impl<'a> Psk<'a> {
+    /// Create a new pre-shared key.
+    #[must_use]
+    pub fn new(psk: &'a [u8], psk_id: &'a [u8]) -> Self {
+        Self { psk, psk_id }
+    }
+}
+
+impl fmt::Debug for Psk<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Psk")
+            .field("psk_id", &self.psk_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error that is returned when the PSK inputs are inconsistent with the [`Mode`].
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidPskInputs;
+
+impl fmt::Display for InvalidPskInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str("inconsistent PSK inputs for the HPKE mode")
+    }
+}
+
+impl core::error::Error for InvalidPskInputs {}
+
+/// HPKE key schedule ([RFC 9180 Section 5.1]).
+///
+/// The key schedule holds the `secret` and `key_schedule_context` values, from which
+/// the AEAD key, base nonce and exporter secret of the encryption context are derived.
+///
+/// [RFC 9180 Section 5.1]: https://www.rfc-editor.org/rfc/rfc9180#section-5.1
+#[derive(Clone)]
+pub struct KeySchedule<H: HmacImpl> {
+    suite_id: [u8; 10],
+    mode: Mode,
+    psk_id_hash: Secret<Output<H>>,
+    info_hash: Secret<Output<H>>,
+    secret: GenericHkdf<H>,
+}
+
+impl<H: HmacImpl> KeySchedule<H> {
+    /// Run the key schedule on the `shared_secret` produced by the KEM.
+    ///
+    /// # Errors
+    /// Returns [`InvalidPskInputs`] if `psk` is not provided for a PSK mode, is
+    /// provided for a non-PSK mode, or has an empty key or identifier.
+    pub fn new(
+        suite: Suite,
+        mode: Mode,
+        shared_secret: &[u8],
+        info: &[u8],
+        psk: Option<Psk<'_>>,
+    ) -> Result<Self, InvalidPskInputs> {
+        // VerifyPSKInputs
+        let (psk, psk_id) = match psk {
+            Some(psk) if mode.uses_psk() => {
+                if psk.psk.is_empty() || psk.psk_id.is_empty() {
+                    return Err(InvalidPskInputs);
+                }
+                (psk.psk, psk.psk_id)
+            }
+            None if !mode.uses_psk() => (&[][..], &[][..]),
+            _ => return Err(InvalidPskInputs),
+        };
+
+        let suite_id = suite.suite_id();
+        let (psk_id_hash, _) =
+            GenericHkdf::<H>::labeled_extract(&suite_id, &[], b"psk_id_hash", psk_id);
+        let (info_hash, _) = GenericHkdf::<H>::labeled_extract(&suite_id, &[], b"info_hash", info);
+        let (_, secret) =
+            GenericHkdf::<H>::labeled_extract(&suite_id, shared_secret, b"secret", psk);
+
+        Ok(Self {
+            suite_id,
+            mode,
+            psk_id_hash: Secret::<Output<H>>::from(psk_id_hash),
+            info_hash: Secret::<Output<H>>::from(info_hash),
+            secret,
+        })
+    }
+
+    /// HPKE mode of this key schedule.
+    #[must_use]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn expand(&self, label: &[u8], okm: &mut [u8]) -> Result<(), InvalidLength> {
+        // key_schedule_context = concat(mode, psk_id_hash, info_hash)
+        labeled_expand_multi_info(
+            &self.secret,
+            &self.suite_id,
+            label,
+            &[&[self.mode as u8], &self.psk_id_hash, &self.info_hash],
+            okm,
+        )
+    }
+
+    /// Derive the AEAD key. The length of `key` is used as `Nk`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key` is too large.
+    pub fn key(&self, key: &mut [u8]) -> Result<(), InvalidLength> {
+        self.expand(b"key", key)
+    }
+
+    /// Derive the AEAD base nonce. The length of `base_nonce` is used as `Nn`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `base_nonce` is too large.
+    pub fn base_nonce(&self, base_nonce: &mut [u8]) -> Result<(), InvalidLength> {
+        self.expand(b"base_nonce", base_nonce)
+    }
+
+    /// Derive the exporter secret.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "exporter secret size is correct")]
+    pub fn exporter_secret(&self) -> Output<H> {
+        let mut exporter_secret = Output::<H>::default();
+        self.expand(b"exp", &mut exporter_secret)
+            .expect("exporter secret size is correct");
+        exporter_secret
+    }
+
+    /// Derive the exporter secret and return the secret export interface.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "exporter secret size is correct")]
+    pub fn exporter(&self) -> Exporter<H> {
+        let exporter_secret = Secret::<Output<H>>::from(self.exporter_secret());
+        Exporter {
+            suite_id: self.suite_id,
+            hkdf: GenericHkdf::from_prk(&exporter_secret).expect("exporter secret size is correct"),
+        }
+    }
+}
+
+impl<H: HmacImpl> fmt::Debug for KeySchedule<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeySchedule")
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+/// HPKE secret export interface ([RFC 9180 Section 5.3]).
+///
+/// [RFC 9180 Section 5.3]: https://www.rfc-editor.org/rfc/rfc9180#section-5.3
+#[derive(Clone)]
+pub struct Exporter<H: HmacImpl> {
+    suite_id: [u8; 10],
+    hkdf: GenericHkdf<H>,
+}
+
+impl<H: HmacImpl> Exporter<H> {
+    /// Create the secret export interface from an existing exporter secret.
+    ///
+    /// # Errors
+    /// Returns [`InvalidPrkLength`] if `exporter_secret` is shorter than the output size
+    /// of `H`.
+    pub fn new(suite: Suite, exporter_secret: &[u8]) -> Result<Self, InvalidPrkLength> {
+        Ok(Self {
+            suite_id: suite.suite_id(),
+            hkdf: GenericHkdf::from_prk(exporter_secret)?,
+        })
+    }
+
+    /// Export a secret bound to `exporter_context`. The length of `out` is used as `L`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `out` is too large.
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<(), InvalidLength> {
+        self.hkdf
+            .labeled_expand(&self.suite_id, b"sec", exporter_context, out)
+    }
+}
+
+impl<H: HmacImpl> fmt::Debug for Exporter<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Exporter").finish_non_exhaustive()
+    }
+}
+
+/// `LabeledExtract` with the input keying material split into several components.
+fn labeled_extract_multi_ikm<H: HmacImpl>(
+    suite_id: &[u8],
+    salt: &[u8],
+    label: &[u8],
+    ikm_components: &[&[u8]],
+) -> (Output<H>, GenericHkdf<H>) {
+    // labeled_ikm = concat("HPKE-v1", suite_id, label, ikm)
+    let mut extract = GenericHkdfExtract::<H>::new(Some(salt));
+    extract.input_ikm(HPKE_VERSION_LABEL);
+    extract.input_ikm(suite_id);
+    extract.input_ikm(label);
+    for ikm in ikm_components {
+        extract.input_ikm(ikm);
+    }
+    extract.finalize()
+}
+
+/// `LabeledExpand` with `info` split into several components.
+fn labeled_expand_multi_info<H: HmacImpl>(
+    hkdf: &GenericHkdf<H>,
+    suite_id: &[u8],
+    label: &[u8],
+    info_components: &[&[u8]],
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    // labeled_info = concat(I2OSP(L, 2), "HPKE-v1", suite_id, label, info)
+    let length = u16::try_from(okm.len()).map_err(|_| InvalidLength)?;
+    let length = length.to_be_bytes();
+    hkdf.expand_with(okm, |hmac| {
+        for component in [&length, HPKE_VERSION_LABEL, suite_id, label]
+            .into_iter()
+            .chain(info_components.iter().copied())
+        {
+            hmac.update(component);
+        }
+    })
+}
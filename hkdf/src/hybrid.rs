@@ -0,0 +1,213 @@
+//! Combiner of the shared secrets of hybrid (e.g. post-quantum and traditional) key
+//! exchanges.
+//!
+//! The shared secrets and, optionally, the ciphertexts or public keys which bind them
+//! are encoded as the input key material of HKDF-Extract. With [`Encoding::Concat`], they
+//! are concatenated:
+//!
+//! ```text
+//! IKM = ss_1 || ... || ss_n || binding_1_1 || ... || binding_n_m || label
+//! ```
+//!
+//! With [`Encoding::LengthPrefixed`], every shared secret is followed by its own bindings,
+//! and the number of components and of bindings per component are encoded as well:
+//!
+//! ```text
+//! IKM = n || enc(ss_1) || m_1 || enc(binding_1_1) || ... || enc(binding_1_m_1) || ...
+//!         || enc(ss_n) || m_n || enc(binding_n_1) || ... || enc(binding_n_m_n) || enc(label)
+//! ```
+//!
+//! where `enc` prefixes its input with its length and all lengths and counts are 32-bit
+//! big-endian integers. The binding counts `m_i` are omitted if the bindings are not
+//! included.
+//!
+//! Two ready-made profiles are provided:
+//! - [`Profile::TLS_CONCAT`] concatenates the shared secrets as described in Section 3.3
+//!   of [draft-ietf-tls-hybrid-design][tls-hybrid]. The result is the TLS 1.3 handshake
+//!   secret if the `derived` secret of the early secret is used as the salt.
+//! - [`Profile::XWING_LIKE`] follows the X-Wing style combiner of Section 5.3 of
+//!   [draft-connolly-cfrg-xwing-kem][xwing], which binds the traditional ciphertext and
+//!   public key and appends the X-Wing label, but uses HKDF-Extract instead of SHA3-256.
+//!   It is therefore not interoperable with X-Wing itself, but can be used with any
+//!   HKDF-based suite, e.g. in HPKE.
+//!
+//! ```
+//! use hkdf::hybrid::{Component, Profile};
+//! use hkdf::hmac::Hmac;
+//! use sha2::Sha256;
+//!
+//! let ss_mlkem = [0x11; 32];
+//! let (ss_x25519, ct_x25519, pk_x25519) = ([0x22; 32], [0x33; 32], [0x44; 32]);
+//!
+//! let (_prk, hkdf) = Profile::XWING_LIKE
+//!     .combine::<Hmac<Sha256>>(
+//!         None,
+//!         &[
+//!             Component::new(&ss_mlkem, &[]),
+//!             Component::new(&ss_x25519, &[&ct_x25519, &pk_x25519]),
+//!         ],
+//!     )
+//!     .unwrap();
+//! let mut key = [0u8; 32];
+//! hkdf.expand(b"hybrid key", &mut key).unwrap();
+//! ```
+//!
+//! [tls-hybrid]: https://datatracker.ietf.org/doc/html/draft-ietf-tls-hybrid-design
+//! [xwing]: https://datatracker.ietf.org/doc/html/draft-connolly-cfrg-xwing-kem
+
+use crate::{GenericHkdf, GenericHkdfExtract, HmacImpl, InvalidLength};
+use core::fmt;
+use hmac::digest::Output;
+
+/// Label appended by the X-Wing combiner.
+pub const X_WING_LABEL: &[u8] = br"\.//^\";
+
+/// Encoding of the inputs of the combiner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Plain concatenation.
+    ///
+    /// The encoding is only unambiguous if all inputs have a fixed length for the
+    /// negotiated algorithms, as it is the case for the KEMs used in the ready-made
+    /// profiles.
+    Concat,
+    /// Every input is prefixed with its length as a 32-bit big-endian integer, every
+    /// shared secret is followed by its bindings and the number of components and bindings
+    /// are encoded.
+    LengthPrefixed,
+}
+
+/// Shared secret of one of the key exchanges of a hybrid key exchange.
+#[derive(Clone, Copy)]
+pub struct Component<'a> {
+    shared_secret: &'a [u8],
+    bindings: &'a [&'a [u8]],
+}
+
+impl<'a> Component<'a> {
+    /// Create a component from its shared secret and the ciphertexts or public keys
+    /// which should be bound to it.
+    ///
+    /// The bindings are ignored by profiles which do not bind them.
+    #[must_use]
+    pub const fn new(shared_secret: &'a [u8], bindings: &'a [&'a [u8]]) -> Self {
+        Self {
+            shared_secret,
+            bindings,
+        }
+    }
+}
+
+impl fmt::Debug for Component<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Component")
+            .field("bindings", &self.bindings)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parameters of the combiner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile<'a> {
+    /// Encoding of the shared secrets, bindings and label.
+    pub encoding: Encoding,
+    /// Whether the bindings of the components are included.
+    pub bind: bool,
+    /// Domain-separation label appended to the input key material.
+    pub label: &'a [u8],
+}
+
+impl Profile<'static> {
+    /// Concatenation of the shared secrets (Section 3.3 of
+    /// [draft-ietf-tls-hybrid-design][tls-hybrid]).
+    ///
+    /// [tls-hybrid]: https://datatracker.ietf.org/doc/html/draft-ietf-tls-hybrid-design
+    pub const TLS_CONCAT: Self = Self {
+        encoding: Encoding::Concat,
+        bind: false,
+        label: &[],
+    };
+
+    /// X-Wing style concatenation of the shared secrets, the bindings and [`X_WING_LABEL`].
+    ///
+    /// This is not X-Wing itself, which uses SHA3-256 instead of HKDF-Extract.
+    pub const XWING_LIKE: Self = Self {
+        encoding: Encoding::Concat,
+        bind: true,
+        label: X_WING_LABEL,
+    };
+}
+
+impl<'a> Profile<'a> {
+    /// Profile which length-prefixes all inputs and binds the components to `label`.
+    #[must_use]
+    pub const fn length_prefixed(label: &'a [u8]) -> Self {
+        Self {
+            encoding: Encoding::LengthPrefixed,
+            bind: true,
+            label,
+        }
+    }
+
+    /// Combine the shared secrets of `components` with HKDF-Extract, returning both the
+    /// generated pseudorandom key and `Hkdf` struct for expanding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if an input is longer than `u32::MAX` bytes or there are
+    /// more than `u32::MAX` components or bindings of a component with
+    /// [`Encoding::LengthPrefixed`].
+    pub fn combine<H: HmacImpl>(
+        &self,
+        salt: Option<&[u8]>,
+        components: &[Component<'_>],
+    ) -> Result<(Output<H>, GenericHkdf<H>), InvalidLength> {
+        let mut extract = GenericHkdfExtract::<H>::new(salt);
+        match self.encoding {
+            Encoding::Concat => {
+                for component in components {
+                    extract.input_ikm(component.shared_secret);
+                }
+                if self.bind {
+                    for binding in components.iter().flat_map(|c| c.bindings) {
+                        extract.input_ikm(binding);
+                    }
+                }
+                extract.input_ikm(self.label);
+            }
+            Encoding::LengthPrefixed => {
+                input_count(&mut extract, components.len())?;
+                for component in components {
+                    input_prefixed(&mut extract, component.shared_secret)?;
+                    if self.bind {
+                        input_count(&mut extract, component.bindings.len())?;
+                        for binding in component.bindings {
+                            input_prefixed(&mut extract, binding)?;
+                        }
+                    }
+                }
+                input_prefixed(&mut extract, self.label)?;
+            }
+        }
+        Ok(extract.finalize())
+    }
+}
+
+/// Inputs `count` as a 32-bit big-endian integer.
+fn input_count<H: HmacImpl>(
+    extract: &mut GenericHkdfExtract<H>,
+    count: usize,
+) -> Result<(), InvalidLength> {
+    let count = u32::try_from(count).map_err(|_| InvalidLength)?;
+    extract.input_ikm(&count.to_be_bytes());
+    Ok(())
+}
+
+/// Inputs `data` prefixed with its length.
+fn input_prefixed<H: HmacImpl>(
+    extract: &mut GenericHkdfExtract<H>,
+    data: &[u8],
+) -> Result<(), InvalidLength> {
+    input_count(extract, data.len())?;
+    extract.input_ikm(data);
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Hierarchical key derivation from paths of components.
+//!
+//! A [`KeyTree`] is a node of a tree of secrets which are all derived from a single root
+//! secret. The secret of a child node is derived from the secret of its parent with one
+//! HKDF-Expand step, using the length-prefixed encoding of
+//! [`expand_framed_info`][crate::GenericHkdf::expand_framed_info] for the component:
+//!
+//! ```text
+//! child = HKDF-Expand(node, u32be(18) || "hkdf key tree node" || u32be(len(component)) || component, HashLen)
+//! key   = HKDF-Expand(node, u32be(17) || "hkdf key tree key" || u32be(len(info)) || info, L)
+//! ```
+//!
+//! The encoding is injective, so the paths `["ab", "c"]` and `["a", "bc"]` address
+//! different nodes, and node secrets are never output as keys. The secret of a node can
+//! be exported with [`KeyTree::as_bytes`] to delegate its subtree to another party, which
+//! can derive the keys of the subtree but not of its parent or siblings:
+//!
+//! ```
+//! use hkdf::key_tree::KeyTree;
+//! use sha2::Sha256;
+//!
+//! let root = KeyTree::<Sha256>::new(Some(b"salt"), b"root secret");
+//! let mut key = [0u8; 32];
+//! root.derive(&[b"tenant", b"42", b"db", b"enc"], &mut key).unwrap();
+//!
+//! // delegate the subtree of tenant 42
+//! let tenant = root.subtree(&[b"tenant", b"42"]).unwrap();
+//! let delegated = KeyTree::<Sha256>::from_secret(*tenant.as_bytes());
+//! let mut delegated_key = [0u8; 32];
+//! delegated.derive(&[b"db", b"enc"], &mut delegated_key).unwrap();
+//! assert_eq!(key, delegated_key);
+//! ```
+//!
+//! With the `alloc` feature, [`KeyTreeCache`] caches the intermediate nodes of derived
+//! paths, so that deriving keys of siblings needs one HKDF-Expand step per uncached
+//! component.
+
+use crate::{Hkdf, InvalidLength};
+use core::fmt;
+use hmac::{EagerHash, Hmac, digest::Output};
+
+#[cfg(feature = "alloc")]
+use {
+    crate::framed_len,
+    alloc::{collections::BTreeMap, vec::Vec},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Label of the derivation of child node secrets.
+const NODE_LABEL: &[u8] = b"hkdf key tree node";
+/// Label of the derivation of keys from a node secret.
+const KEY_LABEL: &[u8] = b"hkdf key tree key";
+
+/// A node of a key tree, i.e. the root of a subtree.
+#[derive(Clone)]
+pub struct KeyTree<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+}
+
+impl<D: EagerHash> KeyTree<D> {
+    /// Create the root of a key tree with HKDF-Extract from the root secret `ikm`.
+    #[must_use]
+    pub fn new(salt: Option<&[u8]>, ikm: &[u8]) -> Self {
+        let (prk, _) = Hkdf::<D>::extract(salt, ikm);
+        Self::from_secret(Output::<Hmac<D>>::clone(&prk))
+    }
+
+    /// Create a node from its secret, e.g. the secret of a delegated subtree exported
+    /// with [`as_bytes`][Self::as_bytes].
+    #[must_use]
+    pub fn from_secret(secret: Output<Hmac<D>>) -> Self {
+        Self { secret }
+    }
+
+    /// Get the raw secret of the node.
+    ///
+    /// The secret allows deriving all keys of the subtree rooted at this node.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    fn hkdf(&self) -> Hkdf<D> {
+        Hkdf::<D>::from_prk(&self.secret).expect("secret has the hash output size")
+    }
+
+    /// Derive the child node `component`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `component` is longer than `u32::MAX` bytes.
+    pub fn child(&self, component: &[u8]) -> Result<Self, InvalidLength> {
+        let mut secret = Output::<Hmac<D>>::default();
+        self.hkdf()
+            .expand_framed_info(&[NODE_LABEL, component], &mut secret)?;
+        Ok(Self::from_secret(secret))
+    }
+
+    /// Derive the descendant node at `path`, relative to this node.
+    ///
+    /// The node itself is returned for an empty path.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if a component is longer than `u32::MAX` bytes.
+    pub fn subtree(&self, path: &[&[u8]]) -> Result<Self, InvalidLength> {
+        path.iter()
+            .try_fold(self.clone(), |node, component| node.child(component))
+    }
+
+    /// Derive key material for `info` from the secret of this node.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large or `info` is longer than
+    /// `u32::MAX` bytes.
+    pub fn expand(&self, info: &[u8], okm: &mut [u8]) -> Result<(), InvalidLength> {
+        self.hkdf().expand_framed_info(&[KEY_LABEL, info], okm)
+    }
+
+    /// Derive the key of the node at `path` with an empty `info`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large or a component is longer than
+    /// `u32::MAX` bytes.
+    pub fn derive(&self, path: &[&[u8]], okm: &mut [u8]) -> Result<(), InvalidLength> {
+        self.subtree(path)?.expand(&[], okm)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for KeyTree<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for KeyTree<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyTree").finish_non_exhaustive()
+    }
+}
+
+/// Cache of the nodes of a [`KeyTree`].
+///
+/// Every node derived through the cache is kept, including the intermediate nodes of its
+/// path, until it is removed with [`remove`][Self::remove] or [`clear`][Self::clear].
+/// Cached secrets stay in memory, so subtrees which are no longer used should be removed.
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+pub struct KeyTreeCache<D: EagerHash> {
+    root: KeyTree<D>,
+    /// Nodes indexed by the length-prefixed encoding of their paths.
+    nodes: BTreeMap<Vec<u8>, KeyTree<D>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<D: EagerHash> KeyTreeCache<D> {
+    /// Create an empty cache of the subtree rooted at `root`.
+    #[must_use]
+    pub fn new(root: KeyTree<D>) -> Self {
+        Self {
+            root,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Root of the cached subtree.
+    #[must_use]
+    pub fn root(&self) -> &KeyTree<D> {
+        &self.root
+    }
+
+    /// Number of cached nodes, not counting the root.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if no nodes are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get the node at `path`, deriving and caching it and its uncached ancestors.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if a component is longer than `u32::MAX` bytes.
+    pub fn subtree(&mut self, path: &[&[u8]]) -> Result<&KeyTree<D>, InvalidLength> {
+        if path.is_empty() {
+            return Ok(&self.root);
+        }
+        let (encoded, ends) = encode_path(path)?;
+
+        // Continue from the deepest cached node on the path
+        let (mut node, start) = ends
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, &end)| Some((self.nodes.get(&encoded[..end])?.clone(), i + 1)))
+            .unwrap_or_else(|| (self.root.clone(), 0));
+        for i in start..path.len() {
+            node = node.child(path[i])?;
+            if i + 1 < path.len() {
+                self.nodes.insert(encoded[..ends[i]].to_vec(), node.clone());
+            }
+        }
+        Ok(self.nodes.entry(encoded).or_insert(node))
+    }
+
+    /// Derive the key of the node at `path` with an empty `info`, caching the node.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large or a component is longer than
+    /// `u32::MAX` bytes.
+    pub fn derive(&mut self, path: &[&[u8]], okm: &mut [u8]) -> Result<(), InvalidLength> {
+        self.subtree(path)?.expand(&[], okm)
+    }
+
+    /// Remove the node at `path` and all of its cached descendants.
+    ///
+    /// Nodes can still be derived again after being removed, the cache only limits how
+    /// long their secrets are kept in memory.
+    pub fn remove(&mut self, path: &[&[u8]]) {
+        // Nodes with paths which can not be encoded can not be derived and are not cached
+        if let Ok((encoded, _)) = encode_path(path) {
+            // The encoding of a path is a prefix of the encodings of its descendants
+            self.nodes.retain(|key, _| !key.starts_with(&encoded));
+        }
+    }
+
+    /// Remove all cached nodes.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<D: EagerHash> fmt::Debug for KeyTreeCache<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyTreeCache")
+            .field("len", &self.nodes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Length-prefixed encoding of `path` and the end of the encoding of every component.
+#[cfg(feature = "alloc")]
+fn encode_path(path: &[&[u8]]) -> Result<(Vec<u8>, Vec<usize>), InvalidLength> {
+    let mut encoded = Vec::new();
+    let mut ends = Vec::with_capacity(path.len());
+    for component in path {
+        u32::try_from(component.len()).map_err(|_| InvalidLength)?;
+        encoded.extend_from_slice(&framed_len(component));
+        encoded.extend_from_slice(component);
+        ends.push(encoded.len());
+    }
+    Ok((encoded, ends))
+}
@@ -6,21 +6,87 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use hmac::{
     Hmac, SimpleHmac,
-    digest::{Output, OutputSizeUser, array::typenum::Unsigned},
+    digest::{
+        Output, OutputSizeUser,
+        array::{Array, typenum::Unsigned},
+        common::KeySizeUser,
+    },
 };
 
+#[cfg(any(feature = "cose", feature = "edhoc"))]
+#[allow(dead_code, reason = "not every data item is used by every feature")]
+mod cbor;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(feature = "edhoc")]
+pub mod edhoc;
 mod errors;
+mod expand_reader;
+mod framed_info;
+#[cfg(feature = "hmac-drbg")]
+pub mod hmac_drbg;
 mod hmac_impl;
+#[cfg(feature = "hpke")]
+pub mod hpke;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+#[cfg(feature = "key-tree")]
+pub mod key_tree;
+#[cfg(feature = "matter")]
+pub mod matter;
+#[cfg(feature = "mls")]
+pub mod mls;
+#[cfg(feature = "noise")]
+pub mod noise;
+#[cfg(feature = "oscore")]
+pub mod oscore;
+#[cfg(feature = "quic")]
+pub mod quic;
+#[cfg(feature = "signal")]
+pub mod signal;
+#[cfg(feature = "tls13")]
+pub mod tls13;
 
-pub use errors::{InvalidLength, InvalidPrkLength};
+pub use errors::{InvalidKeyLength, InvalidLength, InvalidPrkLength};
+pub use expand_reader::ExpandReader;
+pub use framed_info::{FramedInfo, FramedInfoBuilder};
 pub use hmac;
-pub use hmac_impl::HmacImpl;
+pub use hmac_impl::{HmacImpl, MacPrf};
+
+use framed_info::framed_len;
+
+#[cfg(feature = "blake2")]
+pub use blake2;
+
+#[cfg(feature = "derive")]
+pub use kdf_derive::DeriveKeys;
 
 #[cfg(feature = "kdf")]
 pub use kdf::{self, Kdf};
 
+#[cfg(feature = "hmac-drbg")]
+pub use rand_core;
+
+#[cfg(feature = "zeroize")]
+use hmac::{
+    EagerHash,
+    digest::zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing},
+};
+
+/// Internal secret value, wrapped in [`Zeroizing`] if the `zeroize` feature is enabled.
+#[cfg(feature = "zeroize")]
+#[allow(dead_code, reason = "only used by protocol modules")]
+type Secret<T> = Zeroizing<T>;
+/// Internal secret value, wrapped in `Zeroizing` if the `zeroize` feature is enabled.
+#[cfg(not(feature = "zeroize"))]
+#[allow(dead_code, reason = "only used by protocol modules")]
+type Secret<T> = T;
+
 /// [`GenericHkdfExtract`] variant which uses [`Hmac`] for the underlying HMAC implementation.
 pub type HkdfExtract<H> = GenericHkdfExtract<Hmac<H>>;
 /// [`GenericHkdf`] variant which uses [`Hmac`] for the underlying HMAC implementation.
@@ -31,6 +97,20 @@ pub type SimpleHkdfExtract<H> = GenericHkdfExtract<SimpleHmac<H>>;
 /// [`GenericHkdf`] variant which uses [`SimpleHmac`] for the underlying HMAC implementation.
 pub type SimpleHkdf<H> = GenericHkdf<SimpleHmac<H>>;
 
+/// [`GenericHkdfExtract`] variant which uses keyed BLAKE2b-512 as the PRF.
+#[cfg(feature = "blake2")]
+pub type Blake2bHkdfExtract = GenericHkdfExtract<MacPrf<blake2::Blake2bMac512>>;
+/// [`GenericHkdf`] variant which uses keyed BLAKE2b-512 as the PRF.
+#[cfg(feature = "blake2")]
+pub type Blake2bHkdf = GenericHkdf<MacPrf<blake2::Blake2bMac512>>;
+
+/// [`GenericHkdfExtract`] variant which uses keyed BLAKE2s-256 as the PRF.
+#[cfg(feature = "blake2")]
+pub type Blake2sHkdfExtract = GenericHkdfExtract<MacPrf<blake2::Blake2sMac256>>;
+/// [`GenericHkdf`] variant which uses keyed BLAKE2s-256 as the PRF.
+#[cfg(feature = "blake2")]
+pub type Blake2sHkdf = GenericHkdf<MacPrf<blake2::Blake2sMac256>>;
+
 /// Structure representing the streaming context of an HKDF-Extract operation.
 ///
 /// This type is generic over HMAC implementation. Most users should use
@@ -50,6 +130,18 @@ impl<H: HmacImpl> GenericHkdfExtract<H> {
         Self { hmac }
     }
 
+    /// Initiates the HKDF-Extract context with the given optional salt, returning an error
+    /// if the salt is rejected by the underlying PRF.
+    ///
+    /// # Errors
+    /// Returns [`InvalidKeyLength`] if the length of the salt is not supported by `H`.
+    pub fn try_new(salt: Option<&[u8]>) -> Result<Self, InvalidKeyLength> {
+        let default_salt = Output::<H>::default();
+        let salt = salt.unwrap_or(&default_salt);
+        let hmac = H::try_new_from_slice(salt)?;
+        Ok(Self { hmac })
+    }
+
     /// Feeds in additional input key material to the HKDF-Extract context
     pub fn input_ikm(&mut self, ikm: &[u8]) {
         self.hmac.update(ikm);
@@ -63,6 +155,14 @@ impl<H: HmacImpl> GenericHkdfExtract<H> {
         let hkdf = GenericHkdf::<H>::from_prk(&prk).expect("PRK size is correct");
         (prk, hkdf)
     }
+
+    /// Variant of [`finalize`][Self::finalize] returning the pseudorandom key wrapped in
+    /// [`Zeroizing`].
+    #[cfg(feature = "zeroize")]
+    pub fn finalize_zeroizing(self) -> (Zeroizing<Output<H>>, GenericHkdf<H>) {
+        let (prk, hkdf) = self.finalize();
+        (Zeroizing::new(prk), hkdf)
+    }
 }
 
 #[cfg(feature = "kdf")]
@@ -96,18 +196,28 @@ impl<H: HmacImpl> GenericHkdf<H> {
         hkdf
     }
 
+    /// Fallible variant of [`new`][Hkdf::new] for PRFs which may reject the salt.
+    ///
+    /// # Errors
+    /// Returns [`InvalidKeyLength`] if the length of the salt is not supported by `H`.
+    pub fn try_new(salt: Option<&[u8]>, ikm: &[u8]) -> Result<Self, InvalidKeyLength> {
+        let (_, hkdf) = Self::try_extract(salt, ikm)?;
+        Ok(hkdf)
+    }
+
     /// Create `Hkdf` from an already cryptographically strong pseudorandom key
     /// as per section 3.3 from RFC5869.
     ///
     /// # Errors
-    /// Returns [`InvalidPrkLength`] if `prk` is shorter than the output size of `H`.
+    /// Returns [`InvalidPrkLength`] if `prk` is shorter than the output size of `H`
+    /// or is rejected by `H`.
     pub fn from_prk(prk: &[u8]) -> Result<Self, InvalidPrkLength> {
         // section 2.3 specifies that `prk` must be "at least HashLen octets"
         let hash_len = <H as OutputSizeUser>::OutputSize::to_usize();
         if prk.len() < hash_len {
             return Err(InvalidPrkLength);
         }
-        let hmac = H::new_from_slice(prk);
+        let hmac = H::try_new_from_slice(prk).map_err(|_| InvalidPrkLength)?;
         Ok(Self { hmac })
     }
 
@@ -120,17 +230,93 @@ impl<H: HmacImpl> GenericHkdf<H> {
         extract_ctx.finalize()
     }
 
+    /// Variant of [`extract`][Hkdf::extract] returning the pseudorandom key wrapped in
+    /// [`Zeroizing`].
+    #[cfg(feature = "zeroize")]
+    #[must_use]
+    pub fn extract_zeroizing(salt: Option<&[u8]>, ikm: &[u8]) -> (Zeroizing<Output<H>>, Self) {
+        let mut extract_ctx = GenericHkdfExtract::<H>::new(salt);
+        extract_ctx.input_ikm(ikm);
+        extract_ctx.finalize_zeroizing()
+    }
+
+    /// Fallible variant of [`extract`][Hkdf::extract] for PRFs which may reject the salt.
+    ///
+    /// # Errors
+    /// Returns [`InvalidKeyLength`] if the length of the salt is not supported by `H`.
+    pub fn try_extract(
+        salt: Option<&[u8]>,
+        ikm: &[u8],
+    ) -> Result<(Output<H>, Self), InvalidKeyLength> {
+        let mut extract_ctx = GenericHkdfExtract::<H>::try_new(salt)?;
+        extract_ctx.input_ikm(ikm);
+        Ok(extract_ctx.finalize())
+    }
+
     /// The RFC5869 HKDF-Expand operation. This is equivalent to calling
     /// [`expand`][Hkdf::extract] with the `info` argument set equal to the
     /// concatenation of all the elements of `info_components`.
     ///
+    /// The concatenation is ambiguous, e.g. `["ab", "c"]` and `["a", "bc"]` produce the same
+    /// `info`. Use [`expand_framed_info`][Hkdf::expand_framed_info] if the components
+    /// have variable lengths.
+    ///
     /// # Errors
     /// Returns [`InvalidLength`] in the event `okm` is too large.
-    #[allow(clippy::missing_panics_doc, reason = "expect should not fail")]
     pub fn expand_multi_info(
         &self,
         info_components: &[&[u8]],
         okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        self.expand_with(okm, |hmac| {
+            // Feed in the info components in sequence. This is equivalent to feeding in the
+            // concatenation of all the info components
+            for info in info_components {
+                hmac.update(info);
+            }
+        })
+    }
+
+    /// The RFC5869 HKDF-Expand operation with the `info` argument set to the injective
+    /// encoding of `info_components`, in which every component is prefixed with its length
+    /// as a big-endian `u32`:
+    ///
+    /// ```text
+    /// info = u32be(len(c_1)) || c_1 || ... || u32be(len(c_n)) || c_n
+    /// ```
+    ///
+    /// Different sequences of components, including sequences with different numbers of
+    /// components, never produce the same `info`. [`FramedInfo`] provides a typed builder
+    /// for the common label and context case.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] in the event `okm` is too large or a component is longer
+    /// than `u32::MAX` bytes.
+    pub fn expand_framed_info(
+        &self,
+        info_components: &[&[u8]],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        if info_components
+            .iter()
+            .any(|info| u32::try_from(info.len()).is_err())
+        {
+            return Err(InvalidLength);
+        }
+        self.expand_with(okm, |hmac| {
+            for info in info_components {
+                hmac.update(&framed_len(info));
+                hmac.update(info);
+            }
+        })
+    }
+
+    /// HKDF-Expand with `input_info` feeding the `info` into the HMAC of every block.
+    #[allow(clippy::missing_panics_doc, reason = "expect should not fail")]
+    fn expand_with(
+        &self,
+        okm: &mut [u8],
+        input_info: impl Fn(&mut H),
     ) -> Result<(), InvalidLength> {
         let mut prev: Option<Output<H>> = None;
 
@@ -146,11 +332,7 @@ impl<H: HmacImpl> GenericHkdf<H> {
                 hmac.update(prev);
             };
 
-            // Feed in the info components in sequence. This is equivalent to feeding in the
-            // concatenation of all the info components
-            for info in info_components {
-                hmac.update(info);
-            }
+            input_info(&mut hmac);
 
             hmac.update(&[u8::try_from(block_n).expect("should convert") + 1]);
 
@@ -162,6 +344,9 @@ impl<H: HmacImpl> GenericHkdf<H> {
             prev = Some(output);
         }
 
+        #[cfg(feature = "zeroize")]
+        prev.zeroize();
+
         Ok(())
     }
 
@@ -174,4 +359,115 @@ impl<H: HmacImpl> GenericHkdf<H> {
     pub fn expand(&self, info: &[u8], okm: &mut [u8]) -> Result<(), InvalidLength> {
         self.expand_multi_info(&[info], okm)
     }
+
+    /// The RFC5869 HKDF-Expand operation returning a key of type `K`.
+    ///
+    /// The key size is checked against the `255 * HashLen` limit at compile time:
+    ///
+    /// ```compile_fail
+    /// use hkdf::{Hkdf, hmac::digest::{common::KeySizeUser, consts::U8192}};
+    /// use sha2::Sha256;
+    ///
+    /// struct TooLong;
+    /// impl KeySizeUser for TooLong {
+    ///     type KeySize = U8192;
+    /// }
+    ///
+    /// let hk = Hkdf::<Sha256>::new(None, b"ikm");
+    /// let key = hk.expand_key::<TooLong>(b"info");
+    /// ```
+    #[must_use]
+    #[allow(
+        clippy::missing_panics_doc,
+        reason = "length is checked at compile time"
+    )]
+    pub fn expand_key<K: KeySizeUser>(&self, info: &[u8]) -> Array<u8, K::KeySize> {
+        let mut okm = Array::<u8, K::KeySize>::default();
+        self.expand_key_into::<K>(info, &mut okm);
+        okm
+    }
+
+    /// Variant of [`expand_key`][Hkdf::expand_key] returning the key wrapped in
+    /// [`Zeroizing`].
+    #[cfg(feature = "zeroize")]
+    #[must_use]
+    pub fn expand_key_zeroizing<K: KeySizeUser>(
+        &self,
+        info: &[u8],
+    ) -> Zeroizing<Array<u8, K::KeySize>> {
+        let mut okm = Zeroizing::new(Array::<u8, K::KeySize>::default());
+        self.expand_key_into::<K>(info, &mut okm);
+        okm
+    }
+
+    fn expand_key_into<K: KeySizeUser>(&self, info: &[u8], okm: &mut Array<u8, K::KeySize>) {
+        const {
+            assert!(
+                K::KeySize::USIZE <= 255 * <H as OutputSizeUser>::OutputSize::USIZE,
+                "key size exceeds the maximum HKDF-Expand output length",
+            );
+        }
+        self.expand(info, okm)
+            .expect("length is checked at compile time");
+    }
+
+    /// The RFC5869 HKDF-Expand operation returning an array of `N` bytes.
+    ///
+    /// `N` is checked against the `255 * HashLen` limit at compile time:
+    ///
+    /// ```compile_fail
+    /// use hkdf::Hkdf;
+    /// use sha2::Sha256;
+    ///
+    /// let hk = Hkdf::<Sha256>::new(None, b"ikm");
+    /// let okm = hk.expand_array::<8161>(b"info");
+    /// ```
+    #[must_use]
+    #[allow(
+        clippy::missing_panics_doc,
+        reason = "length is checked at compile time"
+    )]
+    pub fn expand_array<const N: usize>(&self, info: &[u8]) -> [u8; N] {
+        let mut okm = [0u8; N];
+        self.expand_array_into(info, &mut okm);
+        okm
+    }
+
+    /// Variant of [`expand_array`][Hkdf::expand_array] returning the array wrapped in
+    /// [`Zeroizing`].
+    #[cfg(feature = "zeroize")]
+    #[must_use]
+    pub fn expand_array_zeroizing<const N: usize>(&self, info: &[u8]) -> Zeroizing<[u8; N]> {
+        let mut okm = Zeroizing::new([0u8; N]);
+        self.expand_array_into(info, &mut okm);
+        okm
+    }
+
+    fn expand_array_into<const N: usize>(&self, info: &[u8], okm: &mut [u8; N]) {
+        const {
+            assert!(
+                N <= 255 * <H as OutputSizeUser>::OutputSize::USIZE,
+                "array length exceeds the maximum HKDF-Expand output length",
+            );
+        }
+        self.expand(info, okm)
+            .expect("length is checked at compile time");
+    }
+
+    /// The RFC5869 HKDF-Expand operation returning a reader over the output key material.
+    ///
+    /// The reader produces the same bytes as [`expand`][Hkdf::expand] regardless of how
+    /// the reads are split and allows reading at most `255 * HashLen` bytes.
+    #[must_use]
+    pub fn expand_reader<'a>(&self, info: &'a [u8]) -> ExpandReader<'a, H> {
+        ExpandReader::new(self, info)
+    }
 }
+
+// The HMAC state is wiped on drop if the core of the underlying hash function wipes itself.
+// `SimpleHmac` keeps the padded key in a plain array and thus can not provide this guarantee.
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> ZeroizeOnDrop for GenericHkdfExtract<Hmac<D>> where D::Core: ZeroizeOnDrop {}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> ZeroizeOnDrop for GenericHkdf<Hmac<D>> where D::Core: ZeroizeOnDrop {}
@@ -0,0 +1,250 @@
+//! Matter session, resumption and group key derivations.
+//!
+//! See the Matter Core Specification, Sections 3.8 (`Crypto_KDF`), 4.3.2.2 (compressed
+//! fabric identifier), 4.14 (PASE and CASE) and 4.17 (group keys).
+//!
+//! All derivations use `Crypto_KDF`, i.e. HKDF-SHA256, with the fixed `info` strings of
+//! the specification. The key agreement, SPAKE2+ and the transcript hashes are computed
+//! by the caller:
+//!
+//! ```
+//! use hkdf::matter::{SessionKeys, compressed_fabric_id, group_session_id, operational_group_key};
+//!
+//! // CASE session establishment
+//! let (shared_secret, ipk, transcript_hash) = ([0x01; 32], [0x02; 16], [0x03; 32]);
+//! let keys = SessionKeys::case(&shared_secret, &ipk, &transcript_hash);
+//!
+//! // group key set of a fabric
+//! let root_public_key = [0x04; 64];
+//! let fabric_id = compressed_fabric_id(&root_public_key, 0x2906_C908_D115_D362);
+//! let operational_key = operational_group_key(&[0x05; 16], &fabric_id);
+//! let session_id = group_session_id(&operational_key);
+//! ```
+
+use crate::{Hkdf, Secret};
+use core::fmt;
+use sha2::Sha256;
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Size of the symmetric keys (`CRYPTO_SYMMETRIC_KEY_LENGTH_BYTES`).
+pub const SYMMETRIC_KEY_SIZE: usize = 16;
+/// Size of the output of `Crypto_Hash` (`CRYPTO_HASH_LEN_BYTES`).
+pub const HASH_SIZE: usize = 32;
+/// Size of the random values of `Sigma1` and `Sigma2`.
+pub const RANDOM_SIZE: usize = 32;
+/// Size of a resumption ID.
+pub const RESUMPTION_ID_SIZE: usize = 16;
+/// Size of an uncompressed P-256 public key.
+pub const PUBLIC_KEY_SIZE: usize = 65;
+
+/// Symmetric key, e.g. the IPK or an epoch key.
+pub type SymmetricKey = [u8; SYMMETRIC_KEY_SIZE];
+
+/// `Crypto_KDF(secret, salt, info, N * 8)`.
+fn kdf<const N: usize>(secret: &[u8], salt: &[u8], info: &[u8]) -> [u8; N] {
+    let mut okm = [0u8; N];
+    Hkdf::<Sha256>::new(Some(salt), secret)
+        .expand(info, &mut okm)
+        .expect("output length is valid");
+    okm
+}
+
+/// Concatenate `parts` into a salt of `N` bytes.
+fn salt<const N: usize>(parts: &[&[u8]]) -> Secret<[u8; N]> {
+    let mut salt = Secret::<[u8; N]>::from([0u8; N]);
+    let mut pos = 0;
+    for part in parts {
+        salt[pos..pos + part.len()].copy_from_slice(part);
+        pos += part.len();
+    }
+    debug_assert_eq!(pos, N);
+    salt
+}
+
+/// Session keys of a secure unicast session.
+#[derive(Clone)]
+#[allow(
+    missing_copy_implementations,
+    reason = "keys are wiped on drop with zeroize"
+)]
+pub struct SessionKeys {
+    /// `I2RKey`, protecting messages from the initiator to the responder.
+    pub i2r_key: SymmetricKey,
+    /// `R2IKey`, protecting messages from the responder to the initiator.
+    pub r2i_key: SymmetricKey,
+    /// `AttestationChallenge`, used during device attestation.
+    pub attestation_challenge: SymmetricKey,
+}
+
+impl SessionKeys {
+    fn derive(secret: &[u8], salt: &[u8], info: &[u8]) -> Self {
+        let okm = Secret::<[u8; 3 * SYMMETRIC_KEY_SIZE]>::from(kdf(secret, salt, info));
+        let mut keys = Self {
+            i2r_key: [0; SYMMETRIC_KEY_SIZE],
+            r2i_key: [0; SYMMETRIC_KEY_SIZE],
+            attestation_challenge: [0; SYMMETRIC_KEY_SIZE],
+        };
+        keys.i2r_key.copy_from_slice(&okm[..SYMMETRIC_KEY_SIZE]);
+        keys.r2i_key
+            .copy_from_slice(&okm[SYMMETRIC_KEY_SIZE..2 * SYMMETRIC_KEY_SIZE]);
+        keys.attestation_challenge
+            .copy_from_slice(&okm[2 * SYMMETRIC_KEY_SIZE..]);
+        keys
+    }
+
+    /// Session keys of a PASE session, derived from the SPAKE2+ shared key `Ke`.
+    #[must_use]
+    pub fn pase(ke: &[u8]) -> Self {
+        Self::derive(ke, &[], b"SessionKeys")
+    }
+
+    /// Session keys of a CASE session.
+    ///
+    /// `transcript_hash` is `Crypto_Hash(Sigma1 || Sigma2 || Sigma3)`.
+    #[must_use]
+    pub fn case(
+        shared_secret: &[u8],
+        ipk: &SymmetricKey,
+        transcript_hash: &[u8; HASH_SIZE],
+    ) -> Self {
+        let salt = salt::<{ SYMMETRIC_KEY_SIZE + HASH_SIZE }>(&[ipk, transcript_hash]);
+        Self::derive(shared_secret, salt.as_slice(), b"SessionKeys")
+    }
+
+    /// Session keys of a resumed CASE session.
+    ///
+    /// `initiator_random` is the `initiatorRandom` of `Sigma1` and `resumption_id` is the
+    /// new `resumptionID` of `Sigma2_Resume`.
+    #[must_use]
+    pub fn case_resumption(
+        shared_secret: &[u8],
+        initiator_random: &[u8; RANDOM_SIZE],
+        resumption_id: &[u8; RESUMPTION_ID_SIZE],
+    ) -> Self {
+        let salt = salt::<{ RANDOM_SIZE + RESUMPTION_ID_SIZE }>(&[initiator_random, resumption_id]);
+        Self::derive(shared_secret, salt.as_slice(), b"SessionResumptionKeys")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SessionKeys {
+    fn drop(&mut self) {
+        self.i2r_key.zeroize();
+        self.r2i_key.zeroize();
+        self.attestation_challenge.zeroize();
+    }
+}
+
+impl fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionKeys").finish_non_exhaustive()
+    }
+}
+
+/// SPAKE2+ confirmation keys `KcA || KcB`, derived from the shared key `Ka`.
+#[must_use]
+pub fn pase_confirmation_keys(ka: &[u8]) -> (SymmetricKey, SymmetricKey) {
+    let okm = Secret::<[u8; 2 * SYMMETRIC_KEY_SIZE]>::from(kdf(ka, &[], b"ConfirmationKeys"));
+    let mut kca = [0; SYMMETRIC_KEY_SIZE];
+    let mut kcb = [0; SYMMETRIC_KEY_SIZE];
+    kca.copy_from_slice(&okm[..SYMMETRIC_KEY_SIZE]);
+    kcb.copy_from_slice(&okm[SYMMETRIC_KEY_SIZE..]);
+    (kca, kcb)
+}
+
+/// CASE key `S2K` protecting `TBEData2` of `Sigma2`.
+///
+/// `sigma1_hash` is `Crypto_Hash(Sigma1)`.
+#[must_use]
+pub fn sigma2_key(
+    shared_secret: &[u8],
+    ipk: &SymmetricKey,
+    responder_random: &[u8; RANDOM_SIZE],
+    responder_eph_pub_key: &[u8; PUBLIC_KEY_SIZE],
+    sigma1_hash: &[u8; HASH_SIZE],
+) -> SymmetricKey {
+    let salt = salt::<{ SYMMETRIC_KEY_SIZE + RANDOM_SIZE + PUBLIC_KEY_SIZE + HASH_SIZE }>(&[
+        ipk,
+        responder_random,
+        responder_eph_pub_key,
+        sigma1_hash,
+    ]);
+    kdf(shared_secret, salt.as_slice(), b"Sigma2")
+}
+
+/// CASE key `S3K` protecting `TBEData3` of `Sigma3`.
+///
+/// `transcript_hash` is `Crypto_Hash(Sigma1 || Sigma2)`.
+#[must_use]
+pub fn sigma3_key(
+    shared_secret: &[u8],
+    ipk: &SymmetricKey,
+    transcript_hash: &[u8; HASH_SIZE],
+) -> SymmetricKey {
+    let salt = salt::<{ SYMMETRIC_KEY_SIZE + HASH_SIZE }>(&[ipk, transcript_hash]);
+    kdf(shared_secret, salt.as_slice(), b"Sigma3")
+}
+
+/// CASE resumption key `S1RK` used for the `initiatorResumeMIC` of `Sigma1`.
+#[must_use]
+pub fn sigma1_resume_key(
+    shared_secret: &[u8],
+    initiator_random: &[u8; RANDOM_SIZE],
+    resumption_id: &[u8; RESUMPTION_ID_SIZE],
+) -> SymmetricKey {
+    let salt = salt::<{ RANDOM_SIZE + RESUMPTION_ID_SIZE }>(&[initiator_random, resumption_id]);
+    kdf(shared_secret, salt.as_slice(), b"Sigma1_Resume")
+}
+
+/// CASE resumption key `S2RK` used for the `sigma2ResumeMIC` of `Sigma2_Resume`.
+///
+/// `resumption_id` is the new `resumptionID` of `Sigma2_Resume`.
+#[must_use]
+pub fn sigma2_resume_key(
+    shared_secret: &[u8],
+    initiator_random: &[u8; RANDOM_SIZE],
+    resumption_id: &[u8; RESUMPTION_ID_SIZE],
+) -> SymmetricKey {
+    let salt = salt::<{ RANDOM_SIZE + RESUMPTION_ID_SIZE }>(&[initiator_random, resumption_id]);
+    kdf(shared_secret, salt.as_slice(), b"Sigma2_Resume")
+}
+
+/// Compressed fabric identifier of a fabric.
+///
+/// `root_public_key` is the public key of the root CA without the leading `0x04` byte of
+/// the uncompressed encoding.
+#[must_use]
+pub fn compressed_fabric_id(
+    root_public_key: &[u8; PUBLIC_KEY_SIZE - 1],
+    fabric_id: u64,
+) -> [u8; 8] {
+    kdf(
+        root_public_key,
+        &fabric_id.to_be_bytes(),
+        b"CompressedFabric",
+    )
+}
+
+/// Operational group key derived from an epoch key, e.g. the IPK from the IPK epoch key.
+#[must_use]
+pub fn operational_group_key(
+    epoch_key: &SymmetricKey,
+    compressed_fabric_id: &[u8; 8],
+) -> SymmetricKey {
+    kdf(epoch_key, compressed_fabric_id, b"GroupKey v1.0")
+}
+
+/// Group session ID of an operational group key.
+#[must_use]
+pub fn group_session_id(operational_group_key: &SymmetricKey) -> u16 {
+    u16::from_be_bytes(kdf(operational_group_key, &[], b"GroupKeyHash"))
+}
+
+/// Privacy key of an operational group key, used to obfuscate the message headers of
+/// group messages.
+#[must_use]
+pub fn group_privacy_key(operational_group_key: &SymmetricKey) -> SymmetricKey {
+    kdf(operational_group_key, &[], b"PrivacyKey")
+}
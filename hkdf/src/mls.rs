@@ -0,0 +1,127 @@
+//! MLS `ExpandWithLabel`, `DeriveSecret` and `DeriveTreeSecret` functions, key schedule
+//! and secret tree.
+//!
+//! See [RFC 9420 Section 8](https://www.rfc-editor.org/rfc/rfc9420#section-8) and
+//! [Section 9](https://www.rfc-editor.org/rfc/rfc9420#section-9).
+//!
+//! The epoch key schedule is represented by [`JoinerSecret`] and [`EpochSecrets`]:
+//!
+//! ```
+//! use hkdf::mls::JoinerSecret;
+//! use sha2::Sha256;
+//!
+//! let init_secret = [0u8; 32];
+//! let group_context = b"serialized GroupContext";
+//!
+//! let joiner = JoinerSecret::<Sha256>::derive(&init_secret, Some(b"commit secret"), group_context);
+//! let welcome_secret = joiner.welcome_secret(None);
+//! let epoch = joiner.epoch_secrets(None, group_context);
+//!
+//! let tree = epoch.secret_tree(4);
+//! let ratchet = tree.application_ratchet(2).unwrap();
+//! let mut key = [0u8; 16];
+//! let mut nonce = [0u8; 12];
+//! ratchet.key(&mut key).unwrap();
+//! ratchet.nonce(&mut nonce).unwrap();
+//! let ratchet = ratchet.next_generation().unwrap();
+//! ```
+
+use crate::{GenericHkdf, HmacImpl, InvalidLength};
+use hmac::digest::Output;
+
+mod key_schedule;
+mod secret_tree;
+
+pub use key_schedule::{EpochSecrets, JoinerSecret, sender_data_key, sender_data_nonce};
+pub use secret_tree::{InvalidLeafIndex, Ratchet, SecretTree};
+
+/// Label prefix used by MLS 1.0 ([RFC 9420]).
+///
+/// [RFC 9420]: https://www.rfc-editor.org/rfc/rfc9420#section-8
+pub const MLS10_LABEL_PREFIX: &[u8] = b"MLS 1.0 ";
+
+/// Encode `len` as a variable-length integer ([RFC 9420 Section 2.1.2]).
+///
+/// [RFC 9420 Section 2.1.2]: https://www.rfc-editor.org/rfc/rfc9420#section-2.1.2
+fn varint(len: usize, buf: &mut [u8; 4]) -> Result<&[u8], InvalidLength> {
+    let len = u32::try_from(len).map_err(|_| InvalidLength)?;
+    *buf = len.to_be_bytes();
+    match len {
+        0..0x40 => Ok(&buf[3..]),
+        0x40..0x4000 => {
+            buf[2] |= 0x40;
+            Ok(&buf[2..])
+        }
+        0x4000..0x4000_0000 => {
+            buf[0] |= 0x80;
+            Ok(&buf[..])
+        }
+        _ => Err(InvalidLength),
+    }
+}
+
+/// The MLS `ExpandWithLabel` function.
+///
+/// The length of `okm` is used as the `length` field of `KDFLabel`.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `okm` is longer than `u16::MAX` or too large, or if
+/// the prefixed label or `context` can not be encoded.
+pub fn expand_with_label<H: HmacImpl>(
+    secret: &GenericHkdf<H>,
+    label: &[u8],
+    context: &[u8],
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    // struct {
+    //     uint16 length;
+    //     opaque label<V> = "MLS 1.0 " + Label;
+    //     opaque context<V>;
+    // } KDFLabel;
+    let length = u16::try_from(okm.len()).map_err(|_| InvalidLength)?;
+    let mut label_len = [0u8; 4];
+    let label_len = varint(MLS10_LABEL_PREFIX.len() + label.len(), &mut label_len)?;
+    let mut context_len = [0u8; 4];
+    let context_len = varint(context.len(), &mut context_len)?;
+
+    secret.expand_multi_info(
+        &[
+            &length.to_be_bytes(),
+            label_len,
+            MLS10_LABEL_PREFIX,
+            label,
+            context_len,
+            context,
+        ],
+        okm,
+    )
+}
+
+/// The MLS `DeriveSecret` function.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if the prefixed label can not be encoded.
+pub fn derive_secret<H: HmacImpl>(
+    secret: &GenericHkdf<H>,
+    label: &[u8],
+) -> Result<Output<H>, InvalidLength> {
+    let mut out = Output::<H>::default();
+    expand_with_label(secret, label, &[], &mut out)?;
+    Ok(out)
+}
+
+/// The MLS `DeriveTreeSecret` function.
+///
+/// The length of `okm` is used as the `length` parameter.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `okm` is longer than `u16::MAX` or too large, or if
+/// the prefixed label can not be encoded.
+pub fn derive_tree_secret<H: HmacImpl>(
+    secret: &GenericHkdf<H>,
+    label: &[u8],
+    generation: u32,
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    expand_with_label(secret, label, &generation.to_be_bytes(), okm)
+}
@@ -0,0 +1,232 @@
+use super::{SecretTree, derive_secret, expand_with_label};
+use crate::{Hkdf, InvalidLength, Secret};
+use core::fmt;
+use hmac::{
+    EagerHash, Hmac,
+    digest::{Output, OutputSizeUser},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Zero-filled value used when no commit secret or PSK secret is available.
+fn zeros<D: EagerHash>() -> Output<Hmac<D>> {
+    Output::<Hmac<D>>::default()
+}
+
+fn hkdf<D: EagerHash>(secret: &Output<Hmac<D>>) -> Hkdf<D> {
+    Hkdf::<D>::from_prk(secret).expect("secret has the hash output size")
+}
+
+fn derive<D: EagerHash>(hkdf: &Hkdf<D>, label: &[u8]) -> Output<Hmac<D>> {
+    derive_secret(hkdf, label).expect("label length is valid")
+}
+
+fn expand<D: EagerHash>(hkdf: &Hkdf<D>, label: &[u8], context: &[u8]) -> Output<Hmac<D>> {
+    let mut secret = Output::<Hmac<D>>::default();
+    expand_with_label(hkdf, label, context, &mut secret).expect("label length is valid");
+    secret
+}
+
+/// The `joiner_secret` of an epoch, shared with new members in `Welcome` messages.
+#[derive(Clone)]
+pub struct JoinerSecret<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+}
+
+impl<D: EagerHash> JoinerSecret<D> {
+    /// Wrap an existing joiner secret, e.g. one received in a `Welcome` message.
+    #[must_use]
+    pub fn new(secret: Output<Hmac<D>>) -> Self {
+        Self { secret }
+    }
+
+    /// Derive the joiner secret from the `init_secret` of the previous epoch, the
+    /// optional `commit_secret` and the serialized `GroupContext` of the new epoch.
+    ///
+    /// If `commit_secret` is `None`, a string of zeros is used as required by RFC 9420.
+    #[must_use]
+    pub fn derive(init_secret: &[u8], commit_secret: Option<&[u8]>, group_context: &[u8]) -> Self {
+        let zeros = zeros::<D>();
+        let (_, hkdf) = Hkdf::<D>::extract(Some(init_secret), commit_secret.unwrap_or(&zeros));
+        Self {
+            secret: expand(&hkdf, b"joiner", group_context),
+        }
+    }
+
+    /// Get the raw joiner secret.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    fn extract(&self, psk_secret: Option<&[u8]>) -> Hkdf<D> {
+        let zeros = zeros::<D>();
+        let (_, hkdf) = Hkdf::<D>::extract(Some(&self.secret), psk_secret.unwrap_or(&zeros));
+        hkdf
+    }
+
+    /// Derive `welcome_secret` using the optional `psk_secret`.
+    ///
+    /// If `psk_secret` is `None`, a string of zeros is used as required by RFC 9420.
+    #[must_use]
+    pub fn welcome_secret(&self, psk_secret: Option<&[u8]>) -> Output<Hmac<D>> {
+        derive(&self.extract(psk_secret), b"welcome")
+    }
+
+    /// Derive `epoch_secret` and the secrets of the epoch using the optional
+    /// `psk_secret` and the serialized `GroupContext` of the new epoch.
+    ///
+    /// If `psk_secret` is `None`, a string of zeros is used as required by RFC 9420.
+    #[must_use]
+    pub fn epoch_secrets(
+        &self,
+        psk_secret: Option<&[u8]>,
+        group_context: &[u8],
+    ) -> EpochSecrets<D> {
+        let epoch_secret = Secret::<Output<Hmac<D>>>::from(expand(
+            &self.extract(psk_secret),
+            b"epoch",
+            group_context,
+        ));
+        EpochSecrets::new(&epoch_secret)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for JoinerSecret<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for JoinerSecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinerSecret").finish_non_exhaustive()
+    }
+}
+
+/// Secrets derived from the `epoch_secret` of an epoch.
+#[derive(Clone)]
+pub struct EpochSecrets<D: EagerHash> {
+    /// `sender_data_secret` used to protect the sender data of `PrivateMessage`s.
+    pub sender_data_secret: Output<Hmac<D>>,
+    /// `encryption_secret` used as the root of the [`SecretTree`].
+    pub encryption_secret: Output<Hmac<D>>,
+    /// `exporter_secret` used by the [`exporter`][EpochSecrets::exporter].
+    pub exporter_secret: Output<Hmac<D>>,
+    /// `external_secret` from which the external HPKE key pair is derived.
+    pub external_secret: Output<Hmac<D>>,
+    /// `confirmation_key` used to compute the `confirmation_tag`.
+    pub confirmation_key: Output<Hmac<D>>,
+    /// `membership_key` used to compute the `membership_tag`.
+    pub membership_key: Output<Hmac<D>>,
+    /// `resumption_psk` used as a PSK in later epochs.
+    pub resumption_psk: Output<Hmac<D>>,
+    /// `epoch_authenticator` used to confirm that members agree on the epoch.
+    pub epoch_authenticator: Output<Hmac<D>>,
+    /// `init_secret` used to derive the joiner secret of the next epoch.
+    pub init_secret: Output<Hmac<D>>,
+}
+
+impl<D: EagerHash> EpochSecrets<D> {
+    /// Derive the secrets of the epoch from an existing `epoch_secret`.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "secret has the hash output size")]
+    pub fn new(epoch_secret: &Output<Hmac<D>>) -> Self {
+        let hkdf = hkdf::<D>(epoch_secret);
+        Self {
+            sender_data_secret: derive(&hkdf, b"sender data"),
+            encryption_secret: derive(&hkdf, b"encryption"),
+            exporter_secret: derive(&hkdf, b"exporter"),
+            external_secret: derive(&hkdf, b"external"),
+            confirmation_key: derive(&hkdf, b"confirm"),
+            membership_key: derive(&hkdf, b"membership"),
+            resumption_psk: derive(&hkdf, b"resumption"),
+            epoch_authenticator: derive(&hkdf, b"authentication"),
+            init_secret: derive(&hkdf, b"init"),
+        }
+    }
+
+    /// The `MLS-Exporter` function.
+    ///
+    /// The length of `out` is used as the `Length` parameter.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `out` is longer than `u16::MAX` or too large, or if
+    /// `label` can not be encoded.
+    pub fn exporter(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        let secret = Secret::<Output<Hmac<D>>>::from(derive_secret(
+            &hkdf::<D>(&self.exporter_secret),
+            label,
+        )?);
+        expand_with_label(&hkdf::<D>(&secret), b"exported", &D::digest(context), out)
+    }
+
+    /// Create the secret tree of the epoch for a ratchet tree with `leaf_count` leaves.
+    #[must_use]
+    pub fn secret_tree(&self, leaf_count: u32) -> SecretTree<D> {
+        SecretTree::new(self.encryption_secret.clone(), leaf_count)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for EpochSecrets<D> {
+    fn drop(&mut self) {
+        self.sender_data_secret.zeroize();
+        self.encryption_secret.zeroize();
+        self.exporter_secret.zeroize();
+        self.external_secret.zeroize();
+        self.confirmation_key.zeroize();
+        self.membership_key.zeroize();
+        self.resumption_psk.zeroize();
+        self.epoch_authenticator.zeroize();
+        self.init_secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for EpochSecrets<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochSecrets").finish_non_exhaustive()
+    }
+}
+
+/// Derive the key used to protect the sender data of a `PrivateMessage`.
+///
+/// The first `Nh` bytes of `ciphertext` are used as the sample.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `key` is too large.
+pub fn sender_data_key<D: EagerHash>(
+    sender_data_secret: &Output<Hmac<D>>,
+    ciphertext: &[u8],
+    key: &mut [u8],
+) -> Result<(), InvalidLength> {
+    let sample = ciphertext_sample::<D>(ciphertext);
+    expand_with_label(&hkdf::<D>(sender_data_secret), b"key", sample, key)
+}
+
+/// Derive the nonce used to protect the sender data of a `PrivateMessage`.
+///
+/// The first `Nh` bytes of `ciphertext` are used as the sample.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `nonce` is too large.
+pub fn sender_data_nonce<D: EagerHash>(
+    sender_data_secret: &Output<Hmac<D>>,
+    ciphertext: &[u8],
+    nonce: &mut [u8],
+) -> Result<(), InvalidLength> {
+    let sample = ciphertext_sample::<D>(ciphertext);
+    expand_with_label(&hkdf::<D>(sender_data_secret), b"nonce", sample, nonce)
+}
+
+fn ciphertext_sample<D: EagerHash>(ciphertext: &[u8]) -> &[u8] {
+    let len = ciphertext.len().min(Hmac::<D>::output_size());
+    &ciphertext[..len]
+}
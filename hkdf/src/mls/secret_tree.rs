@@ -0,0 +1,191 @@
+use super::{derive_tree_secret, expand_with_label};
+use crate::{Hkdf, InvalidLength, Secret};
+use core::fmt;
+use hmac::{EagerHash, Hmac, digest::Output};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+fn hkdf<D: EagerHash>(secret: &Output<Hmac<D>>) -> Hkdf<D> {
+    Hkdf::<D>::from_prk(secret).expect("secret has the hash output size")
+}
+
+fn expand<D: EagerHash>(secret: &Output<Hmac<D>>, label: &[u8], context: &[u8]) -> Output<Hmac<D>> {
+    let mut out = Output::<Hmac<D>>::default();
+    expand_with_label(&hkdf::<D>(secret), label, context, &mut out).expect("label length is valid");
+    out
+}
+
+/// Error that is returned when a leaf index is outside of the [`SecretTree`].
+#[derive(Copy, Clone, Debug)]
+pub struct InvalidLeafIndex;
+
+impl fmt::Display for InvalidLeafIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str("leaf index is outside of the secret tree")
+    }
+}
+
+impl core::error::Error for InvalidLeafIndex {}
+
+/// The secret tree of an epoch ([RFC 9420 Section 9]).
+///
+/// Leaf secrets are derived on demand from the `encryption_secret`, so this type does
+/// not provide the forward secrecy of a tree which deletes consumed node secrets. It
+/// should be dropped once the ratchets of all members have been created.
+///
+/// [RFC 9420 Section 9]: https://www.rfc-editor.org/rfc/rfc9420#section-9
+#[derive(Clone)]
+pub struct SecretTree<D: EagerHash> {
+    encryption_secret: Output<Hmac<D>>,
+    leaf_count: u64,
+}
+
+impl<D: EagerHash> SecretTree<D> {
+    /// Create the secret tree of a ratchet tree with `leaf_count` leaves.
+    ///
+    /// Ratchet trees are always full, so `leaf_count` is rounded up to a power of two.
+    #[must_use]
+    pub fn new(encryption_secret: Output<Hmac<D>>, leaf_count: u32) -> Self {
+        Self {
+            encryption_secret,
+            leaf_count: u64::from(leaf_count).next_power_of_two(),
+        }
+    }
+
+    /// Derive the secret of the leaf with index `leaf_index`.
+    fn leaf_secret(&self, leaf_index: u32) -> Result<Secret<Output<Hmac<D>>>, InvalidLeafIndex> {
+        if u64::from(leaf_index) >= self.leaf_count {
+            return Err(InvalidLeafIndex);
+        }
+
+        // Walk from the root down to the leaf, using the array representation of
+        // the tree in which leaves have even and parents odd node indices.
+        let target = 2 * u64::from(leaf_index);
+        let mut node = self.leaf_count - 1;
+        let mut secret = Secret::<Output<Hmac<D>>>::from(self.encryption_secret.clone());
+        while node != target {
+            let level = node.trailing_ones();
+            if target < node {
+                secret = Secret::from(expand::<D>(&secret, b"tree", b"left"));
+                node ^= 1 << (level - 1);
+            } else {
+                secret = Secret::from(expand::<D>(&secret, b"tree", b"right"));
+                node ^= 3 << (level - 1);
+            }
+        }
+        Ok(secret)
+    }
+
+    /// Create the handshake ratchet of the leaf with index `leaf_index`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLeafIndex`] if `leaf_index` is outside of the tree.
+    pub fn handshake_ratchet(&self, leaf_index: u32) -> Result<Ratchet<D>, InvalidLeafIndex> {
+        let leaf_secret = self.leaf_secret(leaf_index)?;
+        Ok(Ratchet::new(expand::<D>(&leaf_secret, b"handshake", &[])))
+    }
+
+    /// Create the application ratchet of the leaf with index `leaf_index`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLeafIndex`] if `leaf_index` is outside of the tree.
+    pub fn application_ratchet(&self, leaf_index: u32) -> Result<Ratchet<D>, InvalidLeafIndex> {
+        let leaf_secret = self.leaf_secret(leaf_index)?;
+        Ok(Ratchet::new(expand::<D>(&leaf_secret, b"application", &[])))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for SecretTree<D> {
+    fn drop(&mut self) {
+        self.encryption_secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for SecretTree<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretTree")
+            .field("leaf_count", &self.leaf_count)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handshake or application ratchet of a leaf of the [`SecretTree`].
+#[derive(Clone)]
+pub struct Ratchet<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+    generation: u32,
+}
+
+impl<D: EagerHash> Ratchet<D> {
+    /// Create a ratchet at generation 0 from its initial secret.
+    #[must_use]
+    pub fn new(secret: Output<Hmac<D>>) -> Self {
+        Self {
+            secret,
+            generation: 0,
+        }
+    }
+
+    /// Get the raw ratchet secret of the current generation.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    /// Current generation of the ratchet.
+    #[must_use]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Derive the key of the current generation. The length of `key` is used as `Nk`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key` is too large.
+    pub fn key(&self, key: &mut [u8]) -> Result<(), InvalidLength> {
+        derive_tree_secret(&hkdf::<D>(&self.secret), b"key", self.generation, key)
+    }
+
+    /// Derive the nonce of the current generation. The length of `nonce` is used as `Nn`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `nonce` is too large.
+    pub fn nonce(&self, nonce: &mut [u8]) -> Result<(), InvalidLength> {
+        derive_tree_secret(&hkdf::<D>(&self.secret), b"nonce", self.generation, nonce)
+    }
+
+    /// Derive the next generation of the ratchet.
+    ///
+    /// Returns `None` if the generation counter is exhausted.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "label length is valid")]
+    pub fn next_generation(&self) -> Option<Self> {
+        let generation = self.generation.checked_add(1)?;
+        let mut secret = Output::<Hmac<D>>::default();
+        derive_tree_secret(
+            &hkdf::<D>(&self.secret),
+            b"secret",
+            self.generation,
+            &mut secret,
+        )
+        .expect("label length is valid");
+        Some(Self { secret, generation })
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for Ratchet<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for Ratchet<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ratchet")
+            .field("generation", &self.generation)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,213 @@
+//! Noise Protocol Framework `HKDF` function and `SymmetricState` object.
+//!
+//! See [Noise Section 4.3](https://noiseprotocol.org/noise.html#hash-functions) and
+//! [Section 5.2](https://noiseprotocol.org/noise.html#the-symmetricstate-object).
+//!
+//! This module only implements the key derivation parts of Noise, so it can be combined
+//! with any DH function and cipher. `EncryptAndHash` and `DecryptAndHash` are performed
+//! by encrypting or decrypting with [`SymmetricState::cipher_key`] and
+//! [`SymmetricState::handshake_hash`] as associated data, followed by
+//! [`SymmetricState::mix_hash`] of the ciphertext.
+//!
+//! ```
+//! use hkdf::noise::SymmetricState;
+//! use sha2::Sha256;
+//!
+//! let mut state = SymmetricState::<Sha256>::new(b"Noise_NN_25519_ChaChaPoly_SHA256");
+//! state.mix_hash(b"prologue");
+//! state.mix_hash(b"initiator ephemeral public key");
+//! state.mix_hash(b"responder ephemeral public key");
+//! state.mix_key(b"DH output");
+//! assert!(state.cipher_key().is_some());
+//!
+//! let (initiator_key, responder_key) = state.split();
+//! ```
+
+use crate::{GenericHkdf, HmacImpl, Secret};
+use core::{fmt, marker::PhantomData};
+use hmac::{
+    SimpleHmac,
+    digest::{Digest, Output, OutputSizeUser, array::typenum::Unsigned},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Size of Noise cipher keys.
+pub const CIPHER_KEY_SIZE: usize = 32;
+
+/// Fill `outputs` with the outputs of the Noise `HKDF` function.
+fn hkdf<H: HmacImpl>(chaining_key: &[u8], ikm: &[u8], outputs: &mut [&mut [u8]]) {
+    let (_, hkdf) = GenericHkdf::<H>::extract(Some(chaining_key), ikm);
+    let mut reader = hkdf.expand_reader(&[]);
+    for output in outputs {
+        reader
+            .read(output)
+            .expect("two or three outputs are always valid");
+    }
+}
+
+/// The Noise `HKDF(chaining_key, input_key_material, 2)` function.
+#[must_use]
+pub fn hkdf2<H: HmacImpl>(chaining_key: &[u8], ikm: &[u8]) -> (Output<H>, Output<H>) {
+    let mut out1 = Output::<H>::default();
+    let mut out2 = Output::<H>::default();
+    hkdf::<H>(chaining_key, ikm, &mut [&mut out1, &mut out2]);
+    (out1, out2)
+}
+
+/// The Noise `HKDF(chaining_key, input_key_material, 3)` function.
+#[must_use]
+pub fn hkdf3<H: HmacImpl>(chaining_key: &[u8], ikm: &[u8]) -> (Output<H>, Output<H>, Output<H>) {
+    let mut out1 = Output::<H>::default();
+    let mut out2 = Output::<H>::default();
+    let mut out3 = Output::<H>::default();
+    hkdf::<H>(chaining_key, ikm, &mut [&mut out1, &mut out2, &mut out3]);
+    (out1, out2, out3)
+}
+
+/// Truncate a `HASHLEN` output to a cipher key.
+fn cipher_key(temp_k: &[u8]) -> [u8; CIPHER_KEY_SIZE] {
+    let mut k = [0u8; CIPHER_KEY_SIZE];
+    k.copy_from_slice(&temp_k[..CIPHER_KEY_SIZE]);
+    k
+}
+
+/// The Noise `SymmetricState` object.
+///
+/// `D` is the Noise hash function and `H` the HMAC implementation over it. [`SimpleHmac`]
+/// is used by default, so hash functions without an eager block-level core, e.g. BLAKE2,
+/// can be used as well. The output size of `D` must be at least [`CIPHER_KEY_SIZE`].
+pub struct SymmetricState<D, H = SimpleHmac<D>>
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    ck: Output<D>,
+    h: Output<D>,
+    k: Option<[u8; CIPHER_KEY_SIZE]>,
+    hmac: PhantomData<H>,
+}
+
+impl<D, H> SymmetricState<D, H>
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    /// The Noise `InitializeSymmetric(protocol_name)` function.
+    ///
+    /// `protocol_name` is used as the initial handshake hash if it fits into `HASHLEN`
+    /// bytes and hashed otherwise.
+    #[must_use]
+    pub fn new(protocol_name: &[u8]) -> Self {
+        const {
+            assert!(
+                <D as OutputSizeUser>::OutputSize::USIZE >= CIPHER_KEY_SIZE,
+                "hash output size is smaller than the cipher key size",
+            );
+        }
+        let mut h = Output::<D>::default();
+        if protocol_name.len() <= h.len() {
+            h[..protocol_name.len()].copy_from_slice(protocol_name);
+        } else {
+            h = D::digest(protocol_name);
+        }
+        Self {
+            ck: h.clone(),
+            h,
+            k: None,
+            hmac: PhantomData,
+        }
+    }
+
+    /// The Noise `MixKey(input_key_material)` function.
+    pub fn mix_key(&mut self, ikm: &[u8]) {
+        let mut ck = Secret::<Output<H>>::default();
+        let mut temp_k = Secret::<Output<H>>::default();
+        hkdf::<H>(&self.ck, ikm, &mut [&mut ck, &mut temp_k]);
+        self.ck.copy_from_slice(&ck);
+        self.k = Some(cipher_key(&temp_k));
+    }
+
+    /// The Noise `MixHash(data)` function.
+    pub fn mix_hash(&mut self, data: &[u8]) {
+        self.h = D::new().chain_update(&self.h).chain_update(data).finalize();
+    }
+
+    /// The Noise `MixKeyAndHash(input_key_material)` function.
+    pub fn mix_key_and_hash(&mut self, ikm: &[u8]) {
+        let mut ck = Secret::<Output<H>>::default();
+        let mut temp_h = Output::<H>::default();
+        let mut temp_k = Secret::<Output<H>>::default();
+        hkdf::<H>(&self.ck, ikm, &mut [&mut ck, &mut temp_h, &mut temp_k]);
+        self.ck.copy_from_slice(&ck);
+        self.mix_hash(&temp_h);
+        self.k = Some(cipher_key(&temp_k));
+    }
+
+    /// The Noise `Split()` function returning the cipher keys used to send messages
+    /// from the initiator and from the responder respectively.
+    #[must_use]
+    pub fn split(&self) -> ([u8; CIPHER_KEY_SIZE], [u8; CIPHER_KEY_SIZE]) {
+        let mut temp_k1 = Secret::<Output<H>>::default();
+        let mut temp_k2 = Secret::<Output<H>>::default();
+        hkdf::<H>(&self.ck, &[], &mut [&mut temp_k1, &mut temp_k2]);
+        (cipher_key(&temp_k1), cipher_key(&temp_k2))
+    }
+
+    /// Key of the cipher state, or `None` if no key has been mixed in yet.
+    #[must_use]
+    pub fn cipher_key(&self) -> Option<&[u8; CIPHER_KEY_SIZE]> {
+        self.k.as_ref()
+    }
+
+    /// Current handshake hash `h`, e.g. used for channel binding after the handshake.
+    #[must_use]
+    pub fn handshake_hash(&self) -> &Output<D> {
+        &self.h
+    }
+
+    /// Current chaining key `ck`.
+    #[must_use]
+    pub fn chaining_key(&self) -> &Output<D> {
+        &self.ck
+    }
+}
+
+impl<D, H> Clone for SymmetricState<D, H>
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            ck: self.ck.clone(),
+            h: self.h.clone(),
+            k: self.k,
+            hmac: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D, H> Drop for SymmetricState<D, H>
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    fn drop(&mut self) {
+        self.ck.zeroize();
+        self.h.zeroize();
+        self.k.zeroize();
+    }
+}
+
+impl<D, H> fmt::Debug for SymmetricState<D, H>
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SymmetricState").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,346 @@
+//! OSCORE security context derivation.
+//!
+//! See [RFC 8613 Section 3.2](https://www.rfc-editor.org/rfc/rfc8613#section-3.2).
+//!
+//! The Sender Key, Recipient Key and Common IV are derived from the [`InputParameters`]
+//! of the security context. The HKDF algorithm is selected with the `H` type parameter and
+//! the AEAD algorithm with the [`Aead`] type parameter:
+//!
+//! ```
+//! use hkdf::hmac::Hmac;
+//! use hkdf::oscore::{AesCcm16_64_128, InputParameters};
+//! use hex_literal::hex;
+//! use sha2::Sha256;
+//!
+//! let client = InputParameters {
+//!     master_secret: &hex!("0102030405060708090a0b0c0d0e0f10"),
+//!     master_salt: Some(&hex!("9e7ca92223786340")),
+//!     sender_id: &[],
+//!     recipient_id: &[0x01],
+//!     id_context: None,
+//! };
+//! let context = client.derive::<Hmac<Sha256>, AesCcm16_64_128>();
+//! assert_eq!(context.sender_key, hex!("f0910ed7295e6ad4b54fc793154302ff"));
+//! assert_eq!(context.recipient_key, hex!("ffb14e093c94c9cac9471648b4f98710"));
+//! assert_eq!(context.common_iv, hex!("4622d4dd6d944168eefb54987c"));
+//! ```
+
+use crate::{GenericHkdf, HmacImpl, InvalidLength, cbor, cose::AlgorithmId};
+use core::fmt;
+use hmac::digest::{
+    array::{Array, ArraySize, typenum::Unsigned},
+    consts::{U7, U12, U13, U16, U24, U32},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Maximum number of data item heads of the `info` encoding.
+const MAX_HEADS: usize = 6;
+/// Maximum number of parts of the `info` encoding.
+const MAX_PARTS: usize = 11;
+
+/// ID Context, possibly the concatenation of two byte strings.
+type IdContext<'a> = Option<(&'a [u8], &'a [u8])>;
+
+/// AEAD algorithm of a security context.
+pub trait Aead {
+    /// COSE algorithm identifier, used as the `alg_aead` field of `info`.
+    const ALG: AlgorithmId<'static>;
+    /// Size of the key.
+    type KeySize: ArraySize;
+    /// Size of the nonce, i.e. of the Common IV.
+    type NonceSize: ArraySize;
+}
+
+macro_rules! aead {
+    ($name:ident, $alg:expr, $key:ty, $nonce:ty, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+
+        impl Aead for $name {
+            const ALG: AlgorithmId<'static> = AlgorithmId::Int($alg);
+            type KeySize = $key;
+            type NonceSize = $nonce;
+        }
+    };
+}
+
+aead!(A128Gcm, 1, U16, U12, "`A128GCM` AEAD algorithm.");
+aead!(A192Gcm, 2, U24, U12, "`A192GCM` AEAD algorithm.");
+aead!(A256Gcm, 3, U32, U12, "`A256GCM` AEAD algorithm.");
+aead!(
+    AesCcm16_64_128,
+    10,
+    U16,
+    U13,
+    "`AES-CCM-16-64-128` AEAD algorithm, the mandatory to implement algorithm of OSCORE."
+);
+aead!(
+    AesCcm16_64_256,
+    11,
+    U32,
+    U13,
+    "`AES-CCM-16-64-256` AEAD algorithm."
+);
+aead!(
+    AesCcm64_64_128,
+    12,
+    U16,
+    U7,
+    "`AES-CCM-64-64-128` AEAD algorithm."
+);
+aead!(
+    AesCcm64_64_256,
+    13,
+    U32,
+    U7,
+    "`AES-CCM-64-64-256` AEAD algorithm."
+);
+aead!(
+    ChaCha20Poly1305,
+    24,
+    U32,
+    U12,
+    "`ChaCha20/Poly1305` AEAD algorithm."
+);
+aead!(
+    AesCcm16_128_128,
+    30,
+    U16,
+    U13,
+    "`AES-CCM-16-128-128` AEAD algorithm."
+);
+aead!(
+    AesCcm16_128_256,
+    31,
+    U32,
+    U13,
+    "`AES-CCM-16-128-256` AEAD algorithm."
+);
+aead!(
+    AesCcm64_128_128,
+    32,
+    U16,
+    U7,
+    "`AES-CCM-64-128-128` AEAD algorithm."
+);
+aead!(
+    AesCcm64_128_256,
+    33,
+    U32,
+    U7,
+    "`AES-CCM-64-128-256` AEAD algorithm."
+);
+
+/// The `type` field of `info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoType {
+    /// `"Key"`, used for the Sender Key and the Recipient Key.
+    Key,
+    /// `"IV"`, used for the Common IV.
+    Iv,
+}
+
+impl InfoType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Key => "Key",
+            Self::Iv => "IV",
+        }
+    }
+}
+
+/// The `info` parameter of the derivation without its `L` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Info<'a> {
+    /// The `id` field: the Sender ID or Recipient ID for keys, empty for the Common IV.
+    pub id: &'a [u8],
+    /// The `id_context` field, encoded as `nil` if `None`.
+    pub id_context: Option<&'a [u8]>,
+    /// The `alg_aead` field.
+    pub alg_aead: AlgorithmId<'a>,
+    /// The `type` field.
+    pub kind: InfoType,
+}
+
+impl Info<'_> {
+    /// Encode `info` as the CBOR array `[id, id_context, alg_aead, type, L]` into `buf`,
+    /// returning the length of the encoding.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `buf` is too small.
+    pub fn encode(&self, length: usize, buf: &mut [u8]) -> Result<usize, InvalidLength> {
+        let id_context = self.id_context.map(|id_context| (id_context, &[][..]));
+        with_parts(self, id_context, length, |parts| {
+            let mut len = 0;
+            for part in parts {
+                buf.get_mut(len..len + part.len())
+                    .ok_or(InvalidLength)?
+                    .copy_from_slice(part);
+                len += part.len();
+            }
+            Ok(len)
+        })
+    }
+
+    /// HKDF-Expand with `info` as `info`.
+    ///
+    /// The length of `okm` is used as the `L` field.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `okm` is too large.
+    pub fn expand<H: HmacImpl>(
+        &self,
+        hkdf: &GenericHkdf<H>,
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        let id_context = self.id_context.map(|id_context| (id_context, &[][..]));
+        with_parts(self, id_context, okm.len(), |parts| {
+            hkdf.expand_multi_info(parts, okm)
+        })
+    }
+}
+
+/// Call `f` with the parts of the encoding of `info`, using `id_context` instead of
+/// the `id_context` field.
+fn with_parts<R>(
+    info: &Info<'_>,
+    id_context: IdContext<'_>,
+    length: usize,
+    f: impl FnOnce(&[&[u8]]) -> R,
+) -> R {
+    let mut heads = cbor::Heads::<MAX_HEADS>::default();
+    let mut enc = cbor::Encoder::<MAX_PARTS>::new(&mut heads);
+    enc.array(5);
+    enc.bytes(info.id);
+    match id_context {
+        Some((a, b)) => enc.bytes_concat(a, b),
+        None => enc.null(),
+    }
+    match info.alg_aead {
+        AlgorithmId::Int(alg) => enc.int(alg),
+        AlgorithmId::Text(alg) => enc.text(alg),
+    }
+    enc.text(info.kind.as_str());
+    enc.uint(length as u64);
+    f(enc.parts())
+}
+
+/// Input parameters of a security context.
+///
+/// The AEAD algorithm and the HKDF algorithm are not part of the struct, but are the type
+/// parameters of [`derive`][Self::derive].
+#[derive(Clone, Copy)]
+pub struct InputParameters<'a> {
+    /// The Master Secret.
+    pub master_secret: &'a [u8],
+    /// The Master Salt, or `None` for the default empty byte string.
+    pub master_salt: Option<&'a [u8]>,
+    /// The Sender ID.
+    ///
+    /// Its length must be at most the nonce length of the AEAD algorithm minus 6 bytes
+    /// for the nonce construction.
+    pub sender_id: &'a [u8],
+    /// The Recipient ID.
+    pub recipient_id: &'a [u8],
+    /// The ID Context, or `None` if there is none.
+    pub id_context: Option<&'a [u8]>,
+}
+
+impl InputParameters<'_> {
+    /// Derive the security context.
+    #[must_use]
+    pub fn derive<H: HmacImpl, A: Aead>(&self) -> SecurityContext<A> {
+        let id_context = self.id_context.map(|id_context| (id_context, &[][..]));
+        self.derive_with::<H, A>(id_context)
+    }
+
+    /// Derive a new security context with the ID Context `r1 | r2` for the re-keying
+    /// procedure of [RFC 8613 Appendix B.2].
+    ///
+    /// `r1` and `r2` are the random values (of at least 8 bytes) generated by the client
+    /// and the server respectively; the Master Secret and Master Salt are kept. A context
+    /// with the ID Context `r1` alone, as used to protect the first request of the
+    /// procedure, is derived by [`derive`][Self::derive] with `id_context` set to `r1`.
+    ///
+    /// [RFC 8613 Appendix B.2]: https://www.rfc-editor.org/rfc/rfc8613#appendix-B.2
+    #[must_use]
+    pub fn rekey<H: HmacImpl, A: Aead>(&self, r1: &[u8], r2: &[u8]) -> SecurityContext<A> {
+        self.derive_with::<H, A>(Some((r1, r2)))
+    }
+
+    fn derive_with<H: HmacImpl, A: Aead>(&self, id_context: IdContext<'_>) -> SecurityContext<A> {
+        let hkdf = GenericHkdf::<H>::new(
+            Some(self.master_salt.unwrap_or_default()),
+            self.master_secret,
+        );
+        let expand = |id, kind, okm: &mut [u8]| {
+            let info = Info {
+                id,
+                id_context: None,
+                alg_aead: A::ALG,
+                kind,
+            };
+            with_parts(&info, id_context, okm.len(), |parts| {
+                hkdf.expand_multi_info(parts, okm)
+            })
+            .expect("key and nonce lengths are valid");
+        };
+
+        let mut context = SecurityContext::<A> {
+            sender_key: Array::default(),
+            recipient_key: Array::default(),
+            common_iv: Array::default(),
+        };
+        expand(self.sender_id, InfoType::Key, &mut context.sender_key);
+        expand(self.recipient_id, InfoType::Key, &mut context.recipient_key);
+        expand(&[], InfoType::Iv, &mut context.common_iv);
+        context
+    }
+}
+
+impl fmt::Debug for InputParameters<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputParameters")
+            .field("sender_id", &self.sender_id)
+            .field("recipient_id", &self.recipient_id)
+            .field("id_context", &self.id_context)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys and Common IV of a security context derived from [`InputParameters`].
+#[derive(Clone)]
+pub struct SecurityContext<A: Aead> {
+    /// Sender Key.
+    pub sender_key: Array<u8, A::KeySize>,
+    /// Recipient Key.
+    pub recipient_key: Array<u8, A::KeySize>,
+    /// Common IV.
+    pub common_iv: Array<u8, A::NonceSize>,
+}
+
+impl<A: Aead> SecurityContext<A> {
+    /// Maximum length of the Sender ID and Recipient ID of the AEAD algorithm.
+    #[must_use]
+    pub fn max_id_len() -> usize {
+        A::NonceSize::USIZE.saturating_sub(6)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<A: Aead> Drop for SecurityContext<A> {
+    fn drop(&mut self) {
+        self.sender_key.zeroize();
+        self.recipient_key.zeroize();
+        self.common_iv.zeroize();
+    }
+}
+
+impl<A: Aead> fmt::Debug for SecurityContext<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecurityContext").finish_non_exhaustive()
+    }
+}
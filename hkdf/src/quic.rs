@@ -0,0 +1,274 @@
+//! QUIC initial secrets and packet protection key derivation.
+//!
+//! See [RFC 9001 Section 5](https://www.rfc-editor.org/rfc/rfc9001#section-5) and
+//! [RFC 9369 Section 3.3](https://www.rfc-editor.org/rfc/rfc9369#section-3.3).
+//!
+//! ```
+//! use hkdf::quic::{Aes128Gcm, InitialSecrets, Version};
+//! use hex_literal::hex;
+//!
+//! let secrets = InitialSecrets::new(Version::V1, &hex!("8394c8f03e515708"));
+//! let keys = secrets.client.packet_keys::<Aes128Gcm>();
+//! assert_eq!(keys.key, hex!("1f369613dd76d5467730efcbe3b1a22d"));
+//! assert_eq!(keys.iv, hex!("fa044b2f42a3fd3b46fb255c"));
+//! assert_eq!(keys.hp, hex!("9f50449e04a0e810283a1e9933adedd2"));
+//! ```
+
+use crate::{Hkdf, tls13::TrafficSecret};
+use core::fmt;
+use hmac::{
+    EagerHash, Hmac,
+    digest::{
+        Output,
+        array::{Array, ArraySize},
+        consts::{U12, U16, U32},
+    },
+};
+use sha2::Sha256;
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// QUIC version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Version {
+    /// QUIC version 1 ([RFC 9001]).
+    ///
+    /// [RFC 9001]: https://www.rfc-editor.org/rfc/rfc9001
+    #[default]
+    V1,
+    /// QUIC version 2 ([RFC 9369]).
+    ///
+    /// [RFC 9369]: https://www.rfc-editor.org/rfc/rfc9369
+    V2,
+}
+
+impl Version {
+    /// Salt used to derive the initial secret.
+    #[must_use]
+    pub fn initial_salt(self) -> &'static [u8; 20] {
+        match self {
+            Version::V1 => &[
+                0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8,
+                0x0c, 0xad, 0xcc, 0xbb, 0x7f, 0x0a,
+            ],
+            Version::V2 => &[
+                0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93, 0x81, 0xbe, 0x6e, 0x26,
+                0x9d, 0xcb, 0xf9, 0xbd, 0x2e, 0xd9,
+            ],
+        }
+    }
+
+    /// Label used to derive the packet protection key.
+    #[must_use]
+    pub fn key_label(self) -> &'static [u8] {
+        match self {
+            Version::V1 => b"quic key",
+            Version::V2 => b"quicv2 key",
+        }
+    }
+
+    /// Label used to derive the packet protection IV.
+    #[must_use]
+    pub fn iv_label(self) -> &'static [u8] {
+        match self {
+            Version::V1 => b"quic iv",
+            Version::V2 => b"quicv2 iv",
+        }
+    }
+
+    /// Label used to derive the header protection key.
+    #[must_use]
+    pub fn hp_label(self) -> &'static [u8] {
+        match self {
+            Version::V1 => b"quic hp",
+            Version::V2 => b"quicv2 hp",
+        }
+    }
+
+    /// Label used to derive the next generation of secrets on key update.
+    #[must_use]
+    pub fn ku_label(self) -> &'static [u8] {
+        match self {
+            Version::V1 => b"quic ku",
+            Version::V2 => b"quicv2 ku",
+        }
+    }
+}
+
+/// AEAD algorithm used for packet protection.
+pub trait AeadSuite {
+    /// Size of the packet protection key.
+    type KeySize: ArraySize;
+    /// Size of the header protection key.
+    type HpKeySize: ArraySize;
+}
+
+/// `AEAD_AES_128_GCM` packet protection.
+#[derive(Clone, Copy, Debug)]
+pub struct Aes128Gcm;
+
+impl AeadSuite for Aes128Gcm {
+    type KeySize = U16;
+    type HpKeySize = U16;
+}
+
+/// `AEAD_AES_128_CCM` packet protection.
+#[derive(Clone, Copy, Debug)]
+pub struct Aes128Ccm;
+
+impl AeadSuite for Aes128Ccm {
+    type KeySize = U16;
+    type HpKeySize = U16;
+}
+
+/// `AEAD_AES_256_GCM` packet protection.
+#[derive(Clone, Copy, Debug)]
+pub struct Aes256Gcm;
+
+impl AeadSuite for Aes256Gcm {
+    type KeySize = U32;
+    type HpKeySize = U32;
+}
+
+/// `AEAD_CHACHA20_POLY1305` packet protection.
+#[derive(Clone, Copy, Debug)]
+pub struct ChaCha20Poly1305;
+
+impl AeadSuite for ChaCha20Poly1305 {
+    type KeySize = U32;
+    type HpKeySize = U32;
+}
+
+/// Packet and header protection keys derived from a [`Secret`].
+#[derive(Clone)]
+pub struct PacketKeys<A: AeadSuite> {
+    /// Packet protection key.
+    pub key: Array<u8, A::KeySize>,
+    /// Packet protection IV.
+    pub iv: Array<u8, U12>,
+    /// Header protection key.
+    pub hp: Array<u8, A::HpKeySize>,
+}
+
+#[cfg(feature = "zeroize")]
+impl<A: AeadSuite> Drop for PacketKeys<A> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.iv.zeroize();
+        self.hp.zeroize();
+    }
+}
+
+impl<A: AeadSuite> fmt::Debug for PacketKeys<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketKeys").finish_non_exhaustive()
+    }
+}
+
+/// QUIC secret of one direction of an encryption level.
+#[derive(Clone)]
+pub struct Secret<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+    version: Version,
+}
+
+impl<D: EagerHash> Secret<D> {
+    /// Wrap an existing secret, e.g. a TLS 1.3 traffic secret.
+    #[must_use]
+    pub fn new(secret: Output<Hmac<D>>, version: Version) -> Self {
+        Self { secret, version }
+    }
+
+    /// Create a QUIC secret from a TLS 1.3 traffic secret.
+    #[must_use]
+    pub fn from_traffic_secret(secret: &TrafficSecret<D>, version: Version) -> Self {
+        Self::new(secret.as_bytes().clone(), version)
+    }
+
+    /// Get the raw secret.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    /// QUIC version this secret is used with.
+    #[must_use]
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    fn expand_label<N: ArraySize>(&self, label: &[u8]) -> Array<u8, N> {
+        let hkdf = Hkdf::<D>::from_prk(&self.secret).expect("secret has the hash output size");
+        expand_label(&hkdf, label)
+    }
+
+    /// Derive the packet protection key, IV and header protection key.
+    #[must_use]
+    pub fn packet_keys<A: AeadSuite>(&self) -> PacketKeys<A> {
+        PacketKeys {
+            key: self.expand_label(self.version.key_label()),
+            iv: self.expand_label(self.version.iv_label()),
+            hp: self.expand_label(self.version.hp_label()),
+        }
+    }
+
+    /// Derive the next generation of this secret on key update.
+    ///
+    /// Note that the header protection key is not updated, so it should be
+    /// taken from the first generation of the secret.
+    #[must_use]
+    pub fn next_generation(&self) -> Self {
+        Self {
+            secret: self.expand_label(self.version.ku_label()),
+            version: self.version,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for Secret<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for Secret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secret")
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Client and server initial secrets.
+///
+/// Initial packets are always protected with `AEAD_AES_128_GCM` and SHA-256.
+#[derive(Clone, Debug)]
+pub struct InitialSecrets {
+    /// Secret used to protect packets sent by the client.
+    pub client: Secret<Sha256>,
+    /// Secret used to protect packets sent by the server.
+    pub server: Secret<Sha256>,
+}
+
+impl InitialSecrets {
+    /// Derive initial secrets from the Destination Connection ID of the first
+    /// Initial packet sent by the client.
+    #[must_use]
+    pub fn new(version: Version, client_dst_connection_id: &[u8]) -> Self {
+        let (_, initial) =
+            Hkdf::<Sha256>::extract(Some(version.initial_salt()), client_dst_connection_id);
+        Self {
+            client: Secret::new(expand_label(&initial, b"client in"), version),
+            server: Secret::new(expand_label(&initial, b"server in"), version),
+        }
+    }
+}
+
+fn expand_label<D: EagerHash, N: ArraySize>(hkdf: &Hkdf<D>, label: &[u8]) -> Array<u8, N> {
+    let mut okm = Array::<u8, N>::default();
+    hkdf.expand_label(label, &[], &mut okm)
+        .expect("label and output lengths are valid");
+    okm
+}
@@ -0,0 +1,317 @@
+//! Signal X3DH and Double Ratchet key derivation functions.
+//!
+//! See [X3DH Section 2.2](https://signal.org/docs/specifications/x3dh/#cryptographic-notation)
+//! and [Double Ratchet Section 5.2](https://signal.org/docs/specifications/doubleratchet/#recommended-cryptographic-algorithms).
+//!
+//! This module only implements the key derivation parts of the protocols, so it can be
+//! combined with any DH function and cipher. The DH outputs are passed in by the caller:
+//!
+//! ```
+//! use hkdf::signal::{Curve, Info, RatchetState, x3dh};
+//! use sha2::Sha256;
+//!
+//! // DH1 || DH2 || DH3 computed by Alice and Bob
+//! let dh_outputs: [&[u8]; 3] = [&[0x01; 32], &[0x02; 32], &[0x03; 32]];
+//! let mut sk = [0u8; 32];
+//! x3dh::<Sha256>(Curve::X25519, &dh_outputs, b"MyProtocol", &mut sk).unwrap();
+//!
+//! // Alice starts a sending chain with DH(alice_ratchet, bob_signed_prekey)
+//! let mut alice = RatchetState::<Sha256, 16>::new_initiator(sk, Info::LIBSIGNAL, b"DH output");
+//! let mut bob = RatchetState::<Sha256, 16>::new_responder(sk, Info::LIBSIGNAL);
+//!
+//! let alice_ratchet_key = [0x05; 32];
+//! let sent = alice.sending_keys().unwrap();
+//! bob.ratchet_receive(&alice_ratchet_key, 0, b"DH output").unwrap();
+//! let received = bob.receiving_keys(&alice_ratchet_key, sent.counter).unwrap();
+//! assert_eq!(sent.cipher_key, received.cipher_key);
+//! ```
+
+use crate::{Hkdf, HkdfExtract, HmacImpl, InvalidLength, Secret};
+use core::{fmt, marker::PhantomData};
+use hmac::{
+    EagerHash, Hmac,
+    digest::{OutputSizeUser, consts::U32},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+mod ratchet;
+
+pub use ratchet::{RatchetError, RatchetState};
+
+/// Size of root, chain and message keys.
+pub const KEY_SIZE: usize = 32;
+
+/// Info used by libsignal for the X3DH (`"WhisperText"`) key derivation.
+pub const LIBSIGNAL_X3DH_INFO: &[u8] = b"WhisperText";
+
+/// Curve used for the X3DH key agreement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// X25519, prefixed with 32 `0xFF` bytes.
+    X25519,
+    /// X448, prefixed with 57 `0xFF` bytes.
+    X448,
+}
+
+impl Curve {
+    /// Length of the `0xFF` prefix `F` of the X3DH key material.
+    #[must_use]
+    pub fn prefix_len(self) -> usize {
+        match self {
+            Curve::X25519 => 32,
+            Curve::X448 => 57,
+        }
+    }
+}
+
+/// The X3DH `KDF(KM)` function.
+///
+/// `dh_outputs` are concatenated to `KM`, e.g. `DH1 || DH2 || DH3 || DH4`, which may be
+/// followed by the KEM shared secret of PQXDH. The length of `okm` is used as the output
+/// length, which is 32 bytes for the shared key `SK` of X3DH.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `okm` is too large.
+pub fn x3dh<D: EagerHash>(
+    curve: Curve,
+    dh_outputs: &[&[u8]],
+    info: &[u8],
+    okm: &mut [u8],
+) -> Result<(), InvalidLength> {
+    let mut extract = HkdfExtract::<D>::new(None);
+    extract.input_ikm(&[0xFF; 57][..curve.prefix_len()]);
+    for dh_output in dh_outputs {
+        extract.input_ikm(dh_output);
+    }
+    let (_, hkdf) = extract.finalize();
+    hkdf.expand(info, okm)
+}
+
+/// Application-specific info used by `KDF_RK` and the message key derivation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Info<'a> {
+    /// Info of `KDF_RK`.
+    pub root: &'a [u8],
+    /// Info of the message key derivation.
+    pub message_keys: &'a [u8],
+}
+
+impl Info<'static> {
+    /// Info used by libsignal.
+    pub const LIBSIGNAL: Self = Self {
+        root: b"WhisperRatchet",
+        message_keys: b"WhisperMessageKeys",
+    };
+}
+
+/// Root key of the Double Ratchet.
+///
+/// `D` must have an output size of 32 bytes, e.g. SHA-256.
+pub struct RootKey<D> {
+    key: [u8; KEY_SIZE],
+    hash: PhantomData<D>,
+}
+
+impl<D> RootKey<D>
+where
+    D: EagerHash + OutputSizeUser<OutputSize = U32>,
+{
+    /// Create a root key, e.g. from the shared key `SK` of X3DH.
+    #[must_use]
+    pub fn new(key: [u8; KEY_SIZE]) -> Self {
+        Self {
+            key,
+            hash: PhantomData,
+        }
+    }
+
+    /// Get the raw root key.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.key
+    }
+
+    /// The `KDF_RK(rk, dh_out)` function returning the next root key and a new chain key.
+    #[must_use]
+    pub fn create_chain(&self, dh_output: &[u8], info: &[u8]) -> (Self, ChainKey<D>) {
+        let (_, hkdf) = Hkdf::<D>::extract(Some(&self.key), dh_output);
+        let okm = Secret::<[u8; 2 * KEY_SIZE]>::from(hkdf.expand_array(info));
+        let mut root_key = Self::new([0; KEY_SIZE]);
+        let mut chain_key = ChainKey::new([0; KEY_SIZE], 0);
+        root_key.key.copy_from_slice(&okm[..KEY_SIZE]);
+        chain_key.key.copy_from_slice(&okm[KEY_SIZE..]);
+        (root_key, chain_key)
+    }
+}
+
+impl<D> Clone for RootKey<D> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key,
+            hash: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D> Drop for RootKey<D> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl<D> fmt::Debug for RootKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootKey").finish_non_exhaustive()
+    }
+}
+
+/// Sending or receiving chain key of the symmetric-key ratchet.
+///
+/// `D` must have an output size of 32 bytes, e.g. SHA-256.
+pub struct ChainKey<D> {
+    key: [u8; KEY_SIZE],
+    index: u32,
+    hash: PhantomData<D>,
+}
+
+impl<D> ChainKey<D>
+where
+    D: EagerHash + OutputSizeUser<OutputSize = U32>,
+{
+    const MESSAGE_KEY_SEED: u8 = 0x01;
+    const CHAIN_KEY_SEED: u8 = 0x02;
+
+    /// Create a chain key with the index of the next message.
+    #[must_use]
+    pub fn new(key: [u8; KEY_SIZE], index: u32) -> Self {
+        Self {
+            key,
+            index,
+            hash: PhantomData,
+        }
+    }
+
+    /// Get the raw chain key.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.key
+    }
+
+    /// Index of the message which is protected by the keys of this chain key.
+    #[must_use]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn hmac(&self, seed: u8) -> [u8; KEY_SIZE] {
+        let mut hmac = Hmac::<D>::new_from_slice(&self.key);
+        hmac.update(&[seed]);
+        let mut out = [0u8; KEY_SIZE];
+        out.copy_from_slice(&hmac.finalize());
+        out
+    }
+
+    /// The message key part of `KDF_CK(ck)`, i.e. `HMAC(ck, 0x01)`.
+    #[must_use]
+    pub fn message_key_seed(&self) -> [u8; KEY_SIZE] {
+        self.hmac(Self::MESSAGE_KEY_SEED)
+    }
+
+    /// Derive the message keys of this chain key from [`message_key_seed`][Self::message_key_seed].
+    #[must_use]
+    pub fn message_keys(&self, info: &[u8]) -> MessageKeys {
+        let seed = Secret::<[u8; KEY_SIZE]>::from(self.message_key_seed());
+        MessageKeys::derive::<D>(seed.as_slice(), info, self.index)
+    }
+
+    /// The chain key part of `KDF_CK(ck)`, i.e. `HMAC(ck, 0x02)`.
+    ///
+    /// Returns `None` if the message index is exhausted.
+    #[must_use]
+    pub fn next_chain_key(&self) -> Option<Self> {
+        let index = self.index.checked_add(1)?;
+        Some(Self::new(self.hmac(Self::CHAIN_KEY_SEED), index))
+    }
+}
+
+impl<D> Clone for ChainKey<D> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key,
+            index: self.index,
+            hash: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D> Drop for ChainKey<D> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl<D> fmt::Debug for ChainKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainKey")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keys used to protect a single message.
+#[derive(Clone)]
+#[allow(
+    missing_copy_implementations,
+    reason = "keys are wiped on drop with zeroize"
+)]
+pub struct MessageKeys {
+    /// Encryption key.
+    pub cipher_key: [u8; KEY_SIZE],
+    /// Authentication key.
+    pub mac_key: [u8; KEY_SIZE],
+    /// Initialization vector.
+    pub iv: [u8; 16],
+    /// Index of the message in its chain.
+    pub counter: u32,
+}
+
+impl MessageKeys {
+    /// Expand a message key seed into encryption key, authentication key and IV
+    /// using HKDF with a zero-filled salt.
+    #[must_use]
+    pub fn derive<D: EagerHash>(seed: &[u8], info: &[u8], counter: u32) -> Self {
+        let (_, hkdf) = Hkdf::<D>::extract(None, seed);
+        let okm = Secret::<[u8; 2 * KEY_SIZE + 16]>::from(hkdf.expand_array(info));
+        let mut keys = Self {
+            cipher_key: [0; KEY_SIZE],
+            mac_key: [0; KEY_SIZE],
+            iv: [0; 16],
+            counter,
+        };
+        keys.cipher_key.copy_from_slice(&okm[..KEY_SIZE]);
+        keys.mac_key.copy_from_slice(&okm[KEY_SIZE..2 * KEY_SIZE]);
+        keys.iv.copy_from_slice(&okm[2 * KEY_SIZE..]);
+        keys
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for MessageKeys {
+    fn drop(&mut self) {
+        self.cipher_key.zeroize();
+        self.mac_key.zeroize();
+        self.iv.zeroize();
+    }
+}
+
+impl fmt::Debug for MessageKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageKeys")
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,260 @@
+use super::{ChainKey, Info, MessageKeys, RootKey};
+use core::fmt;
+use hmac::{
+    EagerHash,
+    digest::{OutputSizeUser, consts::U32},
+};
+
+/// Size of the ratchet public keys, i.e. X25519 public keys.
+const PUBLIC_KEY_SIZE: usize = 32;
+
+/// Error returned by [`RatchetState`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RatchetError {
+    /// No sending chain has been started yet.
+    NoSendingChain,
+    /// The ratchet key of the message does not belong to the current receiving chain
+    /// and no skipped message key is stored for it.
+    UnknownRatchetKey,
+    /// The message key has already been used or was evicted from the skipped keys.
+    DuplicateMessage,
+    /// The message would skip more message keys than the configured limit.
+    TooManySkippedMessages,
+    /// The message counter of the chain is exhausted.
+    ChainExhausted,
+}
+
+impl fmt::Display for RatchetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RatchetError::NoSendingChain => "no sending chain has been started",
+            RatchetError::UnknownRatchetKey => "unknown ratchet key",
+            RatchetError::DuplicateMessage => "message key has already been used",
+            RatchetError::TooManySkippedMessages => "too many skipped messages",
+            RatchetError::ChainExhausted => "message counter of the chain is exhausted",
+        })
+    }
+}
+
+impl core::error::Error for RatchetError {}
+
+/// Message keys of a skipped message.
+struct SkippedKeys {
+    ratchet_key: [u8; PUBLIC_KEY_SIZE],
+    keys: MessageKeys,
+}
+
+/// State of the Double Ratchet key derivation ([Double Ratchet Section 3]).
+///
+/// The DH ratchet is driven by the caller, who performs the DH computations and passes
+/// their outputs in. Up to `MAX_SKIP` message keys of skipped messages are stored so
+/// messages can be received out of order. If the storage is full, the oldest skipped
+/// keys are evicted.
+///
+/// `D` must have an output size of 32 bytes, e.g. SHA-256.
+///
+/// [Double Ratchet Section 3]: https://signal.org/docs/specifications/doubleratchet/#double-ratchet-1
+pub struct RatchetState<D, const MAX_SKIP: usize> {
+    root_key: RootKey<D>,
+    info: Info<'static>,
+    sending: Option<ChainKey<D>>,
+    receiving: Option<([u8; PUBLIC_KEY_SIZE], ChainKey<D>)>,
+    skipped: [Option<SkippedKeys>; MAX_SKIP],
+    next_skipped: usize,
+}
+
+impl<D, const MAX_SKIP: usize> RatchetState<D, MAX_SKIP>
+where
+    D: EagerHash + OutputSizeUser<OutputSize = U32>,
+{
+    fn new(root_key: RootKey<D>, info: Info<'static>) -> Self {
+        Self {
+            root_key,
+            info,
+            sending: None,
+            receiving: None,
+            skipped: [const { None }; MAX_SKIP],
+            next_skipped: 0,
+        }
+    }
+
+    /// Initialize the state of the party which sends the first message (Alice).
+    ///
+    /// `dh_output` is the DH output of Alice's first ratchet key pair and Bob's ratchet
+    /// public key, e.g. his signed prekey.
+    #[must_use]
+    pub fn new_initiator(shared_key: [u8; 32], info: Info<'static>, dh_output: &[u8]) -> Self {
+        let mut state = Self::new(RootKey::new(shared_key), info);
+        state.ratchet_send(dh_output);
+        state
+    }
+
+    /// Initialize the state of the party which receives the first message (Bob).
+    #[must_use]
+    pub fn new_responder(shared_key: [u8; 32], info: Info<'static>) -> Self {
+        Self::new(RootKey::new(shared_key), info)
+    }
+
+    /// Current root key.
+    #[must_use]
+    pub fn root_key(&self) -> &RootKey<D> {
+        &self.root_key
+    }
+
+    /// Current sending chain key, or `None` if no sending chain has been started.
+    #[must_use]
+    pub fn sending_chain(&self) -> Option<&ChainKey<D>> {
+        self.sending.as_ref()
+    }
+
+    /// Current receiving chain key and the ratchet key of the remote party, or `None`
+    /// if no message has been received yet.
+    #[must_use]
+    pub fn receiving_chain(&self) -> Option<(&[u8; PUBLIC_KEY_SIZE], &ChainKey<D>)> {
+        self.receiving.as_ref().map(|(key, chain)| (key, chain))
+    }
+
+    /// Number of stored message keys of skipped messages.
+    #[must_use]
+    pub fn skipped_len(&self) -> usize {
+        self.skipped.iter().flatten().count()
+    }
+
+    /// Store the message keys of `chain_key` up to message `until` (exclusive) and
+    /// return the chain key of message `until`.
+    fn skip(
+        &mut self,
+        ratchet_key: &[u8; PUBLIC_KEY_SIZE],
+        chain_key: &ChainKey<D>,
+        until: u32,
+    ) -> Result<ChainKey<D>, RatchetError> {
+        let count = until.saturating_sub(chain_key.index());
+        if usize::try_from(count).map_or(true, |count| count > MAX_SKIP) {
+            return Err(RatchetError::TooManySkippedMessages);
+        }
+
+        let mut chain_key = chain_key.clone();
+        while chain_key.index() < until {
+            let keys = chain_key.message_keys(self.info.message_keys);
+            chain_key = chain_key
+                .next_chain_key()
+                .ok_or(RatchetError::ChainExhausted)?;
+            self.skipped[self.next_skipped] = Some(SkippedKeys {
+                ratchet_key: *ratchet_key,
+                keys,
+            });
+            self.next_skipped = (self.next_skipped + 1) % MAX_SKIP;
+        }
+        Ok(chain_key)
+    }
+
+    /// Start a new sending chain after generating a new ratchet key pair.
+    ///
+    /// `dh_output` is the DH output of the new ratchet key pair and the current ratchet
+    /// public key of the remote party. Returns the length of the previous sending chain,
+    /// which is sent as `PN` in the message headers of the new chain.
+    pub fn ratchet_send(&mut self, dh_output: &[u8]) -> u32 {
+        let previous_len = self.sending.as_ref().map_or(0, ChainKey::index);
+        let (root_key, chain_key) = self.root_key.create_chain(dh_output, self.info.root);
+        self.root_key = root_key;
+        self.sending = Some(chain_key);
+        previous_len
+    }
+
+    /// Start a new receiving chain for the new ratchet key `their_ratchet_key` of the
+    /// remote party.
+    ///
+    /// The message keys of the current receiving chain up to `previous_chain_length`
+    /// (`PN` of the message header) are stored as skipped keys. `dh_output` is the DH
+    /// output of the current ratchet key pair and `their_ratchet_key`. Afterwards a new
+    /// sending chain should be started with [`ratchet_send`][Self::ratchet_send].
+    ///
+    /// # Errors
+    /// Returns [`RatchetError::TooManySkippedMessages`] if more than `MAX_SKIP` message
+    /// keys would be skipped. The state is not modified in this case.
+    pub fn ratchet_receive(
+        &mut self,
+        their_ratchet_key: &[u8; PUBLIC_KEY_SIZE],
+        previous_chain_length: u32,
+        dh_output: &[u8],
+    ) -> Result<(), RatchetError> {
+        if let Some((ratchet_key, chain_key)) = self.receiving.clone() {
+            self.skip(&ratchet_key, &chain_key, previous_chain_length)?;
+        }
+        let (root_key, chain_key) = self.root_key.create_chain(dh_output, self.info.root);
+        self.root_key = root_key;
+        self.receiving = Some((*their_ratchet_key, chain_key));
+        Ok(())
+    }
+
+    /// Derive the message keys of the next message of the sending chain.
+    ///
+    /// # Errors
+    /// Returns [`RatchetError::NoSendingChain`] if no sending chain has been started and
+    /// [`RatchetError::ChainExhausted`] if the message counter is exhausted.
+    pub fn sending_keys(&mut self) -> Result<MessageKeys, RatchetError> {
+        let chain_key = self.sending.as_mut().ok_or(RatchetError::NoSendingChain)?;
+        let next = chain_key
+            .next_chain_key()
+            .ok_or(RatchetError::ChainExhausted)?;
+        let keys = chain_key.message_keys(self.info.message_keys);
+        *chain_key = next;
+        Ok(keys)
+    }
+
+    /// Derive the message keys of the message with index `counter` of the receiving
+    /// chain of `their_ratchet_key`.
+    ///
+    /// The message keys of skipped messages are stored and returned when the skipped
+    /// message is received. Messages with a new ratchet key must be preceded by a call
+    /// to [`ratchet_receive`][Self::ratchet_receive].
+    ///
+    /// # Errors
+    /// Returns an error if the ratchet key is unknown, the message keys have already
+    /// been returned, or more than `MAX_SKIP` message keys would be skipped.
+    pub fn receiving_keys(
+        &mut self,
+        their_ratchet_key: &[u8; PUBLIC_KEY_SIZE],
+        counter: u32,
+    ) -> Result<MessageKeys, RatchetError> {
+        let skipped = self.skipped.iter_mut().find_map(|entry| {
+            entry.take_if(|skipped| {
+                skipped.ratchet_key == *their_ratchet_key && skipped.keys.counter == counter
+            })
+        });
+        if let Some(skipped) = skipped {
+            return Ok(skipped.keys);
+        }
+
+        let Some((ratchet_key, chain_key)) = self
+            .receiving
+            .clone()
+            .filter(|(ratchet_key, _)| ratchet_key == their_ratchet_key)
+        else {
+            return Err(RatchetError::UnknownRatchetKey);
+        };
+        if counter < chain_key.index() {
+            return Err(RatchetError::DuplicateMessage);
+        }
+
+        let chain_key = self.skip(&ratchet_key, &chain_key, counter)?;
+        let next = chain_key
+            .next_chain_key()
+            .ok_or(RatchetError::ChainExhausted)?;
+        let keys = chain_key.message_keys(self.info.message_keys);
+        self.receiving = Some((ratchet_key, next));
+        Ok(keys)
+    }
+}
+
+impl<D, const MAX_SKIP: usize> fmt::Debug for RatchetState<D, MAX_SKIP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RatchetState")
+            .field("sending", &self.sending)
+            .field(
+                "receiving",
+                &self.receiving.as_ref().map(|(_, chain)| chain),
+            )
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,136 @@
+//! TLS 1.3 `HKDF-Expand-Label` and `Derive-Secret` functions and key schedule.
+//!
+//! See [RFC 8446 Section 7.1](https://www.rfc-editor.org/rfc/rfc8446#section-7.1).
+//!
+//! The key schedule is represented by the [`EarlySecret`], [`HandshakeSecret`] and
+//! [`MasterSecret`] stages. Each stage can only be obtained from the previous one and
+//! every derived secret takes the transcript hash type it is defined over:
+//!
+//! ```
+//! use hkdf::tls13::{EarlySecret, ServerFinishedHash, ServerHelloHash};
+//! use sha2::{Digest, Sha256};
+//!
+//! let early = EarlySecret::<Sha256>::new(None);
+//! let handshake = early.into_handshake_secret(Some(b"ecdhe shared secret"));
+//!
+//! let hash = ServerHelloHash::after_server_hello(Sha256::digest(b"ClientHello...ServerHello"));
+//! let client_hs = handshake.client_handshake_traffic_secret(&hash);
+//! let mut key = [0u8; 16];
+//! client_hs.traffic_key(&mut key).unwrap();
+//!
+//! let master = handshake.into_master_secret();
+//! let hash =
+//!     ServerFinishedHash::after_server_finished(Sha256::digest(b"ClientHello...server Finished"));
+//! let client_ap = master.client_application_traffic_secret(&hash);
+//! let client_ap_1 = client_ap.next_generation();
+//! ```
+
+use crate::{GenericHkdf, HmacImpl, InvalidLength};
+use hmac::digest::Output;
+
+mod key_schedule;
+
+pub use key_schedule::{
+    BinderKey, ClientFinishedHash, ClientHelloHash, EarlySecret, HandshakeSecret, ImportedIdentity,
+    MasterSecret, PskKind, ServerFinishedHash, ServerHelloHash, TrafficSecret, import_psk,
+    resumption_psk,
+};
+
+/// Label prefix used by TLS 1.3 ([RFC 8446]).
+///
+/// [RFC 8446]: https://www.rfc-editor.org/rfc/rfc8446#section-7.1
+pub const TLS13_LABEL_PREFIX: &[u8] = b"tls13 ";
+
+/// Label prefix used by DTLS 1.3 ([RFC 9147]).
+///
+/// [RFC 9147]: https://www.rfc-editor.org/rfc/rfc9147#section-5.9
+pub const DTLS13_LABEL_PREFIX: &[u8] = b"dtls13";
+
+impl<H: HmacImpl> GenericHkdf<H> {
+    /// The TLS 1.3 `HKDF-Expand-Label` function using the `"tls13 "` prefix.
+    ///
+    /// The length of `okm` is used as the `length` field of `HkdfLabel`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the prefixed label is not between 7 and 255 bytes
+    /// long, if `context` is longer than 255 bytes, or if `okm` is too large.
+    pub fn expand_label(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        self.expand_label_with_prefix(TLS13_LABEL_PREFIX, label, context, okm)
+    }
+
+    /// The TLS 1.3 `HKDF-Expand-Label` function using a custom label prefix,
+    /// e.g. [`DTLS13_LABEL_PREFIX`].
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the prefixed label is not between 7 and 255 bytes
+    /// long, if `context` is longer than 255 bytes, or if `okm` is too large.
+    pub fn expand_label_with_prefix(
+        &self,
+        prefix: &[u8],
+        label: &[u8],
+        context: &[u8],
+        okm: &mut [u8],
+    ) -> Result<(), InvalidLength> {
+        // struct {
+        //     uint16 length = Length;
+        //     opaque label<7..255> = "tls13 " + Label;
+        //     opaque context<0..255> = Context;
+        // } HkdfLabel;
+        let length = u16::try_from(okm.len()).map_err(|_| InvalidLength)?;
+        let label_len = u8::try_from(prefix.len() + label.len()).map_err(|_| InvalidLength)?;
+        if label_len < 7 {
+            return Err(InvalidLength);
+        }
+        let context_len = u8::try_from(context.len()).map_err(|_| InvalidLength)?;
+
+        self.expand_multi_info(
+            &[
+                &length.to_be_bytes(),
+                &[label_len],
+                prefix,
+                label,
+                &[context_len],
+                context,
+            ],
+            okm,
+        )
+    }
+
+    /// The TLS 1.3 `Derive-Secret` function using the `"tls13 "` prefix.
+    ///
+    /// Unlike in RFC 8446, this method takes the already computed transcript hash
+    /// rather than the handshake messages.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the prefixed label is not between 7 and 255 bytes
+    /// long or if `transcript_hash` is longer than 255 bytes.
+    pub fn derive_secret(
+        &self,
+        label: &[u8],
+        transcript_hash: &[u8],
+    ) -> Result<Output<H>, InvalidLength> {
+        self.derive_secret_with_prefix(TLS13_LABEL_PREFIX, label, transcript_hash)
+    }
+
+    /// The TLS 1.3 `Derive-Secret` function using a custom label prefix,
+    /// e.g. [`DTLS13_LABEL_PREFIX`].
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the prefixed label is not between 7 and 255 bytes
+    /// long or if `transcript_hash` is longer than 255 bytes.
+    pub fn derive_secret_with_prefix(
+        &self,
+        prefix: &[u8],
+        label: &[u8],
+        transcript_hash: &[u8],
+    ) -> Result<Output<H>, InvalidLength> {
+        let mut secret = Output::<H>::default();
+        self.expand_label_with_prefix(prefix, label, transcript_hash, &mut secret)?;
+        Ok(secret)
+    }
+}
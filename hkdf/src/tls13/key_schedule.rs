@@ -0,0 +1,406 @@
+use crate::{Hkdf, InvalidLength, Secret};
+use core::fmt;
+use hmac::{
+    EagerHash, Hmac,
+    digest::{Digest, Output},
+};
+
+#[cfg(feature = "zeroize")]
+use hmac::digest::zeroize::Zeroize;
+
+/// Zero-filled IKM/salt used when no PSK or (EC)DHE input is available.
+fn zeros<D: EagerHash>() -> Output<Hmac<D>> {
+    Output::<Hmac<D>>::default()
+}
+
+/// `Derive-Secret(Secret, "derived", "")` used as the salt of the next extract step.
+fn derived_salt<D: EagerHash>(hkdf: &Hkdf<D>) -> Secret<Output<Hmac<D>>> {
+    let salt = hkdf
+        .derive_secret(b"derived", &D::digest([]))
+        .expect("label and context lengths are valid");
+    Secret::<Output<Hmac<D>>>::from(salt)
+}
+
+fn derive<D: EagerHash>(hkdf: &Hkdf<D>, label: &[u8], transcript_hash: &[u8]) -> Output<Hmac<D>> {
+    hkdf.derive_secret(label, transcript_hash)
+        .expect("label and context lengths are valid")
+}
+
+macro_rules! transcript_hash {
+    ($name:ident, $constructor:ident, $point:literal) => {
+        #[doc = concat!("Transcript hash of `", $point, "`.")]
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name<D: EagerHash>(Output<D>);
+
+        impl<D: EagerHash> $name<D> {
+            #[doc = concat!("Wrap the already computed transcript hash of `", $point, "`.")]
+            #[must_use]
+            pub fn $constructor(hash: Output<D>) -> Self {
+                Self(hash)
+            }
+        }
+
+        impl<D: EagerHash> AsRef<[u8]> for $name<D> {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+    };
+}
+
+transcript_hash!(ClientHelloHash, after_client_hello, "ClientHello");
+transcript_hash!(
+    ServerHelloHash,
+    after_server_hello,
+    "ClientHello...ServerHello"
+);
+transcript_hash!(
+    ServerFinishedHash,
+    after_server_finished,
+    "ClientHello...server Finished"
+);
+transcript_hash!(
+    ClientFinishedHash,
+    after_client_finished,
+    "ClientHello...client Finished"
+);
+
+/// Kind of the PSK used to compute the binder key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PskKind {
+    /// External PSK (`"ext binder"`).
+    External,
+    /// Resumption PSK (`"res binder"`).
+    Resumption,
+    /// Imported external PSK as defined in RFC 9258 (`"imp binder"`).
+    Imported,
+}
+
+impl PskKind {
+    fn binder_label(self) -> &'static [u8] {
+        match self {
+            PskKind::External => b"ext binder",
+            PskKind::Resumption => b"res binder",
+            PskKind::Imported => b"imp binder",
+        }
+    }
+}
+
+/// A traffic secret from which traffic keys and IVs are derived.
+#[derive(Clone)]
+pub struct TrafficSecret<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+}
+
+impl<D: EagerHash> TrafficSecret<D> {
+    /// Wrap an existing traffic secret.
+    #[must_use]
+    pub fn new(secret: Output<Hmac<D>>) -> Self {
+        Self { secret }
+    }
+
+    /// Get the raw traffic secret.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    fn hkdf(&self) -> Hkdf<D> {
+        Hkdf::<D>::from_prk(&self.secret).expect("secret has the hash output size")
+    }
+
+    /// Derive the traffic key (`HKDF-Expand-Label(Secret, "key", "", key_length)`).
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `key` is too large.
+    pub fn traffic_key(&self, key: &mut [u8]) -> Result<(), InvalidLength> {
+        self.hkdf().expand_label(b"key", &[], key)
+    }
+
+    /// Derive the traffic IV (`HKDF-Expand-Label(Secret, "iv", "", iv_length)`).
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `iv` is too large.
+    pub fn traffic_iv(&self, iv: &mut [u8]) -> Result<(), InvalidLength> {
+        self.hkdf().expand_label(b"iv", &[], iv)
+    }
+
+    /// Derive the key used to compute the `Finished` message.
+    #[must_use]
+    pub fn finished_key(&self) -> Output<Hmac<D>> {
+        finished_key(&self.hkdf())
+    }
+
+    /// Derive the next generation of this traffic secret as done on `KeyUpdate`.
+    #[must_use]
+    #[allow(
+        clippy::missing_panics_doc,
+        reason = "label and context lengths are valid"
+    )]
+    pub fn next_generation(&self) -> Self {
+        let mut secret = Output::<Hmac<D>>::default();
+        self.hkdf()
+            .expand_label(b"traffic upd", &[], &mut secret)
+            .expect("label and context lengths are valid");
+        Self { secret }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for TrafficSecret<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for TrafficSecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrafficSecret").finish_non_exhaustive()
+    }
+}
+
+fn finished_key<D: EagerHash>(hkdf: &Hkdf<D>) -> Output<Hmac<D>> {
+    let mut key = Output::<Hmac<D>>::default();
+    hkdf.expand_label(b"finished", &[], &mut key)
+        .expect("label and context lengths are valid");
+    key
+}
+
+/// The binder key used to compute PSK binders.
+#[derive(Clone)]
+pub struct BinderKey<D: EagerHash> {
+    secret: Output<Hmac<D>>,
+}
+
+impl<D: EagerHash> BinderKey<D> {
+    /// Get the raw binder key.
+    #[must_use]
+    pub fn as_bytes(&self) -> &Output<Hmac<D>> {
+        &self.secret
+    }
+
+    /// Derive the key used to compute the PSK binder value.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc, reason = "secret has the hash output size")]
+    pub fn finished_key(&self) -> Output<Hmac<D>> {
+        let hkdf = Hkdf::<D>::from_prk(&self.secret).expect("secret has the hash output size");
+        finished_key(&hkdf)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: EagerHash> Drop for BinderKey<D> {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for BinderKey<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinderKey").finish_non_exhaustive()
+    }
+}
+
+/// The first stage of the TLS 1.3 key schedule.
+#[derive(Clone)]
+pub struct EarlySecret<D: EagerHash> {
+    hkdf: Hkdf<D>,
+}
+
+impl<D: EagerHash> EarlySecret<D> {
+    /// Compute the Early Secret from an optional PSK.
+    ///
+    /// If `psk` is `None`, a string of zeros is used as required by RFC 8446.
+    #[must_use]
+    pub fn new(psk: Option<&[u8]>) -> Self {
+        let zeros = zeros::<D>();
+        let (_, hkdf) = Hkdf::<D>::extract(Some(&zeros), psk.unwrap_or(&zeros));
+        Self { hkdf }
+    }
+
+    /// Derive the binder key for the given PSK kind.
+    #[must_use]
+    pub fn binder_key(&self, kind: PskKind) -> BinderKey<D> {
+        let secret = derive(&self.hkdf, kind.binder_label(), &D::digest([]));
+        BinderKey { secret }
+    }
+
+    /// Derive `client_early_traffic_secret`.
+    #[must_use]
+    pub fn client_early_traffic_secret(&self, hash: &ClientHelloHash<D>) -> TrafficSecret<D> {
+        TrafficSecret::new(derive(&self.hkdf, b"c e traffic", &hash.0))
+    }
+
+    /// Derive `early_exporter_master_secret`.
+    #[must_use]
+    pub fn early_exporter_master_secret(&self, hash: &ClientHelloHash<D>) -> Output<Hmac<D>> {
+        derive(&self.hkdf, b"e exp master", &hash.0)
+    }
+
+    /// Advance to the Handshake Secret using the optional (EC)DHE shared secret.
+    ///
+    /// If `shared_secret` is `None`, a string of zeros is used as required by RFC 8446.
+    #[must_use]
+    pub fn into_handshake_secret(self, shared_secret: Option<&[u8]>) -> HandshakeSecret<D> {
+        let salt = derived_salt(&self.hkdf);
+        let zeros = zeros::<D>();
+        let (_, hkdf) = Hkdf::<D>::extract(Some(&salt), shared_secret.unwrap_or(&zeros));
+        HandshakeSecret { hkdf }
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for EarlySecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EarlySecret").finish_non_exhaustive()
+    }
+}
+
+/// The second stage of the TLS 1.3 key schedule.
+#[derive(Clone)]
+pub struct HandshakeSecret<D: EagerHash> {
+    hkdf: Hkdf<D>,
+}
+
+impl<D: EagerHash> HandshakeSecret<D> {
+    /// Derive `client_handshake_traffic_secret`.
+    #[must_use]
+    pub fn client_handshake_traffic_secret(&self, hash: &ServerHelloHash<D>) -> TrafficSecret<D> {
+        TrafficSecret::new(derive(&self.hkdf, b"c hs traffic", &hash.0))
+    }
+
+    /// Derive `server_handshake_traffic_secret`.
+    #[must_use]
+    pub fn server_handshake_traffic_secret(&self, hash: &ServerHelloHash<D>) -> TrafficSecret<D> {
+        TrafficSecret::new(derive(&self.hkdf, b"s hs traffic", &hash.0))
+    }
+
+    /// Advance to the Master Secret.
+    #[must_use]
+    pub fn into_master_secret(self) -> MasterSecret<D> {
+        let salt = derived_salt(&self.hkdf);
+        let (_, hkdf) = Hkdf::<D>::extract(Some(&salt), &zeros::<D>());
+        MasterSecret { hkdf }
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for HandshakeSecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandshakeSecret").finish_non_exhaustive()
+    }
+}
+
+/// The last stage of the TLS 1.3 key schedule.
+#[derive(Clone)]
+pub struct MasterSecret<D: EagerHash> {
+    hkdf: Hkdf<D>,
+}
+
+impl<D: EagerHash> MasterSecret<D> {
+    /// Derive `client_application_traffic_secret_0`.
+    #[must_use]
+    pub fn client_application_traffic_secret(
+        &self,
+        hash: &ServerFinishedHash<D>,
+    ) -> TrafficSecret<D> {
+        TrafficSecret::new(derive(&self.hkdf, b"c ap traffic", &hash.0))
+    }
+
+    /// Derive `server_application_traffic_secret_0`.
+    #[must_use]
+    pub fn server_application_traffic_secret(
+        &self,
+        hash: &ServerFinishedHash<D>,
+    ) -> TrafficSecret<D> {
+        TrafficSecret::new(derive(&self.hkdf, b"s ap traffic", &hash.0))
+    }
+
+    /// Derive `exporter_master_secret`.
+    #[must_use]
+    pub fn exporter_master_secret(&self, hash: &ServerFinishedHash<D>) -> Output<Hmac<D>> {
+        derive(&self.hkdf, b"exp master", &hash.0)
+    }
+
+    /// Derive `resumption_master_secret`.
+    #[must_use]
+    pub fn resumption_master_secret(&self, hash: &ClientFinishedHash<D>) -> Output<Hmac<D>> {
+        derive(&self.hkdf, b"res master", &hash.0)
+    }
+}
+
+impl<D: EagerHash> fmt::Debug for MasterSecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterSecret").finish_non_exhaustive()
+    }
+}
+
+/// Derive the PSK for a resumption ticket from `resumption_master_secret` and `ticket_nonce`.
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `ticket_nonce` is longer than 255 bytes.
+#[allow(clippy::missing_panics_doc, reason = "secret has the hash output size")]
+pub fn resumption_psk<D: EagerHash>(
+    resumption_master_secret: &Output<Hmac<D>>,
+    ticket_nonce: &[u8],
+) -> Result<Output<Hmac<D>>, InvalidLength> {
+    let hkdf =
+        Hkdf::<D>::from_prk(resumption_master_secret).expect("secret has the hash output size");
+    let mut psk = Output::<Hmac<D>>::default();
+    hkdf.expand_label(b"resumption", ticket_nonce, &mut psk)?;
+    Ok(psk)
+}
+
+/// `ImportedIdentity` structure as defined in RFC 9258.
+#[derive(Clone, Copy, Debug)]
+pub struct ImportedIdentity<'a> {
+    /// Identity of the external PSK.
+    pub external_identity: &'a [u8],
+    /// Context the PSK is imported for.
+    pub context: &'a [u8],
+    /// Target protocol version (e.g. `0x0304` for TLS 1.3).
+    pub target_protocol: u16,
+    /// Target KDF identifier from the IANA "TLS KDF Identifiers" registry.
+    pub target_kdf: u16,
+}
+
+impl ImportedIdentity<'_> {
+    /// Compute the hash of the serialized `ImportedIdentity` structure.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if `external_identity` is empty or if either
+    /// `external_identity` or `context` is longer than 65535 bytes.
+    pub fn hash<D: Digest>(&self) -> Result<Output<D>, InvalidLength> {
+        let identity_len =
+            u16::try_from(self.external_identity.len()).map_err(|_| InvalidLength)?;
+        if identity_len == 0 {
+            return Err(InvalidLength);
+        }
+        let context_len = u16::try_from(self.context.len()).map_err(|_| InvalidLength)?;
+
+        let mut hasher = D::new();
+        hasher.update(identity_len.to_be_bytes());
+        hasher.update(self.external_identity);
+        hasher.update(context_len.to_be_bytes());
+        hasher.update(self.context);
+        hasher.update(self.target_protocol.to_be_bytes());
+        hasher.update(self.target_kdf.to_be_bytes());
+        Ok(hasher.finalize())
+    }
+}
+
+/// Import an external PSK as defined in RFC 9258.
+///
+/// `D` is the hash function associated with `epsk` (SHA-256 by default) and the
+/// length of `ipsk` must match the output size of the target KDF.
+///
+/// The resulting PSK should be used with [`PskKind::Imported`].
+///
+/// # Errors
+/// Returns [`InvalidLength`] if `identity` can not be serialized or if `ipsk` is too large.
+pub fn import_psk<D: EagerHash>(
+    epsk: &[u8],
+    identity: &ImportedIdentity<'_>,
+    ipsk: &mut [u8],
+) -> Result<(), InvalidLength> {
+    let (_, epskx) = Hkdf::<D>::extract(Some(&zeros::<D>()), epsk);
+    epskx.expand_label(b"derived psk", &identity.hash::<D>()?, ipsk)
+}
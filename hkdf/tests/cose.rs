@@ -0,0 +1,128 @@
+//! `COSE_KDF_Context` tests.
+#![cfg(feature = "cose")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::Hkdf;
+use hkdf::cose::{AlgorithmId, KdfContext, Nonce, PartyInfo};
+use hkdf::hmac::Hmac;
+use sha2::{Sha256, Sha512};
+
+/// Context of the `ECDH-ES + HKDF-256` example with A128GCM of the COSE WG examples
+/// (`ecdh-direct-examples/p256-hkdf-256-01.json`).
+const ECDH_ES_CONTEXT: &[u8] = &hex!("840183F6F6F683F6F6F682188044A1013818");
+
+fn ecdh_es_context() -> KdfContext<'static> {
+    KdfContext {
+        protected: &hex!("A1013818"),
+        ..KdfContext::new(AlgorithmId::Int(1))
+    }
+}
+
+#[test]
+fn encode_ecdh_es() {
+    let context = ecdh_es_context();
+    assert_eq!(context.encoded_len(16).unwrap(), ECDH_ES_CONTEXT.len());
+    let mut buf = [0u8; 64];
+    let len = context.encode(16, &mut buf).unwrap();
+    assert_eq!(&buf[..len], ECDH_ES_CONTEXT);
+
+    assert!(context.encode(16, &mut buf[..len - 1]).is_err());
+    assert!(context.encode(16, &mut buf[..len]).is_ok());
+}
+
+#[test]
+fn encode_all_fields() {
+    let context = KdfContext {
+        algorithm_id: AlgorithmId::Text("AES-CCM-16-128/64"),
+        party_u: PartyInfo {
+            identity: Some(b"client"),
+            nonce: Nonce::Int(-1000),
+            other: Some(&[0x01]),
+        },
+        party_v: PartyInfo {
+            identity: Some(&[0xAA; 24]),
+            nonce: Nonce::Bytes(&[0x02; 8]),
+            other: None,
+        },
+        protected: &[],
+        supp_pub_other: Some(b"pub"),
+        supp_priv: Some(b"priv"),
+    };
+
+    let mut buf = [0u8; 128];
+    let len = context.encode(32, &mut buf).unwrap();
+    assert_eq!(context.encoded_len(32).unwrap(), len);
+    assert_eq!(
+        &buf[..len],
+        hex!(
+            "85"
+            "71" "4145532D43434D2D31362D3132382F3634"
+            "83" "46636C69656E74" "3903E7" "4101"
+            "83"
+            "5818" "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+            "480202020202020202"
+            "F6"
+            "83" "190100" "40" "43707562"
+            "4470726976"
+        ),
+    );
+}
+
+#[test]
+fn expand() {
+    let context = ecdh_es_context();
+    let secret = [0x0B; 32];
+    let salt = [0x0C; 16];
+
+    let mut expected = [0u8; 16];
+    Hkdf::<Sha256>::new(Some(&salt), &secret)
+        .expand(ECDH_ES_CONTEXT, &mut expected)
+        .unwrap();
+
+    let mut okm = [0u8; 16];
+    context
+        .derive::<Hmac<Sha256>>(Some(&salt), &secret, &mut okm)
+        .unwrap();
+    assert_eq!(okm, expected);
+
+    let hkdf = Hkdf::<Sha512>::new(None, &secret);
+    let mut okm = [0u8; 32];
+    context.expand(&hkdf, &mut okm).unwrap();
+    let mut info = [0u8; 32];
+    let len = context.encode(okm.len(), &mut info).unwrap();
+    assert_eq!(info[len - 8..len - 5], [0x19, 0x01, 0x00]);
+    let mut expected = [0u8; 32];
+    hkdf.expand(&info[..len], &mut expected).unwrap();
+    assert_eq!(okm, expected);
+
+    let mut okm = [0u8; 255 * 64 + 1];
+    assert!(context.expand(&hkdf, &mut okm).is_err());
+}
+
+#[test]
+fn integers() {
+    let mut buf = [0u8; 64];
+    for (id, encoding) in [
+        (0, &hex!("00")[..]),
+        (23, &hex!("17")),
+        (24, &hex!("1818")),
+        (255, &hex!("18FF")),
+        (256, &hex!("190100")),
+        (65536, &hex!("1A00010000")),
+        (i64::MAX, &hex!("1B7FFFFFFFFFFFFFFF")),
+        (-1, &hex!("20")),
+        (-25, &hex!("3818")),
+        (i64::MIN, &hex!("3B7FFFFFFFFFFFFFFF")),
+    ] {
+        let len = KdfContext::new(AlgorithmId::Int(id))
+            .encode(0, &mut buf)
+            .unwrap();
+        assert_eq!(buf[0], 0x84);
+        assert_eq!(&buf[1..1 + encoding.len()], encoding);
+        assert_eq!(
+            &buf[1 + encoding.len()..len],
+            hex!("83F6F6F683F6F6F6820040")
+        );
+    }
+}
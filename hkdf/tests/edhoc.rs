@@ -0,0 +1,195 @@
+//! EDHOC key schedule tests using the RFC 9529 Section 3 trace (method 3, cipher suite 2).
+//!
+//! `K_3`, `IV_3` and `KEY_UPDATE` were computed from values of the trace with the HKDF-Expand
+//! of OpenSSL 3.5, with `info` encoded by hand as the CBOR sequence `(label, context, length)`.
+//! The same command reproduces `K_4` and `IV_4` of the trace:
+//!
+//! ```text
+//! openssl kdf -binary -keylen <length> -kdfopt digest:SHA256 -kdfopt mode:EXPAND_ONLY \
+//!     -kdfopt hexkey:<prk> -kdfopt hexinfo:<info> HKDF
+//! ```
+#![cfg(feature = "edhoc")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::Hkdf;
+use hkdf::edhoc::{Info, Prk2e, PrkExporter, PrkOut, edhoc_extract};
+use hkdf::hmac::Hmac;
+use p256::elliptic_curve::point::AffineCoordinates;
+use p256::{PublicKey, SecretKey};
+use sha2::Sha256;
+
+const G_XY: [u8; 32] = hex!("2f0cb7e860ba538fbf5c8bded009f6259b4b628fe1eb7dbe9378e5ecf7a824ba");
+const X: [u8; 32] = hex!("368ec1f69aeb659ba37d5a8d45b21bdc0299dceaa8ef235f3ca42ce3530f9525");
+const G_Y: [u8; 32] = hex!("419701d7f00a26c2dc587a36dd752549f33763c893422c8ea0f955a13a4ff5d5");
+const SK_I: [u8; 32] = hex!("fb13adeb6518cee5f88417660841142e830a81fe334380a953406a1305e8706b");
+const G_R: [u8; 32] = hex!("bbc34960526ea4d32e940cad2a234148ddc21791a12afbcbac93622046dd44f0");
+
+const TH_2: [u8; 32] = hex!("356efd53771425e008f3fe3a86c83ff4c6b16e57028ff39d5236c182b202084b");
+const TH_3: [u8; 32] = hex!("adaf67a78a4bcc91e018f8882762a722000b2507039df0bc1bbf0c161bb3155c");
+const TH_4: [u8; 32] = hex!("c902b1e3a4326c93c5551f5f3aa6c5ecc0246806765612e52b5d99e6059d6b6e");
+
+const PRK_2E: [u8; 32] = hex!("5aa0d69f3e3d1e0c479f0b8a486690c9802630c3466b1dc92371c982563170b5");
+const KEYSTREAM_2: [u8; 11] = hex!("bf50e9e7bad0bb68173399");
+const SALT_3E2M: [u8; 32] =
+    hex!("af4e103a47cb3cf32570d5c25ad27732bd8d8178e9a69d061c31a27f8e3ca926");
+const PRK_3E2M: [u8; 32] = hex!("0ca3d3398296b3c03900987620c11f6fce70781c1d1219720f9ec08c122d8434");
+const CONTEXT_2: [u8; 134] = hex!(
+    "27a10441325820356efd53771425e008f3fe3a86c83ff4c6b16e57028ff39d5236c182b202084ba2026b6578616d"
+    "706c652e65647508a101a501020241322001215820bbc34960526ea4d32e940cad2a234148ddc21791a12afbcbac"
+    "93622046dd44f02258204519e257236b2a0ce2023f0931f1f386ca7afda64fcde0108c224c51eabf6072"
+);
+const MAC_2: [u8; 8] = hex!("0943305c899f5c54");
+// info = (3, h'TH_3', 16) and (4, h'TH_3', 13), with PRK_3e2m
+const K_3: [u8; 16] = hex!("8e7a30042000f7900e8174131f75f3ed");
+const IV_3: [u8; 13] = hex!("6d8300c1e23b56153ae70ee457");
+const SALT_4E3M: [u8; 32] =
+    hex!("cfddf9515a7e46e7b4dbff31cbd56cd04ba332250de9ea5de1caf9f6d13914a7");
+const PRK_4E3M: [u8; 32] = hex!("81cc8a298e357044e3c466bb5c0a1e507e01d49238aeba138df94635407c0ff7");
+const ID_CRED_I: [u8; 4] = hex!("a104412b");
+const CRED_I: [u8; 107] = hex!(
+    "a2027734322d35302d33312d46462d45462d33372d33322d333908a101a5010202412b2001215820ac75e9ece3e5"
+    "0bfc8ed60399889522405c47bf16df96660a41298cb4307f7eb62258206e5de611388a4b8a8211334ac7d37ecb52"
+    "a387d257e6db3c2a93df21ff3affc8"
+);
+const MAC_3: [u8; 8] = hex!("623c91df41e34c2f");
+const K_4: [u8; 16] = hex!("d3c77872b6eeb508911bdbd308b2e6a0");
+const IV_4: [u8; 13] = hex!("04ff0f44456e96e217853c3601");
+const PRK_OUT: [u8; 32] = hex!("2c71afc1a9338a940bb3529ca734b886f30d1aba0b4dc51beeaeabdfea9ecbf8");
+const PRK_EXPORTER: [u8; 32] =
+    hex!("e14d06699cee248c5a04bf9227bbcd4ce394de7dcb56db43555474171e6446db");
+const OSCORE_MASTER_SECRET: [u8; 16] = hex!("f9868f6a3aca78a05d1485b35030b162");
+const OSCORE_MASTER_SALT: [u8; 8] = hex!("ada24c7dbfc85eeb");
+// info = (11, h'd6be169602b8bceaa01158fdb820890c', 32), with PRK_out
+const KEY_UPDATE_CONTEXT: [u8; 16] = hex!("d6be169602b8bceaa01158fdb820890c");
+const KEY_UPDATE: [u8; 32] =
+    hex!("b68f660f287b1d321ef804647d53a8e4e6abd4abbc7bafddd4536ababe614880");
+
+/// P-256 ECDH with the x-coordinate `public` of a public key.
+fn dh(secret: &[u8; 32], public: &[u8; 32]) -> [u8; 32] {
+    let mut sec1 = [0x02; 33];
+    sec1[1..].copy_from_slice(public);
+    let secret = SecretKey::from_slice(secret).unwrap();
+    let public = PublicKey::from_sec1_bytes(&sec1).unwrap();
+    let shared = public.to_projective() * *secret.to_nonzero_scalar();
+    shared.to_affine().x().into()
+}
+
+#[test]
+fn trace_2() {
+    let prk_2e = Prk2e::<Sha256>::derive(&TH_2, &G_XY);
+    assert_eq!(prk_2e.as_bytes()[..], PRK_2E);
+    let mut keystream_2 = [0u8; 11];
+    prk_2e.keystream_2(&TH_2, &mut keystream_2).unwrap();
+    assert_eq!(keystream_2, KEYSTREAM_2);
+    assert_eq!(prk_2e.salt_3e2m(&TH_2)[..], SALT_3E2M);
+
+    let g_rx = dh(&X, &G_R);
+    let prk_3e2m = prk_2e.prk_3e2m(&TH_2, Some(&g_rx));
+    assert_eq!(prk_3e2m.as_bytes()[..], PRK_3E2M);
+    let mut mac_2 = [0u8; 8];
+    prk_3e2m.mac_2(&CONTEXT_2, &mut mac_2).unwrap();
+    assert_eq!(mac_2, MAC_2);
+    assert_eq!(prk_3e2m.salt_4e3m(&TH_3)[..], SALT_4E3M);
+    let mut k_3 = [0u8; 16];
+    let mut iv_3 = [0u8; 13];
+    prk_3e2m.k_3(&TH_3, &mut k_3).unwrap();
+    prk_3e2m.iv_3(&TH_3, &mut iv_3).unwrap();
+    assert_eq!(k_3, K_3);
+    assert_eq!(iv_3, IV_3);
+
+    let g_iy = dh(&SK_I, &G_Y);
+    let prk_4e3m = prk_3e2m.prk_4e3m(&TH_3, Some(&g_iy));
+    assert_eq!(prk_4e3m.as_bytes()[..], PRK_4E3M);
+    let context_3 = [&ID_CRED_I[..], &hex!("5820"), &TH_3, &CRED_I].concat();
+    let mut mac_3 = [0u8; 8];
+    prk_4e3m.mac_3(&context_3, &mut mac_3).unwrap();
+    assert_eq!(mac_3, MAC_3);
+
+    let mut k_4 = [0u8; 16];
+    let mut iv_4 = [0u8; 13];
+    prk_4e3m.k_4(&TH_4, &mut k_4).unwrap();
+    prk_4e3m.iv_4(&TH_4, &mut iv_4).unwrap();
+    assert_eq!(k_4, K_4);
+    assert_eq!(iv_4, IV_4);
+
+    let prk_out = prk_4e3m.prk_out(&TH_4);
+    assert_eq!(prk_out.as_bytes()[..], PRK_OUT);
+    let prk_exporter = prk_out.prk_exporter();
+    assert_eq!(prk_exporter.as_bytes()[..], PRK_EXPORTER);
+
+    let mut master_secret = [0u8; 16];
+    let mut master_salt = [0u8; 8];
+    prk_exporter
+        .oscore_master_secret(&mut master_secret)
+        .unwrap();
+    prk_exporter.oscore_master_salt(&mut master_salt).unwrap();
+    assert_eq!(master_secret, OSCORE_MASTER_SECRET);
+    assert_eq!(master_salt, OSCORE_MASTER_SALT);
+}
+
+#[test]
+fn signature_authentication() {
+    // without static DH keys the PRKs are not changed
+    let prk_2e = Prk2e::<Sha256>::new(PRK_2E.into());
+    let prk_3e2m = prk_2e.prk_3e2m(&TH_2, None);
+    assert_eq!(prk_3e2m.as_bytes()[..], PRK_2E);
+    let prk_4e3m = prk_3e2m.prk_4e3m(&TH_3, None);
+    assert_eq!(prk_4e3m.as_bytes()[..], PRK_2E);
+}
+
+#[test]
+fn key_update() {
+    let prk_out = PrkOut::<Sha256>::new(PRK_OUT.into());
+    let context = KEY_UPDATE_CONTEXT;
+    let updated = prk_out.key_update(&context);
+    assert_eq!(updated.as_bytes()[..], KEY_UPDATE);
+    assert_ne!(updated.prk_exporter().as_bytes()[..], PRK_EXPORTER);
+
+    // the update is deterministic, so both peers derive the same keys
+    let mut secret_1 = [0u8; 16];
+    let mut secret_2 = [0u8; 16];
+    updated
+        .prk_exporter()
+        .oscore_master_secret(&mut secret_1)
+        .unwrap();
+    prk_out
+        .key_update(&context)
+        .prk_exporter()
+        .oscore_master_secret(&mut secret_2)
+        .unwrap();
+    assert_eq!(secret_1, secret_2);
+    assert_ne!(secret_1, OSCORE_MASTER_SECRET);
+}
+
+#[test]
+fn kdf() {
+    // EDHOC_Extract is HKDF-Extract
+    let (prk, _) = edhoc_extract::<Hmac<Sha256>>(&TH_2, &G_XY);
+    assert_eq!(prk[..], PRK_2E);
+
+    // info = (0, h'TH_2', 11)
+    let mut info = [0u8; 64];
+    let len = Info::new(0, &TH_2).encode(11, &mut info).unwrap();
+    assert_eq!(info[..3], hex!("005820"));
+    assert_eq!(info[3..len - 1], TH_2);
+    assert_eq!(info[len - 1], 0x0b);
+    assert!(
+        Info::new(0, &TH_2)
+            .encode(11, &mut info[..len - 1])
+            .is_err()
+    );
+
+    let hkdf = Hkdf::<Sha256>::from_prk(&PRK_2E).unwrap();
+    let mut keystream_2 = [0u8; 11];
+    hkdf.expand(&info[..len], &mut keystream_2).unwrap();
+    assert_eq!(keystream_2, KEYSTREAM_2);
+
+    // negative and large labels and lengths
+    let len = Info::new(-24, &[]).encode(300, &mut info).unwrap();
+    assert_eq!(info[..len], hex!("374019012c"));
+
+    let exporter = PrkExporter::<Sha256>::new(PRK_EXPORTER.into());
+    let mut out = [0u8; 255 * 32 + 1];
+    assert!(exporter.exporter(2, &[], &mut out).is_err());
+}
@@ -0,0 +1,303 @@
+//! `HMAC_DRBG` tests, using test vectors of the NIST CAVP `HMAC_DRBG.rsp` files
+//! (`drbgvectors_no_reseed` and `drbgvectors_pr_false`).
+#![cfg(feature = "hmac-drbg")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::HmacImpl;
+use hkdf::hmac_drbg::{Error, HmacDrbg, MAX_REQUEST_SIZE};
+use hkdf::rand_core::{Rng, SeedableRng, TryRng, UnwrapErr};
+use hmac::Hmac;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+type HmacDrbgSha256 = HmacDrbg<Hmac<Sha256>>;
+
+/// Test vector of `HMAC_DRBG.rsp`.
+struct Vector {
+    entropy_input: &'static [u8],
+    nonce: &'static [u8],
+    personalization_string: &'static [u8],
+    /// `EntropyInputReseed` and `AdditionalInputReseed` of `drbgvectors_pr_false`.
+    reseed: Option<(&'static [u8], &'static [u8])>,
+    additional_input: [&'static [u8]; 2],
+    returned_bits: &'static [u8],
+}
+
+impl Vector {
+    /// Instantiates the DRBG, reseeds it if required and compares the output of the second
+    /// call of the generate function with the returned bits.
+    fn check<H: HmacImpl>(&self) {
+        let mut drbg =
+            HmacDrbg::<H>::new(self.entropy_input, self.nonce, self.personalization_string);
+        if let Some((entropy_input, additional_input)) = self.reseed {
+            drbg.reseed(entropy_input, additional_input);
+        }
+        let mut out = vec![0u8; self.returned_bits.len()];
+        for additional_input in self.additional_input {
+            drbg.generate(&mut out, additional_input).unwrap();
+        }
+        assert_eq!(out, self.returned_bits);
+    }
+}
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 0]`, `[AdditionalInputLen = 0]`, `COUNT = 0`.
+static SHA256_NO_RESEED_0_0: Vector = Vector {
+    entropy_input: &hex!("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"),
+    nonce: &hex!("659ba96c601dc69fc902940805ec0ca8"),
+    personalization_string: &[],
+    reseed: None,
+    additional_input: [&[], &[]],
+    returned_bits: &hex!(
+        "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89"
+        "d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1"
+        "07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668"
+        "961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8"
+    ),
+};
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 256]`, `[AdditionalInputLen = 256]`, `COUNT = 0`.
+static SHA256_NO_RESEED_256_256: Vector = Vector {
+    entropy_input: &hex!("5d3286bc53a258a53ba781e2c4dcd79a790e43bbe0e89fb3eed39086be34174b"),
+    nonce: &hex!("c5422294b7318952ace7055ab7570abf"),
+    personalization_string: &hex!(
+        "2dba094d008e150d51c4135bb2f03dcde9cbf3468a12908a1b025c120c985b9d"
+    ),
+    reseed: None,
+    additional_input: [
+        &hex!("793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6"),
+        &hex!("2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530"),
+    ],
+    returned_bits: &hex!(
+        "d04678198ae7e1aeb435b45291458ffde0891560748b43330eaf866b5a6385e7"
+        "4c6fa5a5a44bdb284d436e98d244018d6acedcdfa2e9f499d8089e4db86ae89a"
+        "6ab2d19cb705e2f048f97fb597f04106a1fa6a1416ad3d859118e079a0c319eb"
+        "95686f4cbcce3b5101c7a0b010ef029c4ef6d06cdfac97efb9773891688c37cf"
+    ),
+};
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 0]`, `[AdditionalInputLen = 0]`, `COUNT = 0`.
+static SHA256_PR_FALSE_0_0: Vector = Vector {
+    entropy_input: &hex!("06032cd5eed33f39265f49ecb142c511da9aff2af71203bffaf34a9ca5bd9c0d"),
+    nonce: &hex!("0e66f71edc43e42a45ad3c6fc6cdc4df"),
+    personalization_string: &[],
+    reseed: Some((
+        &hex!("01920a4e669ed3a85ae8a33b35a74ad7fb2a6bb4cf395ce00334a9c9a5a5d552"),
+        &[],
+    )),
+    additional_input: [&[], &[]],
+    returned_bits: &hex!(
+        "76fc79fe9b50beccc991a11b5635783a83536add03c157fb30645e611c2898bb"
+        "2b1bc215000209208cd506cb28da2a51bdb03826aaf2bd2335d576d519160842"
+        "e7158ad0949d1a9ec3e66ea1b1a064b005de914eac2e9d4f2d72a8616a802254"
+        "22918250ff66a41bd2f864a6a38cc5b6499dc43f7f2bd09e1e0f8f5885935124"
+    ),
+};
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 0]`, `[AdditionalInputLen = 256]`, `COUNT = 0`.
+static SHA256_PR_FALSE_0_256: Vector = Vector {
+    entropy_input: &hex!("05ac9fc4c62a02e3f90840da5616218c6de5743d66b8e0fbf833759c5928b53d"),
+    nonce: &hex!("2b89a17904922ed8f017a63044848545"),
+    personalization_string: &[],
+    reseed: Some((
+        &hex!("2791126b8b52ee1fd9392a0a13e0083bed4186dc649b739607ac70ec8dcecf9b"),
+        &hex!("43bac13bae715092cf7eb280a2e10a962faf7233c41412f69bc74a35a584e54c"),
+    )),
+    additional_input: [
+        &hex!("3f2fed4b68d506ecefa21f3f5bb907beb0f17dbc30f6ffbba5e5861408c53a1e"),
+        &hex!("529030df50f410985fde068df82b935ec23d839cb4b269414c0ede6cffea5b68"),
+    ],
+    returned_bits: &hex!(
+        "02ddff5173da2fcffa10215b030d660d61179e61ecc22609b1151a75f1cbcbb4"
+        "363c3a89299b4b63aca5e581e73c860491010aa35de3337cc6c09ebec8c91a62"
+        "87586f3a74d9694b462d2720ea2e11bbd02af33adefb4a16e6b370fa0effd57d"
+        "607547bdcfbb7831f54de7073ad2a7da987a0016a82fa958779a168674b56524"
+    ),
+};
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 256]`, `[AdditionalInputLen = 0]`, `COUNT = 0`.
+static SHA256_PR_FALSE_256_0: Vector = Vector {
+    entropy_input: &hex!("fa0ee1fe39c7c390aa94159d0de97564342b591777f3e5f6a4ba2aea342ec840"),
+    nonce: &hex!("dd0820655cb2ffdb0da9e9310a67c9e5"),
+    personalization_string: &hex!(
+        "f2e58fe60a3afc59dad37595415ffd318ccf69d67780f6fa0797dc9aa43e144c"
+    ),
+    reseed: Some((
+        &hex!("e0629b6d7975ddfa96a399648740e60f1f9557dc58b3d7415f9ba9d4dbb501f6"),
+        &[],
+    )),
+    additional_input: [&[], &[]],
+    returned_bits: &hex!(
+        "f92d4cf99a535b20222a52a68db04c5af6f5ffc7b66a473a37a256bd8d298f9b"
+        "4aa4af7e8d181e02367903f93bdb744c6c2f3f3472626b40ce9bd6a70e7b8f93"
+        "992a16a76fab6b5f162568e08ee6c3e804aefd952ddd3acb791c50f2ad69e9a0"
+        "4028a06a9c01d3a62aca2aaf6efe69ed97a016213a2dd642b4886764072d9cbe"
+    ),
+};
+
+/// `[SHA-256]`, `[PersonalizationStringLen = 256]`, `[AdditionalInputLen = 256]`, `COUNT = 0`.
+static SHA256_PR_FALSE_256_256: Vector = Vector {
+    entropy_input: &hex!("cdb0d9117cc6dbc9ef9dcb06a97579841d72dc18b2d46a1cb61e314012bdf416"),
+    nonce: &hex!("d0c0d01d156016d0eb6b7e9c7c3c8da8"),
+    personalization_string: &hex!(
+        "6f0fb9eab3f9ea7ab0a719bfa879bf0aaed683307fda0c6d73ce018b6e34faaa"
+    ),
+    reseed: Some((
+        &hex!("8ec6f7d5a8e2e88f43986f70b86e050d07c84b931bcf18e601c5a3eee3064c82"),
+        &hex!("1ab4ca9014fa98a55938316de8ba5a68c629b0741bdd058c4d70c91cda5099b3"),
+    )),
+    additional_input: [
+        &hex!("16e2d0721b58d839a122852abd3bf2c942a31c84d82fca74211871880d7162ff"),
+        &hex!("53686f042a7b087d5d2eca0d2a96de131f275ed7151189f7ca52deaa78b79fb2"),
+    ],
+    returned_bits: &hex!(
+        "dda04a2ca7b8147af1548f5d086591ca4fd951a345ce52b3cd49d47e84aa31a1"
+        "83e31fbc42a1ff1d95afec7143c8008c97bc2a9c091df0a763848391f68cb4a3"
+        "66ad89857ac725a53b303ddea767be8dc5f605b1b95f6d24c9f06be65a973a08"
+        "9320b3cc42569dcfd4b92b62a993785b0301b3fc452445656fce22664827b88f"
+    ),
+};
+
+/// `[SHA-1]`, `[PersonalizationStringLen = 128]`, `[AdditionalInputLen = 128]`, `COUNT = 0`.
+static SHA1_PR_FALSE: Vector = Vector {
+    entropy_input: &hex!("03e7b41c95818eb0b667bfa8a175a824"),
+    nonce: &hex!("66a1e417a9b6b92f"),
+    personalization_string: &hex!("126dded5eb0bc81be37c10bcd9d5f793"),
+    reseed: Some((
+        &hex!("d17e98c2e50ee0db00d25c3364451e95"),
+        &hex!("dc596d188e2343802240bc7f5cc60516"),
+    )),
+    additional_input: [
+        &hex!("14c8ec10f5bdde6b9e75898d7f9f03d0"),
+        &hex!("31aa842afcc1daa94098241a87d6ddfc"),
+    ],
+    returned_bits: &hex!(
+        "4739b1bcf87404a2290829bd7a61f0b391a794c71c055c7cc513b28dcb5fdc88"
+        "645bc9cb490f41fab134c6b33ce9336571762754343961de671b02a47960b4b4"
+        "e23c5bfb87dcc19b260b3bcb921ae325"
+    ),
+};
+
+/// `[SHA-512]`, `[PersonalizationStringLen = 256]`, `[AdditionalInputLen = 256]`, `COUNT = 0`.
+static SHA512_PR_FALSE: Vector = Vector {
+    entropy_input: &hex!("da740cbc36057a8e282ae717fe7dfbb245e9e5d49908a0119c5dbcf0a1f2d5ab"),
+    nonce: &hex!("46561ff612217ba3ff91baa06d4b5440"),
+    personalization_string: &hex!(
+        "fc227293523ecb5b1e28c87863626627d958acc558a672b148ce19e2abd2dde4"
+    ),
+    reseed: Some((
+        &hex!("1d61d4d8a41c3254b92104fd555adae0569d1835bb52657ec7fbba0fe03579c5"),
+        &hex!("b9ed8e35ad018a375b61189c8d365b00507cb1b4510d21cac212356b5bbaa8b2"),
+    )),
+    additional_input: [
+        &hex!("b7998998eaf9e5d34e64ff7f03de765b31f407899d20535573e670c1b402c26a"),
+        &hex!("2089d49d63e0c4df58879d0cb1ba998e5b3d1a7786b785e7cf13ca5ea5e33cfd"),
+    ],
+    returned_bits: &hex!(
+        "5b70f3e4da95264233efbab155b828d4e231b67cc92757feca407cc9615a6608"
+        "71cb07ad1a2e9a99412feda8ee34dc9c57fa08d3f8225b30d29887d20907d123"
+        "30fffd14d1697ba0756d37491b0a8814106e46c8677d49d9157109c402ad0c24"
+        "7a2f50cd5d99e538c850b906937a05dbb8888d984bc77f6ca00b0e3bc97b16d6"
+        "d25814a54aa12143afddd8b2263690565d545f4137e593bb3ca88a37b0aadf79"
+        "726b95c61906257e6dc47acd5b6b7e4b534243b13c16ad5a0a1163c0099fce43"
+        "f428cd27c3e6463cf5e9a9621f4b3d0b3d4654316f4707675df39278d5783823"
+        "049477dcce8c57fdbd576711c91301e9bd6bb0d3e72dc46d480ed8f61fd63811"
+    ),
+};
+
+#[test]
+fn cavp_no_reseed() {
+    SHA256_NO_RESEED_0_0.check::<Hmac<Sha256>>();
+    SHA256_NO_RESEED_256_256.check::<Hmac<Sha256>>();
+}
+
+#[test]
+fn cavp_pr_false() {
+    SHA256_PR_FALSE_0_0.check::<Hmac<Sha256>>();
+    SHA256_PR_FALSE_0_256.check::<Hmac<Sha256>>();
+    SHA256_PR_FALSE_256_0.check::<Hmac<Sha256>>();
+    SHA256_PR_FALSE_256_256.check::<Hmac<Sha256>>();
+    SHA1_PR_FALSE.check::<Hmac<Sha1>>();
+    SHA512_PR_FALSE.check::<Hmac<Sha512>>();
+}
+
+fn drbg() -> HmacDrbgSha256 {
+    HmacDrbgSha256::new(&[0x01; 32], &[0x02; 16], b"personalization")
+}
+
+fn generate(drbg: &mut HmacDrbgSha256, additional_input: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    drbg.generate(&mut out, additional_input).unwrap();
+    out
+}
+
+#[test]
+fn inputs() {
+    let first = generate(&mut drbg(), &[]);
+    assert_ne!(generate(&mut drbg(), b"additional input"), first);
+    assert_ne!(
+        generate(&mut HmacDrbgSha256::new(&[0x01; 32], &[0x02; 16], &[]), &[]),
+        first
+    );
+
+    let mut reseeded = drbg();
+    reseeded.reseed(&[0x03; 32], &[]);
+    assert_ne!(generate(&mut reseeded, &[]), first);
+
+    // A longer request extends the output of a shorter one
+    let mut out = [0u8; 64];
+    drbg().generate(&mut out, &[]).unwrap();
+    assert_eq!(out[..32], first);
+}
+
+#[test]
+fn reseed_interval() {
+    let mut drbg = drbg().with_reseed_interval(2);
+    assert_eq!(drbg.reseed_counter(), 1);
+    generate(&mut drbg, &[]);
+    generate(&mut drbg, &[]);
+    assert_eq!(drbg.reseed_counter(), 3);
+    let mut out = [0u8; 32];
+    assert_eq!(drbg.generate(&mut out, &[]), Err(Error::ReseedRequired));
+    assert_eq!(drbg.try_next_u32(), Err(Error::ReseedRequired));
+
+    drbg.reseed(&[0x03; 32], b"additional input");
+    assert_eq!(drbg.reseed_counter(), 1);
+    generate(&mut drbg, &[]);
+}
+
+#[test]
+fn request_size() {
+    let mut out = vec![0u8; MAX_REQUEST_SIZE + 1];
+    assert_eq!(drbg().generate(&mut out, &[]), Err(Error::RequestTooLong));
+
+    // `TryRng` splits long requests
+    let mut drbg = drbg();
+    drbg.try_fill_bytes(&mut out).unwrap();
+    assert_eq!(drbg.reseed_counter(), 3);
+    let mut expected = vec![0u8; MAX_REQUEST_SIZE];
+    let mut reference = self::drbg();
+    reference.generate(&mut expected, &[]).unwrap();
+    assert_eq!(out[..MAX_REQUEST_SIZE], expected);
+    reference.generate(&mut expected[..1], &[]).unwrap();
+    assert_eq!(out[MAX_REQUEST_SIZE], expected[0]);
+}
+
+#[test]
+fn rng() {
+    let block = generate(&mut drbg(), &[]);
+    let mut rng = UnwrapErr(drbg());
+    assert_eq!(rng.next_u32().to_le_bytes(), block[..4]);
+    assert_eq!(UnwrapErr(drbg()).next_u64().to_le_bytes(), block[..8]);
+
+    let seed = [0x04; 32];
+    let mut seeded = HmacDrbgSha256::from_seed(seed.into());
+    assert_eq!(
+        generate(&mut seeded, &[]),
+        generate(&mut HmacDrbgSha256::new(&seed, &[], &[]), &[])
+    );
+    assert_eq!(
+        HmacDrbgSha256::seed_from_u64(42).try_next_u64(),
+        HmacDrbgSha256::seed_from_u64(42).try_next_u64()
+    );
+}
@@ -0,0 +1,156 @@
+//! HPKE key derivation tests using the test vectors from
+//! <https://www.rfc-editor.org/rfc/rfc9180#appendix-A.1>
+//! (DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM).
+#![cfg(feature = "hpke")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::hmac::Hmac;
+use hkdf::hpke::{Exporter, KeySchedule, Mode, Psk, Suite, extract_and_expand};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SUITE: Suite = Suite::new(0x0020, 0x0001, 0x0001);
+const INFO: [u8; 20] = hex!("4f6465206f6e2061204772656369616e2055726e");
+const PSK: [u8; 32] = hex!("0247fd33b913760fa1fa51e1892d9f307fbe65eb171e8132c2af18555a738b82");
+const PSK_ID: [u8; 22] = hex!("456e6e796e20447572696e206172616e204d6f726961");
+
+struct Vector {
+    mode: Mode,
+    psk: Option<Psk<'static>>,
+    shared_secret: [u8; 32],
+    key: [u8; 16],
+    base_nonce: [u8; 12],
+    exporter_secret: [u8; 32],
+}
+
+fn check(v: &Vector) {
+    let ks = KeySchedule::<HmacSha256>::new(SUITE, v.mode, &v.shared_secret, &INFO, v.psk).unwrap();
+    assert_eq!(ks.mode(), v.mode);
+
+    let mut key = [0u8; 16];
+    ks.key(&mut key).unwrap();
+    assert_eq!(key, v.key);
+
+    let mut base_nonce = [0u8; 12];
+    ks.base_nonce(&mut base_nonce).unwrap();
+    assert_eq!(base_nonce, v.base_nonce);
+
+    assert_eq!(ks.exporter_secret()[..], v.exporter_secret);
+}
+
+#[test]
+fn rfc9180_base() {
+    let dh = hex!("b3b5c19eab3f088ac18f23f774ff6414ba4fde45404d10085efc3e4dc9c72e35");
+    let enc = hex!("37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431");
+    let pk_rm = hex!("3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d");
+
+    let mut shared_secret = [0u8; 32];
+    extract_and_expand::<HmacSha256>(0x0020, &[&dh], &[&enc, &pk_rm], &mut shared_secret).unwrap();
+    assert_eq!(
+        shared_secret,
+        hex!("fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc")
+    );
+
+    // `kem_context` may be split into any number of components
+    let mut split_shared_secret = [0u8; 32];
+    extract_and_expand::<HmacSha256>(
+        0x0020,
+        &[&dh[..16], &dh[16..]],
+        &[
+            &enc[..8],
+            &enc[8..],
+            &pk_rm[..1],
+            &pk_rm[1..20],
+            &[],
+            &pk_rm[20..],
+        ],
+        &mut split_shared_secret,
+    )
+    .unwrap();
+    assert_eq!(split_shared_secret, shared_secret);
+
+    check(&Vector {
+        mode: Mode::Base,
+        psk: None,
+        shared_secret,
+        key: hex!("4531685d41d65f03dc48f6b8302c05b0"),
+        base_nonce: hex!("56d890e5accaaf011cff4b7d"),
+        exporter_secret: hex!("45ff1c2e220db587171952c0592d5f5ebe103f1561a2614e38f2ffd47e99e3f8"),
+    });
+}
+
+#[test]
+fn rfc9180_psk() {
+    check(&Vector {
+        mode: Mode::Psk,
+        psk: Some(Psk::new(&PSK, &PSK_ID)),
+        shared_secret: hex!("727699f009ffe3c076315019c69648366b69171439bd7dd0807743bde76986cd"),
+        key: hex!("15026dba546e3ae05836fc7de5a7bb26"),
+        base_nonce: hex!("9518635eba129d5ce0914555"),
+        exporter_secret: hex!("3d76025dbbedc49448ec3f9080a1abab6b06e91c0b11ad23c912f043a0ee7655"),
+    });
+}
+
+#[test]
+fn rfc9180_auth() {
+    check(&Vector {
+        mode: Mode::Auth,
+        psk: None,
+        shared_secret: hex!("2d6db4cf719dc7293fcbf3fa64690708e44e2bebc81f84608677958c0d4448a7"),
+        key: hex!("b062cb2c4dd4bca0ad7c7a12bbc341e6"),
+        base_nonce: hex!("a1bc314c1942ade7051ffed0"),
+        exporter_secret: hex!("ee1a093e6e1c393c162ea98fdf20560c75909653550540a2700511b65c88c6f1"),
+    });
+}
+
+#[test]
+fn rfc9180_export() {
+    let exporter_secret = hex!("45ff1c2e220db587171952c0592d5f5ebe103f1561a2614e38f2ffd47e99e3f8");
+    let exporter = Exporter::<HmacSha256>::new(SUITE, &exporter_secret).unwrap();
+
+    let vectors: [(&[u8], [u8; 32]); 3] = [
+        (
+            b"",
+            hex!("3853fe2b4035195a573ffc53856e77058e15d9ea064de3e59f4961d0095250ee"),
+        ),
+        (
+            &hex!("00"),
+            hex!("2e8f0b54673c7029649d4eb9d5e33bf1872cf76d623ff164ac185da9e88c21a5"),
+        ),
+        (
+            b"TestContext",
+            hex!("e9e43065102c3836401bed8c3c3c75ae46be1639869391d62c61f1ec7af54931"),
+        ),
+    ];
+    for (exporter_context, expected) in vectors {
+        let mut exported = [0u8; 32];
+        exporter.export(exporter_context, &mut exported).unwrap();
+        assert_eq!(exported, expected);
+    }
+}
+
+#[test]
+fn invalid_psk_inputs() {
+    let shared_secret = [0u8; 32];
+    let psk = Psk::new(&PSK, &PSK_ID);
+    let new = |mode, psk| KeySchedule::<HmacSha256>::new(SUITE, mode, &shared_secret, &INFO, psk);
+
+    assert!(new(Mode::Base, Some(psk)).is_err());
+    assert!(new(Mode::Auth, Some(psk)).is_err());
+    assert!(new(Mode::Psk, None).is_err());
+    assert!(new(Mode::AuthPsk, None).is_err());
+    assert!(new(Mode::Psk, Some(Psk::new(&[], &PSK_ID))).is_err());
+    assert!(new(Mode::AuthPsk, Some(Psk::new(&PSK, &[]))).is_err());
+    assert!(new(Mode::AuthPsk, Some(psk)).is_ok());
+}
+
+#[test]
+fn invalid_lengths() {
+    let ks = KeySchedule::<HmacSha256>::new(SUITE, Mode::Base, &[0u8; 32], &INFO, None).unwrap();
+    let mut okm = [0u8; 255 * 32 + 1];
+    assert!(ks.key(&mut okm).is_err());
+    assert!(ks.exporter().export(b"", &mut okm).is_err());
+    assert!(ks.exporter().export(b"", &mut okm[..255 * 32]).is_ok());
+}
@@ -0,0 +1,117 @@
+//! Hybrid shared secret combiner tests.
+#![cfg(feature = "hybrid")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hkdf::Hkdf;
+use hkdf::hmac::Hmac;
+use hkdf::hybrid::{Component, Encoding, Profile, X_WING_LABEL};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SS_PQ: [u8; 32] = [0x11; 32];
+const SS_T: [u8; 32] = [0x22; 32];
+const CT_T: [u8; 32] = [0x33; 32];
+const PK_T: [u8; 32] = [0x44; 32];
+
+fn components() -> [Component<'static>; 2] {
+    [
+        Component::new(&SS_PQ, &[]),
+        Component::new(&SS_T, &[&CT_T, &PK_T]),
+    ]
+}
+
+#[test]
+fn test_tls_concat() {
+    let salt = [0x55; 32];
+    let (prk, _) = Profile::TLS_CONCAT
+        .combine::<HmacSha256>(Some(&salt), &components())
+        .unwrap();
+    let (expected, _) = Hkdf::<Sha256>::extract(Some(&salt), &[SS_PQ, SS_T].concat());
+    assert_eq!(prk, expected);
+}
+
+#[test]
+fn test_xwing_like() {
+    let (prk, hkdf) = Profile::XWING_LIKE
+        .combine::<HmacSha256>(None, &components())
+        .unwrap();
+    let ikm = [&SS_PQ[..], &SS_T, &CT_T, &PK_T, X_WING_LABEL].concat();
+    let (expected, expected_hkdf) = Hkdf::<Sha256>::extract(None, &ikm);
+    assert_eq!(prk, expected);
+    assert_eq!(
+        hkdf.expand_array::<32>(b"info")[..],
+        expected_hkdf.expand_array::<32>(b"info")[..],
+    );
+}
+
+#[test]
+fn test_length_prefixed() {
+    let profile = Profile::length_prefixed(b"label");
+    assert_eq!(profile.encoding, Encoding::LengthPrefixed);
+    let (prk, _) = profile.combine::<HmacSha256>(None, &components()).unwrap();
+
+    let mut ikm = 2u32.to_be_bytes().to_vec();
+    let parts: [&[u8]; 5] = [&SS_PQ, &SS_T, &CT_T, &PK_T, b"label"];
+    for (i, part) in parts.into_iter().enumerate() {
+        ikm.extend_from_slice(&u32::try_from(part.len()).unwrap().to_be_bytes());
+        ikm.extend_from_slice(part);
+        // Number of bindings following each shared secret
+        match i {
+            0 => ikm.extend_from_slice(&0u32.to_be_bytes()),
+            1 => ikm.extend_from_slice(&2u32.to_be_bytes()),
+            _ => {}
+        }
+    }
+    let (expected, _) = Hkdf::<Sha256>::extract(None, &ikm);
+    assert_eq!(prk, expected);
+}
+
+#[test]
+fn test_length_prefixed_is_unambiguous() {
+    let a = [Component::new(b"ab", &[]), Component::new(b"c", &[])];
+    let b = [Component::new(b"a", &[]), Component::new(b"bc", &[])];
+
+    let concat = Profile {
+        encoding: Encoding::Concat,
+        bind: true,
+        label: b"label",
+    };
+    let (prk_a, _) = concat.combine::<HmacSha256>(None, &a).unwrap();
+    let (prk_b, _) = concat.combine::<HmacSha256>(None, &b).unwrap();
+    assert_eq!(prk_a, prk_b);
+
+    let prefixed = Profile::length_prefixed(b"label");
+    let (prk_a, _) = prefixed.combine::<HmacSha256>(None, &a).unwrap();
+    let (prk_b, _) = prefixed.combine::<HmacSha256>(None, &b).unwrap();
+    assert_ne!(prk_a, prk_b);
+}
+
+#[test]
+fn test_length_prefixed_encodes_counts() {
+    let separate = [Component::new(b"A", &[]), Component::new(b"B", &[])];
+    let bound = [Component::new(b"A", &[b"B"])];
+
+    let prefixed = Profile::length_prefixed(b"label");
+    let (prk_separate, _) = prefixed.combine::<HmacSha256>(None, &separate).unwrap();
+    let (prk_bound, _) = prefixed.combine::<HmacSha256>(None, &bound).unwrap();
+    assert_ne!(prk_separate, prk_bound);
+
+    let moved = [Component::new(b"A", &[]), Component::new(b"B", &[b"C"])];
+    let other = [Component::new(b"A", &[b"C"]), Component::new(b"B", &[])];
+    let (prk_moved, _) = prefixed.combine::<HmacSha256>(None, &moved).unwrap();
+    let (prk_other, _) = prefixed.combine::<HmacSha256>(None, &other).unwrap();
+    assert_ne!(prk_moved, prk_other);
+}
+
+#[test]
+fn test_bindings_ignored_without_bind() {
+    let unbound = [Component::new(&SS_PQ, &[]), Component::new(&SS_T, &[])];
+    let (prk_a, _) = Profile::TLS_CONCAT
+        .combine::<HmacSha256>(None, &components())
+        .unwrap();
+    let (prk_b, _) = Profile::TLS_CONCAT
+        .combine::<HmacSha256>(None, &unbound)
+        .unwrap();
+    assert_eq!(prk_a, prk_b);
+}
@@ -0,0 +1,126 @@
+//! Key tree tests.
+#![cfg(feature = "key-tree")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hkdf::{Hkdf, key_tree::KeyTree};
+use sha2::Sha256;
+
+const PATH: &[&[u8]] = &[b"tenant", b"42", b"db", b"enc"];
+
+fn root() -> KeyTree<Sha256> {
+    KeyTree::new(Some(b"salt"), b"root secret")
+}
+
+fn derive(tree: &KeyTree<Sha256>, path: &[&[u8]]) -> [u8; 32] {
+    let mut okm = [0u8; 32];
+    tree.derive(path, &mut okm).unwrap();
+    okm
+}
+
+#[test]
+fn encoding() {
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(b"salt"), b"root secret");
+    let root = root();
+    assert_eq!(root.as_bytes().as_slice(), prk.as_slice());
+
+    let mut secret = [0u8; 32];
+    Hkdf::<Sha256>::from_prk(&prk)
+        .unwrap()
+        .expand(
+            b"\x00\x00\x00\x12hkdf key tree node\x00\x00\x00\x06tenant",
+            &mut secret,
+        )
+        .unwrap();
+    let child = root.child(b"tenant").unwrap();
+    assert_eq!(child.as_bytes().as_slice(), secret);
+
+    let mut key = [0u8; 42];
+    Hkdf::<Sha256>::from_prk(&secret)
+        .unwrap()
+        .expand(
+            b"\x00\x00\x00\x11hkdf key tree key\x00\x00\x00\x04info",
+            &mut key,
+        )
+        .unwrap();
+    let mut okm = [0u8; 42];
+    child.expand(b"info", &mut okm).unwrap();
+    assert_eq!(okm, key);
+}
+
+#[test]
+fn paths() {
+    let root = root();
+    let mut node = root.clone();
+    for component in PATH {
+        node = node.child(component).unwrap();
+    }
+    assert_eq!(node.as_bytes(), root.subtree(PATH).unwrap().as_bytes());
+    assert_eq!(root.subtree(&[]).unwrap().as_bytes(), root.as_bytes());
+
+    let mut okm = [0u8; 32];
+    node.expand(&[], &mut okm).unwrap();
+    assert_eq!(derive(&root, PATH), okm);
+
+    // Paths are encoded injectively and keys differ from node secrets
+    assert_ne!(derive(&root, &[b"ab", b"c"]), derive(&root, &[b"a", b"bc"]));
+    assert_ne!(derive(&root, &[b"abc"]), derive(&root, &[b"abc", b""]));
+    assert_ne!(
+        &derive(&root, &[b"abc"]),
+        root.child(b"abc").unwrap().as_bytes()
+    );
+
+    let mut okm = [0u8; 255 * 32 + 1];
+    assert!(root.derive(PATH, &mut okm).is_err());
+}
+
+#[test]
+fn delegation() {
+    let root = root();
+    let tenant = root.subtree(&PATH[..2]).unwrap();
+    let delegated = KeyTree::<Sha256>::from_secret(*tenant.as_bytes());
+    assert_eq!(derive(&delegated, &PATH[2..]), derive(&root, PATH));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn cache() {
+    use hkdf::key_tree::KeyTreeCache;
+
+    let root = root();
+    let mut cache = KeyTreeCache::new(root.clone());
+    assert!(cache.is_empty());
+    assert_eq!(cache.subtree(&[]).unwrap().as_bytes(), root.as_bytes());
+    assert!(cache.is_empty());
+
+    let mut okm = [0u8; 32];
+    cache.derive(PATH, &mut okm).unwrap();
+    assert_eq!(okm, derive(&root, PATH));
+    assert_eq!(cache.len(), 4);
+
+    // Siblings are derived from the cached parent
+    let sibling: &[&[u8]] = &[b"tenant", b"42", b"db", b"mac"];
+    assert_eq!(
+        cache.subtree(sibling).unwrap().as_bytes(),
+        root.subtree(sibling).unwrap().as_bytes()
+    );
+    assert_eq!(cache.len(), 5);
+    assert_eq!(
+        cache.subtree(&PATH[..2]).unwrap().as_bytes(),
+        root.subtree(&PATH[..2]).unwrap().as_bytes()
+    );
+    assert_eq!(cache.len(), 5);
+
+    let other: &[&[u8]] = &[b"tenant", b"43"];
+    cache.subtree(other).unwrap();
+    assert_eq!(cache.len(), 6);
+
+    cache.remove(&PATH[..2]);
+    assert_eq!(cache.len(), 2);
+    cache.derive(PATH, &mut okm).unwrap();
+    assert_eq!(okm, derive(&root, PATH));
+    assert_eq!(cache.len(), 5);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.root().as_bytes(), root.as_bytes());
+}
@@ -0,0 +1,94 @@
+//! Tests for HKDF over MAC PRFs other than HMAC.
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use aes::Aes128;
+use cmac::Cmac;
+use hkdf::{GenericHkdf, GenericHkdfExtract, MacPrf};
+
+type CmacHkdf = GenericHkdf<MacPrf<Cmac<Aes128>>>;
+
+#[cfg(feature = "blake2")]
+#[test]
+fn test_blake2b() {
+    use hex_literal::hex;
+    use hkdf::{
+        Blake2bHkdf, Blake2bHkdfExtract,
+        blake2::Blake2bMac512,
+        hmac::digest::{KeyInit, Mac},
+    };
+
+    let salt = hex!("000102030405060708090a0b0c");
+    let ikm = hex!("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b");
+    let info = hex!("f0f1f2f3f4f5f6f7f8f9");
+
+    // Compute the expected values with the MAC directly
+    let mac = |key: &[u8], parts: &[&[u8]]| {
+        let mut mac = <Blake2bMac512 as KeyInit>::new_from_slice(key).unwrap();
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes()
+    };
+    let prk = mac(&salt, &[&ikm]);
+    let t1 = mac(&prk, &[&info, &[1]]);
+    let t2 = mac(&prk, &[&t1, &info, &[2]]);
+
+    let (actual_prk, hk) = Blake2bHkdf::extract(Some(&salt), &ikm);
+    assert_eq!(actual_prk[..], prk[..]);
+    let mut okm = [0u8; 100];
+    hk.expand(&info, &mut okm).unwrap();
+    assert_eq!(okm[..64], t1[..]);
+    assert_eq!(okm[64..], t2[..36]);
+
+    let mut extract = Blake2bHkdfExtract::try_new(Some(&salt)).unwrap();
+    extract.input_ikm(&ikm[..10]);
+    extract.input_ikm(&ikm[10..]);
+    let (streamed_prk, _) = extract.finalize();
+    assert_eq!(streamed_prk[..], prk[..]);
+
+    // The default salt is a string of `HashLen` zeros
+    let (prk, _) = Blake2bHkdf::extract(None, &ikm);
+    assert_eq!(prk[..], mac(&[0; 64], &[&ikm])[..]);
+}
+
+#[cfg(feature = "blake2")]
+#[test]
+fn test_blake2_invalid_key_length() {
+    use hkdf::{Blake2bHkdf, Blake2sHkdf};
+
+    assert!(Blake2bHkdf::try_new(Some(&[0; 64]), b"ikm").is_ok());
+    assert!(Blake2bHkdf::try_new(Some(&[0; 65]), b"ikm").is_err());
+    assert!(Blake2sHkdf::try_new(Some(&[0; 32]), b"ikm").is_ok());
+    assert!(Blake2sHkdf::try_extract(Some(&[0; 33]), b"ikm").is_err());
+
+    assert!(Blake2bHkdf::from_prk(&[0; 63]).is_err());
+    assert!(Blake2bHkdf::from_prk(&[0; 64]).is_ok());
+    assert!(Blake2bHkdf::from_prk(&[0; 65]).is_err());
+}
+
+#[test]
+fn test_fixed_key_size() {
+    let salt = [0x42; 16];
+    let (prk, hk) = CmacHkdf::try_extract(Some(&salt), b"ikm").unwrap();
+    assert_eq!(
+        CmacHkdf::new(Some(&salt), b"ikm").expand_array::<48>(b"info")[..],
+        hk.expand_array::<48>(b"info")[..],
+    );
+    assert_eq!(
+        CmacHkdf::from_prk(&prk)
+            .unwrap()
+            .expand_array::<48>(b"info")[..],
+        hk.expand_array::<48>(b"info")[..],
+    );
+
+    assert!(CmacHkdf::try_new(None, b"ikm").is_ok());
+    assert!(CmacHkdf::try_new(Some(&salt[..15]), b"ikm").is_err());
+    assert!(GenericHkdfExtract::<MacPrf<Cmac<Aes128>>>::try_new(Some(&[0; 17])).is_err());
+    assert!(CmacHkdf::from_prk(&[0; 17]).is_err());
+}
+
+#[test]
+#[should_panic(expected = "key length is supported by the MAC")]
+fn test_rejected_salt_panics() {
+    let _ = CmacHkdf::new(Some(&[0; 15]), b"ikm");
+}
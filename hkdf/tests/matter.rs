@@ -0,0 +1,122 @@
+//! Matter key derivation tests, using the test vectors of the Matter Core Specification
+//! (Sections 4.3.2.2 and 4.17.2) where available. The other known answers were computed
+//! with the HKDF implementation of OpenSSL 3.5 from the salts and infos of the
+//! specification:
+//!
+//! ```text
+//! openssl kdf -keylen <N> -kdfopt digest:SHA256 -kdfopt hexkey:<secret> \
+//!     -kdfopt hexsalt:<salt> -kdfopt hexinfo:<info> HKDF
+//! ```
+#![cfg(feature = "matter")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::matter::{
+    SessionKeys, compressed_fabric_id, group_privacy_key, group_session_id, operational_group_key,
+    pase_confirmation_keys, sigma1_resume_key, sigma2_key, sigma2_resume_key, sigma3_key,
+};
+
+const ROOT_PUBLIC_KEY: [u8; 64] = hex!(
+    "4a9f42b1ca4840d37292bbc7f6a7e11e22200c976fc900dbc98a7a383a641cb8"
+    "254a2e56d4e295a847943b4e3897c4a773e930277b4d9fbede8a052686bfacfa"
+);
+const FABRIC_ID: u64 = 0x2906_C908_D115_D362;
+const COMPRESSED_FABRIC_ID: [u8; 8] = hex!("87e1b004e235a130");
+const EPOCH_KEY: [u8; 16] = hex!("235bf7e62823d358dca4ba50b1535f4b");
+const OPERATIONAL_GROUP_KEY: [u8; 16] = hex!("a6f5306baf6d050af23ba4bd6b9dd960");
+
+const SHARED_SECRET: [u8; 32] = [0x0B; 32];
+const IPK: [u8; 16] = [0x0C; 16];
+const INITIATOR_RANDOM: [u8; 32] = [0x0D; 32];
+const RESUMPTION_ID: [u8; 16] = [0x0E; 16];
+
+fn assert_session_keys(keys: &SessionKeys, okm: &[u8; 48]) {
+    assert_eq!(keys.i2r_key, okm[..16]);
+    assert_eq!(keys.r2i_key, okm[16..32]);
+    assert_eq!(keys.attestation_challenge, okm[32..]);
+}
+
+#[test]
+fn compressed_fabric_id_vector() {
+    assert_eq!(
+        compressed_fabric_id(&ROOT_PUBLIC_KEY, FABRIC_ID),
+        COMPRESSED_FABRIC_ID
+    );
+}
+
+#[test]
+fn group_key_vectors() {
+    let key = operational_group_key(&EPOCH_KEY, &COMPRESSED_FABRIC_ID);
+    assert_eq!(key, OPERATIONAL_GROUP_KEY);
+    assert_eq!(group_session_id(&key), 0xB9F7);
+    assert_eq!(
+        group_privacy_key(&key),
+        hex!("01f8d1927126f194082572d49b1fdc73")
+    );
+}
+
+#[test]
+fn pase() {
+    let ke = hex!("2f2bc2b5f2b18d1d0c5ee7f6b2a4c6e1");
+    assert_session_keys(
+        &SessionKeys::pase(&ke),
+        &hex!(
+            "483d9c6086388e973dbb1205717245c44abde260f25426532a19e08444bb6db0"
+            "68bc50f8ee3d8c0e9a97c11e63c10c81"
+        ),
+    );
+
+    let ka = hex!("3a4f1a8a6b4b0c1c2d3e4f5a6b7c8d9e");
+    let (kca, kcb) = pase_confirmation_keys(&ka);
+    assert_eq!(kca, hex!("c2bdc95e432dd8b3f78f5ae198caa99d"));
+    assert_eq!(kcb, hex!("a99b90cc7bbe7c02bcd7217340e640ec"));
+}
+
+#[test]
+fn case() {
+    let transcript_hash = [0x0F; 32];
+    assert_session_keys(
+        &SessionKeys::case(&SHARED_SECRET, &IPK, &transcript_hash),
+        &hex!(
+            "4d08f59faad3f70da384d6e271e3c376fd55aa3b62001ce670d3abbb920233e0"
+            "c7ad9acef74f82644a75a7e1d55dbc3c"
+        ),
+    );
+    assert_eq!(
+        sigma3_key(&SHARED_SECRET, &IPK, &transcript_hash),
+        hex!("d0af9a08038acdc3f2351fe7b35d600c")
+    );
+
+    let responder_random = [0x10; 32];
+    let responder_eph_pub_key = [0x04; 65];
+    let sigma1_hash = [0x11; 32];
+    assert_eq!(
+        sigma2_key(
+            &SHARED_SECRET,
+            &IPK,
+            &responder_random,
+            &responder_eph_pub_key,
+            &sigma1_hash,
+        ),
+        hex!("3c02691479edf112146bf664c8ffd438")
+    );
+}
+
+#[test]
+fn case_resumption() {
+    assert_session_keys(
+        &SessionKeys::case_resumption(&SHARED_SECRET, &INITIATOR_RANDOM, &RESUMPTION_ID),
+        &hex!(
+            "bbf634bff7dca4324e6dc517b20e21a64109c6296b036a91b6518e7abf5f8db6"
+            "4360e9efbf02f371d53f080d301d5ee2"
+        ),
+    );
+    assert_eq!(
+        sigma1_resume_key(&SHARED_SECRET, &INITIATOR_RANDOM, &RESUMPTION_ID),
+        hex!("83f480a2abaa0ac6ead174d6c7583448")
+    );
+    assert_eq!(
+        sigma2_resume_key(&SHARED_SECRET, &INITIATOR_RANDOM, &RESUMPTION_ID),
+        hex!("4c1d1e9e89bd030d4701692a07b48b92")
+    );
+}
@@ -0,0 +1,259 @@
+//! MLS key derivation tests using the interoperability test vectors from
+//! <https://github.com/mlswg/mls-implementations/tree/main/test-vectors>.
+#![cfg(feature = "mls")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hkdf::{
+    Hkdf,
+    mls::{
+        JoinerSecret, Ratchet, derive_secret, derive_tree_secret, expand_with_label,
+        sender_data_key, sender_data_nonce,
+    },
+};
+use hmac::{
+    EagerHash, Hmac,
+    digest::{Output, array::Array},
+};
+use sha2::{Sha256, Sha384, Sha512};
+
+#[derive(Copy, Clone, Debug)]
+struct CryptoBasics {
+    ewl_secret: &'static [u8],
+    ewl_label: &'static [u8],
+    ewl_context: &'static [u8],
+    ewl_out: &'static [u8],
+    ds_secret: &'static [u8],
+    ds_label: &'static [u8],
+    ds_out: &'static [u8],
+    dts_secret: &'static [u8],
+    dts_label: &'static [u8],
+    dts_generation: &'static [u8],
+    dts_out: &'static [u8],
+}
+
+#[derive(Copy, Clone, Debug)]
+struct KeySchedule {
+    prev_init_secret: &'static [u8],
+    group_context: &'static [u8],
+    commit_secret: &'static [u8],
+    psk_secret: &'static [u8],
+    joiner_secret: &'static [u8],
+    welcome_secret: &'static [u8],
+    sender_data_secret: &'static [u8],
+    encryption_secret: &'static [u8],
+    exporter_secret: &'static [u8],
+    external_secret: &'static [u8],
+    confirmation_key: &'static [u8],
+    membership_key: &'static [u8],
+    resumption_psk: &'static [u8],
+    epoch_authenticator: &'static [u8],
+    init_secret: &'static [u8],
+    exporter_label: &'static [u8],
+    exporter_context: &'static [u8],
+    exported: &'static [u8],
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SecretTree {
+    encryption_secret: &'static [u8],
+    leaf_count: &'static [u8],
+    leaf_index: &'static [u8],
+    generation: &'static [u8],
+    handshake_key: &'static [u8],
+    handshake_nonce: &'static [u8],
+    application_key: &'static [u8],
+    application_nonce: &'static [u8],
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SenderData {
+    sender_data_secret: &'static [u8],
+    ciphertext: &'static [u8],
+    key: &'static [u8],
+    nonce: &'static [u8],
+}
+
+fn u32_be(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes.try_into().unwrap())
+}
+
+fn secret<D: EagerHash>(bytes: &[u8]) -> Output<Hmac<D>> {
+    Array::try_from(bytes).unwrap()
+}
+
+fn crypto_basics<D: EagerHash>(test_vectors: &[CryptoBasics]) {
+    let mut buf = [0u8; 128];
+    for tv in test_vectors {
+        let hkdf = Hkdf::<D>::from_prk(tv.ewl_secret).unwrap();
+        let out = &mut buf[..tv.ewl_out.len()];
+        expand_with_label(&hkdf, tv.ewl_label, tv.ewl_context, out).unwrap();
+        assert_eq!(out, tv.ewl_out);
+
+        let hkdf = Hkdf::<D>::from_prk(tv.ds_secret).unwrap();
+        assert_eq!(
+            derive_secret(&hkdf, tv.ds_label).unwrap()[..],
+            tv.ds_out[..]
+        );
+
+        let hkdf = Hkdf::<D>::from_prk(tv.dts_secret).unwrap();
+        let out = &mut buf[..tv.dts_out.len()];
+        derive_tree_secret(&hkdf, tv.dts_label, u32_be(tv.dts_generation), out).unwrap();
+        assert_eq!(out, tv.dts_out);
+    }
+}
+
+fn key_schedule<D: EagerHash>(test_vectors: &[KeySchedule]) {
+    let mut buf = [0u8; 128];
+    for tv in test_vectors {
+        let joiner = JoinerSecret::<D>::derive(
+            tv.prev_init_secret,
+            Some(tv.commit_secret),
+            tv.group_context,
+        );
+        assert_eq!(joiner.as_bytes()[..], tv.joiner_secret[..]);
+        let welcome_secret = joiner.welcome_secret(Some(tv.psk_secret));
+        assert_eq!(welcome_secret[..], tv.welcome_secret[..]);
+
+        // A joiner secret received in a `Welcome` leads to the same epoch
+        let joiner = JoinerSecret::<D>::new(secret::<D>(tv.joiner_secret));
+        let epoch = joiner.epoch_secrets(Some(tv.psk_secret), tv.group_context);
+        assert_eq!(epoch.sender_data_secret[..], tv.sender_data_secret[..]);
+        assert_eq!(epoch.encryption_secret[..], tv.encryption_secret[..]);
+        assert_eq!(epoch.exporter_secret[..], tv.exporter_secret[..]);
+        assert_eq!(epoch.external_secret[..], tv.external_secret[..]);
+        assert_eq!(epoch.confirmation_key[..], tv.confirmation_key[..]);
+        assert_eq!(epoch.membership_key[..], tv.membership_key[..]);
+        assert_eq!(epoch.resumption_psk[..], tv.resumption_psk[..]);
+        assert_eq!(epoch.epoch_authenticator[..], tv.epoch_authenticator[..]);
+        assert_eq!(epoch.init_secret[..], tv.init_secret[..]);
+
+        let out = &mut buf[..tv.exported.len()];
+        epoch
+            .exporter(tv.exporter_label, tv.exporter_context, out)
+            .unwrap();
+        assert_eq!(out, tv.exported);
+    }
+}
+
+fn check_ratchet<D: EagerHash>(
+    mut ratchet: Ratchet<D>,
+    generation: u32,
+    expected_key: &[u8],
+    expected_nonce: &[u8],
+) {
+    while ratchet.generation() < generation {
+        ratchet = ratchet.next_generation().unwrap();
+    }
+    let mut buf = [0u8; 32];
+    let key = &mut buf[..expected_key.len()];
+    ratchet.key(key).unwrap();
+    assert_eq!(key, expected_key);
+    let nonce = &mut buf[..expected_nonce.len()];
+    ratchet.nonce(nonce).unwrap();
+    assert_eq!(nonce, expected_nonce);
+}
+
+fn secret_tree<D: EagerHash>(test_vectors: &[SecretTree]) {
+    for tv in test_vectors {
+        let tree = hkdf::mls::SecretTree::<D>::new(
+            secret::<D>(tv.encryption_secret),
+            u32_be(tv.leaf_count),
+        );
+        let leaf_index = u32_be(tv.leaf_index);
+        let generation = u32_be(tv.generation);
+
+        let ratchet = tree.handshake_ratchet(leaf_index).unwrap();
+        check_ratchet(ratchet, generation, tv.handshake_key, tv.handshake_nonce);
+        let ratchet = tree.application_ratchet(leaf_index).unwrap();
+        check_ratchet(
+            ratchet,
+            generation,
+            tv.application_key,
+            tv.application_nonce,
+        );
+
+        assert!(tree.handshake_ratchet(u32_be(tv.leaf_count)).is_err());
+    }
+}
+
+fn sender_data<D: EagerHash>(test_vectors: &[SenderData]) {
+    let mut buf = [0u8; 32];
+    for tv in test_vectors {
+        let sender_data_secret = secret::<D>(tv.sender_data_secret);
+        let key = &mut buf[..tv.key.len()];
+        sender_data_key::<D>(&sender_data_secret, tv.ciphertext, key).unwrap();
+        assert_eq!(key, tv.key);
+        let nonce = &mut buf[..tv.nonce.len()];
+        sender_data_nonce::<D>(&sender_data_secret, tv.ciphertext, nonce).unwrap();
+        assert_eq!(nonce, tv.nonce);
+    }
+}
+
+macro_rules! new_tests {
+    ($test:ident, $tv:ident $fields:tt, [$($name:ident: $hash:ty),* $(,)?]) => {
+        $(
+            #[test]
+            fn $name() {
+                blobby::parse_into_structs!(
+                    include_bytes!(concat!("data/", stringify!($name), ".blb"));
+                    static TEST_VECTORS: &[$tv $fields];
+                );
+
+                $test::<$hash>(TEST_VECTORS);
+            }
+        )*
+    };
+}
+
+new_tests!(
+    crypto_basics,
+    CryptoBasics {
+        ewl_secret, ewl_label, ewl_context, ewl_out,
+        ds_secret, ds_label, ds_out,
+        dts_secret, dts_label, dts_generation, dts_out,
+    },
+    [
+        mls_crypto_basics_sha256: Sha256,
+        mls_crypto_basics_sha384: Sha384,
+        mls_crypto_basics_sha512: Sha512,
+    ]
+);
+
+new_tests!(
+    key_schedule,
+    KeySchedule {
+        prev_init_secret, group_context, commit_secret, psk_secret,
+        joiner_secret, welcome_secret, sender_data_secret, encryption_secret,
+        exporter_secret, external_secret, confirmation_key, membership_key,
+        resumption_psk, epoch_authenticator, init_secret,
+        exporter_label, exporter_context, exported,
+    },
+    [
+        mls_key_schedule_sha256: Sha256,
+        mls_key_schedule_sha384: Sha384,
+        mls_key_schedule_sha512: Sha512,
+    ]
+);
+
+new_tests!(
+    secret_tree,
+    SecretTree {
+        encryption_secret, leaf_count, leaf_index, generation,
+        handshake_key, handshake_nonce, application_key, application_nonce,
+    },
+    [
+        mls_secret_tree_sha256: Sha256,
+        mls_secret_tree_sha384: Sha384,
+        mls_secret_tree_sha512: Sha512,
+    ]
+);
+
+new_tests!(
+    sender_data,
+    SenderData { sender_data_secret, ciphertext, key, nonce },
+    [
+        mls_sender_data_sha256: Sha256,
+        mls_sender_data_sha384: Sha384,
+        mls_sender_data_sha512: Sha512,
+    ]
+);
@@ -0,0 +1,195 @@
+//! Noise `SymmetricState` tests using the cacophony test vectors from
+//! <https://github.com/haskell-cryptography/cacophony/tree/master/vectors>
+//! (`Noise_{NN,XX,IK}_25519_ChaChaPoly_*`).
+//!
+//! The handshakes are replayed with the DH outputs of the private keys of both
+//! parties, and the keys produced by `Split()` are checked against the first
+//! transport message in each direction.
+#![cfg(feature = "noise")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, aead::Aead};
+use hkdf::hmac::{Hmac, SimpleHmac, digest::Digest};
+use hkdf::{HmacImpl, noise::SymmetricState};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+#[derive(Copy, Clone, Debug)]
+struct TestVector {
+    protocol_name: &'static [u8],
+    prologue: &'static [u8],
+    init_static: &'static [u8],
+    init_ephemeral: &'static [u8],
+    resp_static: &'static [u8],
+    resp_ephemeral: &'static [u8],
+    handshake_hash: &'static [u8],
+    msg1: &'static [u8],
+    msg2: &'static [u8],
+    msg3: &'static [u8],
+    transport1_payload: &'static [u8],
+    transport1_ciphertext: &'static [u8],
+    transport2_payload: &'static [u8],
+    transport2_ciphertext: &'static [u8],
+}
+
+struct KeyPair {
+    secret: StaticSecret,
+    public: [u8; 32],
+}
+
+impl KeyPair {
+    fn new(secret: &[u8]) -> Option<Self> {
+        let secret = StaticSecret::from(<[u8; 32]>::try_from(secret).ok()?);
+        let public = PublicKey::from(&secret).to_bytes();
+        Some(Self { secret, public })
+    }
+
+    fn dh(&self, other: &KeyPair) -> [u8; 32] {
+        self.secret
+            .diffie_hellman(&PublicKey::from(other.public))
+            .to_bytes()
+    }
+}
+
+/// Message patterns of the initiator (`->`) and responder (`<-`) in turn.
+fn patterns(protocol_name: &[u8]) -> &'static [&'static [&'static str]] {
+    match &protocol_name[6..8] {
+        b"NN" => &[&["e"], &["e", "ee"]],
+        b"XX" => &[&["e"], &["e", "ee", "s", "es"], &["s", "se"]],
+        b"IK" => &[&["e", "es", "s", "ss"], &["e", "ee", "se"]],
+        _ => unreachable!(),
+    }
+}
+
+fn encrypt(key: &[u8; 32], payload: &[u8]) -> Vec<u8> {
+    let cipher = ChaCha20Poly1305::new(key.into());
+    cipher.encrypt(&[0u8; 12].into(), payload).unwrap()
+}
+
+fn test<D, H>(tvs: &[TestVector])
+where
+    D: Digest,
+    H: HmacImpl<OutputSize = D::OutputSize>,
+{
+    for tv in tvs {
+        let init_s = KeyPair::new(tv.init_static);
+        let init_e = KeyPair::new(tv.init_ephemeral).unwrap();
+        let resp_s = KeyPair::new(tv.resp_static);
+        let resp_e = KeyPair::new(tv.resp_ephemeral).unwrap();
+
+        let mut state = SymmetricState::<D, H>::new(tv.protocol_name);
+        assert!(state.cipher_key().is_none());
+        state.mix_hash(tv.prologue);
+        if &tv.protocol_name[6..8] == b"IK" {
+            state.mix_hash(&resp_s.as_ref().unwrap().public);
+        }
+
+        let patterns = patterns(tv.protocol_name);
+        let messages = [tv.msg1, tv.msg2, tv.msg3];
+        for (i, (tokens, mut message)) in patterns.iter().zip(messages).enumerate() {
+            let (s, e) = if i % 2 == 0 {
+                (&init_s, &init_e)
+            } else {
+                (&resp_s, &resp_e)
+            };
+            for token in tokens.iter() {
+                match *token {
+                    "e" => {
+                        let (public, rest) = message.split_at(32);
+                        assert_eq!(public, e.public);
+                        state.mix_hash(public);
+                        message = rest;
+                    }
+                    "s" => {
+                        // The static public key is encrypted if a key has been mixed in
+                        let len = if state.cipher_key().is_some() { 48 } else { 32 };
+                        let (ciphertext, rest) = message.split_at(len);
+                        if len == 32 {
+                            assert_eq!(ciphertext, s.as_ref().unwrap().public);
+                        }
+                        state.mix_hash(ciphertext);
+                        message = rest;
+                    }
+                    "ee" => state.mix_key(&init_e.dh(&resp_e)),
+                    "es" => state.mix_key(&init_e.dh(resp_s.as_ref().unwrap())),
+                    "se" => state.mix_key(&init_s.as_ref().unwrap().dh(&resp_e)),
+                    "ss" => state.mix_key(&init_s.as_ref().unwrap().dh(resp_s.as_ref().unwrap())),
+                    _ => unreachable!(),
+                }
+            }
+            // Payload
+            state.mix_hash(message);
+        }
+
+        assert_eq!(state.handshake_hash()[..], tv.handshake_hash[..]);
+
+        // Transport messages alternate between the parties, starting with the party
+        // which did not send the last handshake message
+        let (initiator_key, responder_key) = state.split();
+        let (key1, key2) = if patterns.len() % 2 == 0 {
+            (&initiator_key, &responder_key)
+        } else {
+            (&responder_key, &initiator_key)
+        };
+        assert_eq!(
+            encrypt(key1, tv.transport1_payload),
+            tv.transport1_ciphertext
+        );
+        assert_eq!(
+            encrypt(key2, tv.transport2_payload),
+            tv.transport2_ciphertext
+        );
+    }
+}
+
+macro_rules! new_test {
+    ($name:ident, $hash:ty $(, $hmac:ident)+) => {
+        #[test]
+        fn $name() {
+            blobby::parse_into_structs!(
+                include_bytes!(concat!("data/", stringify!($name), ".blb"));
+                static TEST_VECTORS: &[TestVector {
+                    protocol_name,
+                    prologue,
+                    init_static,
+                    init_ephemeral,
+                    resp_static,
+                    resp_ephemeral,
+                    handshake_hash,
+                    msg1,
+                    msg2,
+                    msg3,
+                    transport1_payload,
+                    transport1_ciphertext,
+                    transport2_payload,
+                    transport2_ciphertext,
+                }];
+            );
+
+            $(test::<$hash, $hmac<$hash>>(TEST_VECTORS);)+
+        }
+    };
+}
+
+new_test!(noise_sha256, sha2::Sha256, Hmac, SimpleHmac);
+new_test!(noise_sha512, sha2::Sha512, Hmac, SimpleHmac);
+new_test!(noise_blake2s, blake2::Blake2s256, SimpleHmac);
+new_test!(noise_blake2b, blake2::Blake2b512, SimpleHmac);
+
+#[test]
+fn test_mix_key_and_hash() {
+    use hkdf::noise::hkdf3;
+
+    let mut state = SymmetricState::<sha2::Sha256>::new(b"Noise_NNpsk0_25519_ChaChaPoly_SHA256");
+    let ck = *state.chaining_key();
+    let h = *state.handshake_hash();
+    state.mix_key_and_hash(b"psk");
+
+    let (new_ck, temp_h, temp_k) = hkdf3::<Hmac<sha2::Sha256>>(&ck, b"psk");
+    assert_eq!(state.chaining_key()[..], new_ck[..]);
+    assert_eq!(state.cipher_key().unwrap()[..], temp_k[..]);
+    let h = sha2::Sha256::new()
+        .chain_update(h)
+        .chain_update(temp_h)
+        .finalize();
+    assert_eq!(state.handshake_hash()[..], h[..]);
+}
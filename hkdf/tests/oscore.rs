@@ -0,0 +1,187 @@
+//! OSCORE security context derivation tests using the RFC 8613 Appendix C test vectors.
+#![cfg(feature = "oscore")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::Hkdf;
+use hkdf::cose::AlgorithmId;
+use hkdf::hmac::Hmac;
+use hkdf::oscore::{
+    A128Gcm, Aead, AesCcm16_64_128, Info, InfoType, InputParameters, SecurityContext,
+};
+use sha2::Sha256;
+
+const MASTER_SECRET: [u8; 16] = hex!("0102030405060708090a0b0c0d0e0f10");
+const MASTER_SALT: [u8; 8] = hex!("9e7ca92223786340");
+const ID_CONTEXT: [u8; 8] = hex!("37cbf3210017a2d3");
+
+fn derive(
+    master_salt: Option<&[u8]>,
+    sender_id: &[u8],
+    recipient_id: &[u8],
+    id_context: Option<&[u8]>,
+) -> SecurityContext<AesCcm16_64_128> {
+    InputParameters {
+        master_secret: &MASTER_SECRET,
+        master_salt,
+        sender_id,
+        recipient_id,
+        id_context,
+    }
+    .derive::<Hmac<Sha256>, AesCcm16_64_128>()
+}
+
+/// Appendix C.1.1 and C.1.2.
+#[test]
+fn test_vector_1() {
+    let client = derive(Some(&MASTER_SALT), &[], &[0x01], None);
+    assert_eq!(client.sender_key, hex!("f0910ed7295e6ad4b54fc793154302ff"));
+    assert_eq!(
+        client.recipient_key,
+        hex!("ffb14e093c94c9cac9471648b4f98710")
+    );
+    assert_eq!(client.common_iv, hex!("4622d4dd6d944168eefb54987c"));
+
+    let server = derive(Some(&MASTER_SALT), &[0x01], &[], None);
+    assert_eq!(server.sender_key, client.recipient_key);
+    assert_eq!(server.recipient_key, client.sender_key);
+    assert_eq!(server.common_iv, client.common_iv);
+}
+
+/// Appendix C.2.1 and C.2.2.
+#[test]
+fn test_vector_2() {
+    let client = derive(None, &[0x00], &[0x01], None);
+    assert_eq!(client.sender_key, hex!("321b26943253c7ffb6003b0b64d74041"));
+    assert_eq!(
+        client.recipient_key,
+        hex!("e57b5635815177cd679ab4bcec9d7dda")
+    );
+    assert_eq!(client.common_iv, hex!("be35ae297d2dace910c52e99f9"));
+
+    let server = derive(None, &[0x01], &[0x00], None);
+    assert_eq!(server.sender_key, client.recipient_key);
+    assert_eq!(server.recipient_key, client.sender_key);
+    assert_eq!(server.common_iv, client.common_iv);
+}
+
+/// Appendix C.3.1 and C.3.2.
+#[test]
+fn test_vector_3() {
+    let client = derive(Some(&MASTER_SALT), &[], &[0x01], Some(&ID_CONTEXT));
+    assert_eq!(client.sender_key, hex!("af2a1300a5e95788b356336eeecd2b92"));
+    assert_eq!(
+        client.recipient_key,
+        hex!("e39a0c7c77b43f03b4b39ab9a268699f")
+    );
+    assert_eq!(client.common_iv, hex!("2ca58fb85ff1b81c0b7181b85e"));
+
+    let server = derive(Some(&MASTER_SALT), &[0x01], &[], Some(&ID_CONTEXT));
+    assert_eq!(server.sender_key, client.recipient_key);
+    assert_eq!(server.recipient_key, client.sender_key);
+    assert_eq!(server.common_iv, client.common_iv);
+}
+
+#[test]
+fn encode_info() {
+    let mut buf = [0u8; 32];
+    let info = |id, id_context, kind| Info {
+        id,
+        id_context,
+        alg_aead: AesCcm16_64_128::ALG,
+        kind,
+    };
+
+    let len = info(&[], None, InfoType::Key).encode(16, &mut buf).unwrap();
+    assert_eq!(&buf[..len], hex!("8540f60a634b657910"));
+    let len = info(&[0x01], None, InfoType::Key)
+        .encode(16, &mut buf)
+        .unwrap();
+    assert_eq!(&buf[..len], hex!("854101f60a634b657910"));
+    let len = info(&[], None, InfoType::Iv).encode(13, &mut buf).unwrap();
+    assert_eq!(&buf[..len], hex!("8540f60a6249560d"));
+    let len = info(&[], Some(&ID_CONTEXT), InfoType::Key)
+        .encode(16, &mut buf)
+        .unwrap();
+    assert_eq!(&buf[..len], hex!("85404837cbf3210017a2d30a634b657910"));
+
+    assert!(
+        info(&[], None, InfoType::Key)
+            .encode(16, &mut buf[..8])
+            .is_err()
+    );
+
+    let text = Info {
+        alg_aead: AlgorithmId::Text("AES-CCM"),
+        ..info(&[], None, InfoType::Iv)
+    };
+    let len = text.encode(13, &mut buf).unwrap();
+    assert_eq!(&buf[..len], hex!("8540f6674145532d43434d6249560d"));
+}
+
+#[test]
+fn info_expand() {
+    let hkdf = Hkdf::<Sha256>::new(Some(&MASTER_SALT), &MASTER_SECRET);
+    let info = Info {
+        id: &[],
+        id_context: Some(&ID_CONTEXT),
+        alg_aead: AesCcm16_64_128::ALG,
+        kind: InfoType::Iv,
+    };
+    let mut iv = [0u8; 13];
+    info.expand(&hkdf, &mut iv).unwrap();
+    assert_eq!(iv, hex!("2ca58fb85ff1b81c0b7181b85e"));
+}
+
+#[test]
+fn alg_aead() {
+    let params = InputParameters {
+        master_secret: &MASTER_SECRET,
+        master_salt: Some(&MASTER_SALT),
+        sender_id: &[],
+        recipient_id: &[0x01],
+        id_context: None,
+    };
+    let context = params.derive::<Hmac<Sha256>, A128Gcm>();
+
+    let hkdf = Hkdf::<Sha256>::new(Some(&MASTER_SALT), &MASTER_SECRET);
+    let mut key = [0u8; 16];
+    hkdf.expand(&hex!("8540f601634b657910"), &mut key).unwrap();
+    assert_eq!(context.sender_key, key);
+    let mut iv = [0u8; 12];
+    hkdf.expand(&hex!("8540f6016249560c"), &mut iv).unwrap();
+    assert_eq!(context.common_iv, iv);
+
+    assert_eq!(SecurityContext::<A128Gcm>::max_id_len(), 6);
+    assert_eq!(SecurityContext::<AesCcm16_64_128>::max_id_len(), 7);
+}
+
+/// Appendix B.2: the new context uses the concatenation of R1 and R2 as ID Context.
+#[test]
+fn rekey() {
+    let r1 = hex!("0102030405060708");
+    let r2 = hex!("1112131415161718");
+    let mut r1_r2 = [0u8; 16];
+    r1_r2[..8].copy_from_slice(&r1);
+    r1_r2[8..].copy_from_slice(&r2);
+
+    let params = InputParameters {
+        master_secret: &MASTER_SECRET,
+        master_salt: Some(&MASTER_SALT),
+        sender_id: &[],
+        recipient_id: &[0x01],
+        id_context: Some(&ID_CONTEXT),
+    };
+    let rekeyed = params.rekey::<Hmac<Sha256>, AesCcm16_64_128>(&r1, &r2);
+    let expected = InputParameters {
+        id_context: Some(&r1_r2),
+        ..params
+    }
+    .derive::<Hmac<Sha256>, AesCcm16_64_128>();
+    assert_eq!(rekeyed.sender_key, expected.sender_key);
+    assert_eq!(rekeyed.recipient_key, expected.recipient_key);
+    assert_eq!(rekeyed.common_iv, expected.common_iv);
+
+    let old = params.derive::<Hmac<Sha256>, AesCcm16_64_128>();
+    assert_ne!(rekeyed.sender_key, old.sender_key);
+}
@@ -0,0 +1,97 @@
+//! QUIC key derivation tests using the test vectors from
+//! <https://www.rfc-editor.org/rfc/rfc9001#appendix-A> and
+//! <https://www.rfc-editor.org/rfc/rfc9369#appendix-A>.
+#![cfg(feature = "quic")]
+
+use hex_literal::hex;
+use hkdf::quic::{Aes128Gcm, ChaCha20Poly1305, InitialSecrets, Secret, Version};
+use sha2::Sha256;
+
+const DCID: [u8; 8] = hex!("8394c8f03e515708");
+
+#[test]
+fn rfc9001_initial_secrets() {
+    let secrets = InitialSecrets::new(Version::V1, &DCID);
+
+    assert_eq!(
+        secrets.client.as_bytes()[..],
+        hex!("c00cf151ca5be075ed0ebfb5c80323c42d6b7db67881289af4008f1f6c357aea")
+    );
+    let keys = secrets.client.packet_keys::<Aes128Gcm>();
+    assert_eq!(keys.key, hex!("1f369613dd76d5467730efcbe3b1a22d"));
+    assert_eq!(keys.iv, hex!("fa044b2f42a3fd3b46fb255c"));
+    assert_eq!(keys.hp, hex!("9f50449e04a0e810283a1e9933adedd2"));
+
+    assert_eq!(
+        secrets.server.as_bytes()[..],
+        hex!("3c199828fd139efd216c155ad844cc81fb82fa8d7446fa7d78be803acdda951b")
+    );
+    let keys = secrets.server.packet_keys::<Aes128Gcm>();
+    assert_eq!(keys.key, hex!("cf3a5331653c364c88f0f379b6067e37"));
+    assert_eq!(keys.iv, hex!("0ac1493ca1905853b0bba03e"));
+    assert_eq!(keys.hp, hex!("c206b8d9b9f0f37644430b490eeaa314"));
+}
+
+#[test]
+fn rfc9369_initial_secrets() {
+    let secrets = InitialSecrets::new(Version::V2, &DCID);
+
+    assert_eq!(
+        secrets.client.as_bytes()[..],
+        hex!("14ec9d6eb9fd7af83bf5a668bc17a7e283766aade7ecd0891f70f9ff7f4bf47b")
+    );
+    let keys = secrets.client.packet_keys::<Aes128Gcm>();
+    assert_eq!(keys.key, hex!("8b1a0bc121284290a29e0971b5cd045d"));
+    assert_eq!(keys.iv, hex!("91f73e2351d8fa91660e909f"));
+    assert_eq!(keys.hp, hex!("45b95e15235d6f45a6b19cbcb0294ba9"));
+
+    assert_eq!(
+        secrets.server.as_bytes()[..],
+        hex!("0263db1782731bf4588e7e4d93b7463907cb8cd8200b5da55a8bd488eafc37c1")
+    );
+    let keys = secrets.server.packet_keys::<Aes128Gcm>();
+    assert_eq!(keys.key, hex!("82db637861d55e1d011f19ea71d5d2a7"));
+    assert_eq!(keys.iv, hex!("dd13c276499c0249d3310652"));
+    assert_eq!(keys.hp, hex!("edf6d05c83121201b436e16877593c3a"));
+}
+
+/// RFC 9001 Appendix A.5, "ChaCha20-Poly1305 Short Header Packet".
+#[test]
+fn rfc9001_chacha20_poly1305() {
+    let secret = Secret::<Sha256>::new(
+        hex!("9ac312a7f877468ebe69422748ad00a15443f18203a07d6060f688f30f21632b").into(),
+        Version::V1,
+    );
+    let keys = secret.packet_keys::<ChaCha20Poly1305>();
+    assert_eq!(
+        keys.key,
+        hex!("c6d98ff3441c3fe1b2182094f69caa2ed4b716b65488960a7a984979fb23e1c8")
+    );
+    assert_eq!(keys.iv, hex!("e0459b3474bdd0e44a41c144"));
+    assert_eq!(
+        keys.hp,
+        hex!("25a282b9e82f06f21f488917a4fc8f1b73573685608597d0efcb076b0ab7a7a4")
+    );
+    assert_eq!(
+        secret.next_generation().as_bytes()[..],
+        hex!("1223504755036d556342ee9361d253421a826c9ecdf3c7148684b36b714881f9")
+    );
+}
+
+#[test]
+fn key_update() {
+    let secret = Secret::<Sha256>::new(
+        hex!("b8767708f8772358a6ea9fc43e4add2c961b3f5287a6d1467ee0aeab33724dbf").into(),
+        Version::V1,
+    );
+    assert_eq!(
+        secret.next_generation().as_bytes()[..],
+        hex!("42cac8c91cd5eb40682e432edf2d2be9f41a52ca6b22d8e6cdb1e8aca9061fce")
+    );
+
+    let v2 = Secret::<Sha256>::new(*secret.as_bytes(), Version::V2);
+    assert_ne!(
+        v2.next_generation().as_bytes(),
+        secret.next_generation().as_bytes()
+    );
+}
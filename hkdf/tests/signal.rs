@@ -0,0 +1,251 @@
+//! Signal X3DH and Double Ratchet tests using the test vectors of libsignal-protocol-c
+//! (`tests/test_ratchet.c`).
+#![cfg(feature = "signal")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use hex_literal::hex;
+use hkdf::signal::{
+    ChainKey, Curve, Info, LIBSIGNAL_X3DH_INFO, MessageKeys, RatchetError, RatchetState, x3dh,
+};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+fn dh(secret: [u8; 32], public: [u8; 32]) -> [u8; 32] {
+    StaticSecret::from(secret)
+        .diffie_hellman(&PublicKey::from(public))
+        .to_bytes()
+}
+
+fn public(secret: [u8; 32]) -> [u8; 32] {
+    PublicKey::from(&StaticSecret::from(secret)).to_bytes()
+}
+
+#[test]
+fn chain_key_derivation_v3() {
+    let seed = hex!("8ab72d6f4cc5ac0d387eaf463378ddb28edd07385b1cb01250c715982e7ad48f");
+    let cipher_key = hex!("bf51e9d75e0e31031051f82a2491ffc084fa298b7793bd9db620056febf45217");
+    let mac_key = hex!("c6c77d6a73a354337a56435e34607dfe48e3ace14e77314dc6abc172e7a7030b");
+    let next_chain_key = hex!("28e8f8fee54b801eef7c5cfb2f17f32c7b334485bbb70fac6ec10342a246d15d");
+
+    let chain_key = ChainKey::<Sha256>::new(seed, 0);
+    let keys = chain_key.message_keys(Info::LIBSIGNAL.message_keys);
+    assert_eq!(keys.cipher_key, cipher_key);
+    assert_eq!(keys.mac_key, mac_key);
+    assert_eq!(keys.counter, 0);
+
+    let next = chain_key.next_chain_key().unwrap();
+    assert_eq!(next.as_bytes(), &next_chain_key);
+    assert_eq!(next.index(), 1);
+    assert_eq!(next.message_keys(b"WhisperMessageKeys").counter, 1);
+
+    assert!(
+        ChainKey::<Sha256>::new(seed, u32::MAX)
+            .next_chain_key()
+            .is_none()
+    );
+}
+
+/// `test_ratcheting_session_as_bob`: the X3DH output of Bob is the initial sending chain
+/// key of Bob and the receiving chain key of Alice.
+#[test]
+fn ratcheting_session_as_bob() {
+    let bob_identity = hex!("4875cc69ddf8ea0719ec947d61081135868d5fd801f02c0225e516df2156605e");
+    let bob_signed_pre_key =
+        hex!("583900131fb727998b7803fe6ac22cc591f342e4e42a8c8d5d78194209b8d253");
+    let alice_base_public =
+        hex!("472d1fb1a9862c3af6beaca8920277e2b26f4a79213ec7c906aeb35e03cf8950");
+    let alice_identity_public =
+        hex!("b4a8455660ada65b401007f615e654041746432e3339c6875149bceefcb42b4a");
+    let receiver_and_sender_chain =
+        hex!("9797caca53c989bbe229a40ca7727010eb2604fc14945d77958a0aeda088b44d");
+
+    let dh_outputs = [
+        dh(bob_signed_pre_key, alice_identity_public),
+        dh(bob_identity, alice_base_public),
+        dh(bob_signed_pre_key, alice_base_public),
+    ];
+    let dh_outputs = dh_outputs.each_ref().map(|dh| &dh[..]);
+    let mut okm = [0u8; 64];
+    x3dh::<Sha256>(Curve::X25519, &dh_outputs, LIBSIGNAL_X3DH_INFO, &mut okm).unwrap();
+    assert_eq!(okm[32..], receiver_and_sender_chain);
+}
+
+#[test]
+fn x3dh_prefix() {
+    let dh_outputs: [&[u8]; 1] = [&[0x01; 56]];
+    let mut x25519 = [0u8; 32];
+    let mut x448 = [0u8; 32];
+    x3dh::<Sha256>(Curve::X25519, &dh_outputs, b"info", &mut x25519).unwrap();
+    x3dh::<Sha256>(Curve::X448, &dh_outputs, b"info", &mut x448).unwrap();
+    assert_ne!(x25519, x448);
+
+    let mut okm = [0u8; 255 * 32 + 1];
+    assert!(x3dh::<Sha256>(Curve::X25519, &dh_outputs, b"info", &mut okm).is_err());
+}
+
+struct Party {
+    ratchet: [u8; 32],
+    state: RatchetState<Sha256, 4>,
+}
+
+impl Party {
+    fn send(&mut self) -> (MessageKeys, [u8; 32]) {
+        (self.state.sending_keys().unwrap(), public(self.ratchet))
+    }
+
+    /// Receive a message with a new ratchet key and reply with a new ratchet key pair.
+    fn turn(&mut self, their_ratchet: [u8; 32], previous_chain_length: u32, new_ratchet: u8) {
+        let dh_output = dh(self.ratchet, their_ratchet);
+        self.state
+            .ratchet_receive(&their_ratchet, previous_chain_length, &dh_output)
+            .unwrap();
+        self.ratchet = [new_ratchet; 32];
+        self.state.ratchet_send(&dh(self.ratchet, their_ratchet));
+    }
+}
+
+fn assert_keys_eq(sent: &MessageKeys, received: &MessageKeys) {
+    assert_eq!(sent.cipher_key, received.cipher_key);
+    assert_eq!(sent.mac_key, received.mac_key);
+    assert_eq!(sent.iv, received.iv);
+    assert_eq!(sent.counter, received.counter);
+}
+
+#[test]
+fn double_ratchet() {
+    let sk = [0x42; 32];
+    let bob_signed_pre_key = [0x01; 32];
+    let alice_ratchet = [0x02; 32];
+
+    let mut alice = Party {
+        ratchet: alice_ratchet,
+        state: RatchetState::new_initiator(
+            sk,
+            Info::LIBSIGNAL,
+            &dh(alice_ratchet, public(bob_signed_pre_key)),
+        ),
+    };
+    let mut bob = Party {
+        ratchet: bob_signed_pre_key,
+        state: RatchetState::new_responder(sk, Info::LIBSIGNAL),
+    };
+    assert_eq!(
+        bob.state.sending_keys().unwrap_err(),
+        RatchetError::NoSendingChain
+    );
+
+    // Alice sends three messages, Bob receives them out of order
+    let (a0, a_key) = alice.send();
+    let (a1, _) = alice.send();
+    let (a2, _) = alice.send();
+    assert_eq!(
+        bob.state.receiving_keys(&a_key, 0).unwrap_err(),
+        RatchetError::UnknownRatchetKey
+    );
+    bob.turn(a_key, 0, 0x03);
+    assert_keys_eq(&a2, &bob.state.receiving_keys(&a_key, 2).unwrap());
+    assert_eq!(bob.state.skipped_len(), 2);
+    assert_keys_eq(&a0, &bob.state.receiving_keys(&a_key, 0).unwrap());
+    assert_eq!(
+        bob.state.receiving_keys(&a_key, 0).unwrap_err(),
+        RatchetError::DuplicateMessage
+    );
+    assert_eq!(
+        bob.state.receiving_keys(&a_key, 2).unwrap_err(),
+        RatchetError::DuplicateMessage
+    );
+
+    // Bob replies, Alice receives it and the skipped message a1 is delivered late
+    let (b0, b_key) = bob.send();
+    alice.turn(b_key, 0, 0x04);
+    assert_keys_eq(&b0, &alice.state.receiving_keys(&b_key, 0).unwrap());
+    assert_keys_eq(&a1, &bob.state.receiving_keys(&a_key, 1).unwrap());
+    assert_eq!(bob.state.skipped_len(), 0);
+
+    // Alice sends two messages of a new chain, the first one is lost
+    let (_, a_key_2) = alice.send();
+    let (a4, _) = alice.send();
+    bob.turn(a_key_2, 3, 0x05);
+    assert_keys_eq(&a4, &bob.state.receiving_keys(&a_key_2, 1).unwrap());
+    assert_eq!(bob.state.skipped_len(), 1);
+
+    // Bob sends a chain of three messages which is delayed until Bob has started a
+    // new chain
+    let (b1, b_key_2) = bob.send();
+    let (b2, _) = bob.send();
+    let (b3, _) = bob.send();
+    alice.turn(b_key_2, 1, 0x06);
+    let (a5, a_key_3) = alice.send();
+    bob.turn(a_key_3, 2, 0x07);
+    assert_keys_eq(&a5, &bob.state.receiving_keys(&a_key_3, 0).unwrap());
+    assert_eq!(bob.state.skipped_len(), 1);
+    let (b4, b_key_3) = bob.send();
+
+    assert_eq!(
+        alice.state.receiving_keys(&b_key_3, 0).unwrap_err(),
+        RatchetError::UnknownRatchetKey
+    );
+    alice.turn(b_key_3, 3, 0x08);
+    assert_eq!(alice.state.skipped_len(), 3);
+    assert_keys_eq(&b4, &alice.state.receiving_keys(&b_key_3, 0).unwrap());
+    assert_keys_eq(&b3, &alice.state.receiving_keys(&b_key_2, 2).unwrap());
+    assert_keys_eq(&b1, &alice.state.receiving_keys(&b_key_2, 0).unwrap());
+    assert_keys_eq(&b2, &alice.state.receiving_keys(&b_key_2, 1).unwrap());
+    assert_eq!(alice.state.skipped_len(), 0);
+}
+
+#[test]
+fn skipped_keys_limit() {
+    let sk = [0x42; 32];
+    let their_key = [0x01; 32];
+    let mut alice = RatchetState::<Sha256, 4>::new_initiator(sk, Info::LIBSIGNAL, b"dh");
+    let mut bob = RatchetState::<Sha256, 4>::new_responder(sk, Info::LIBSIGNAL);
+    bob.ratchet_receive(&their_key, 0, b"dh").unwrap();
+
+    let sent: Vec<_> = (0..10).map(|_| alice.sending_keys().unwrap()).collect();
+    assert_eq!(
+        bob.receiving_keys(&their_key, 5).unwrap_err(),
+        RatchetError::TooManySkippedMessages
+    );
+    assert_eq!(bob.skipped_len(), 0);
+    assert_keys_eq(&sent[4], &bob.receiving_keys(&their_key, 4).unwrap());
+    assert_eq!(bob.skipped_len(), 4);
+
+    // The oldest skipped keys are evicted
+    assert_keys_eq(&sent[7], &bob.receiving_keys(&their_key, 7).unwrap());
+    assert_eq!(bob.skipped_len(), 4);
+    assert_eq!(
+        bob.receiving_keys(&their_key, 0).unwrap_err(),
+        RatchetError::DuplicateMessage
+    );
+    assert_keys_eq(&sent[3], &bob.receiving_keys(&their_key, 3).unwrap());
+
+    // A new ratchet key can not skip more than the limit of the current chain
+    let root_key = *bob.root_key().as_bytes();
+    assert_eq!(
+        bob.ratchet_receive(&[0x02; 32], 13, b"dh").unwrap_err(),
+        RatchetError::TooManySkippedMessages
+    );
+    assert_eq!(bob.root_key().as_bytes(), &root_key);
+    assert_eq!(bob.receiving_chain().unwrap().0, &their_key);
+    bob.ratchet_receive(&[0x02; 32], 10, b"dh").unwrap();
+    assert_keys_eq(&sent[9], &bob.receiving_keys(&their_key, 9).unwrap());
+}
+
+#[test]
+fn zero_skipped_keys() {
+    let sk = [0x42; 32];
+    let their_key = [0x01; 32];
+    let mut alice = RatchetState::<Sha256, 0>::new_initiator(sk, Info::LIBSIGNAL, b"dh");
+    let mut bob = RatchetState::<Sha256, 0>::new_responder(sk, Info::LIBSIGNAL);
+    bob.ratchet_receive(&their_key, 0, b"dh").unwrap();
+
+    let _ = alice.sending_keys().unwrap();
+    let sent = alice.sending_keys().unwrap();
+    assert_eq!(
+        bob.receiving_keys(&their_key, 1).unwrap_err(),
+        RatchetError::TooManySkippedMessages
+    );
+    let _ = bob.receiving_keys(&their_key, 0).unwrap();
+    assert_keys_eq(&sent, &bob.receiving_keys(&their_key, 1).unwrap());
+}
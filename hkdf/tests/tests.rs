@@ -3,7 +3,11 @@
 use core::iter;
 
 use hex_literal::hex;
-use hkdf::{Hkdf, HkdfExtract, SimpleHkdfExtract};
+use hkdf::{ExpandReader, FramedInfo, Hkdf, HkdfExtract, SimpleHkdfExtract};
+use hmac::{
+    Hmac,
+    digest::{common::KeySizeUser, consts::U32},
+};
 use sha1::Sha1;
 use sha2::Sha256;
 
@@ -125,6 +129,73 @@ fn test_expand_multi_info() {
     }
 }
 
+#[test]
+fn test_expand_framed_info() {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"mysalt"), b"some ikm here");
+
+    let mut info = Vec::new();
+    for component in COMPONENTS {
+        info.extend(u32::try_from(component.len()).unwrap().to_be_bytes());
+        info.extend(*component);
+    }
+    let mut expected = [0u8; 42];
+    hkdf.expand(&info, &mut expected).unwrap();
+
+    let mut okm = [0u8; 42];
+    hkdf.expand_framed_info(COMPONENTS, &mut okm).unwrap();
+    assert_eq!(okm, expected);
+
+    // Moving bytes between components or splitting components changes the output
+    let mut other = [0u8; 42];
+    hkdf.expand_framed_info(&[b"ab", b"c"], &mut okm).unwrap();
+    hkdf.expand_framed_info(&[b"a", b"bc"], &mut other).unwrap();
+    assert_ne!(okm, other);
+    hkdf.expand_framed_info(&[b"abc"], &mut other).unwrap();
+    assert_ne!(okm, other);
+    hkdf.expand_framed_info(&[b"ab", b"c", b""], &mut other)
+        .unwrap();
+    assert_ne!(okm, other);
+
+    let mut okm = [0u8; MAX_SHA256_LENGTH + 1];
+    assert!(hkdf.expand_framed_info(COMPONENTS, &mut okm).is_err());
+}
+
+#[test]
+fn test_framed_info() {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"mysalt"), b"some ikm here");
+    let info = FramedInfo::builder(b"label\x00")
+        .with_context(b"context")
+        .build();
+
+    let mut buf = [0u8; 32];
+    let len = info.encode(&mut buf).unwrap();
+    assert_eq!(info.encoded_len().unwrap(), len);
+    assert_eq!(
+        &buf[..len],
+        b"\x00\x00\x00\x06label\x00\x00\x00\x00\x07context"
+    );
+    assert!(info.encode(&mut buf[..len - 1]).is_err());
+
+    let mut expected = [0u8; 32];
+    hkdf.expand(&buf[..len], &mut expected).unwrap();
+    let mut okm = [0u8; 32];
+    info.expand(&hkdf, &mut okm).unwrap();
+    assert_eq!(okm, expected);
+
+    // The label `b"label\x00"` is not confused with the label `b"label"` and a context
+    // starting with a NULL byte
+    let other = FramedInfo::builder(b"label")
+        .with_context(b"\x00context")
+        .build();
+    let mut other_okm = [0u8; 32];
+    other.expand(&hkdf, &mut other_okm).unwrap();
+    assert_ne!(okm, other_okm);
+
+    let empty = FramedInfo::builder(b"").build();
+    let len = empty.encode(&mut buf).unwrap();
+    assert_eq!(&buf[..len], &[0u8; 8]);
+}
+
 #[test]
 fn test_extract_streaming() {
     let ikm_components = COMPONENTS;
@@ -184,9 +255,104 @@ fn test_extract_streaming() {
     }
 }
 
+#[test]
+fn test_expand_reader() {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"mysalt"), b"some ikm here");
+    let info = COMPONENTS[0];
+    let mut expected = vec![0u8; MAX_SHA256_LENGTH];
+    hkdf.expand(info, &mut expected).unwrap();
+
+    // Read the OKM in chunks which are smaller than, equal to and span several blocks
+    for chunk_len in (1..=70).chain([255, 256, 1000]) {
+        let mut reader = hkdf.expand_reader(info);
+        let mut okm = vec![0u8; MAX_SHA256_LENGTH];
+        for chunk in okm.chunks_mut(chunk_len) {
+            reader.read(chunk).unwrap();
+        }
+        assert_eq!(okm, expected);
+        assert_eq!(reader.remaining(), 0);
+        assert!(reader.read(&mut [0u8]).is_err());
+        reader.read(&mut []).unwrap();
+    }
+}
+
+#[test]
+fn test_expand_reader_max_length_exceeded() {
+    let hkdf = Hkdf::<Sha256>::new(Some(&[]), &[]);
+    let mut expected = [0u8; 64];
+    hkdf.expand(&[], &mut expected).unwrap();
+
+    let mut reader = hkdf.expand_reader(&[]);
+    let mut okm = vec![0u8; MAX_SHA256_LENGTH + 1];
+    assert!(reader.read(&mut okm).is_err());
+
+    // A failed read does not consume any output
+    assert_eq!(reader.remaining(), MAX_SHA256_LENGTH);
+    reader.read(&mut okm[..64]).unwrap();
+    assert_eq!(okm[..64], expected);
+}
+
+#[test]
+fn test_expand_array() {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"mysalt"), b"some ikm here");
+    let info = COMPONENTS[0];
+    let mut expected = vec![0u8; MAX_SHA256_LENGTH];
+    hkdf.expand(info, &mut expected).unwrap();
+
+    let okm: [u8; 0] = hkdf.expand_array(info);
+    assert_eq!(okm[..], expected[..0]);
+    let okm: [u8; 42] = hkdf.expand_array(info);
+    assert_eq!(okm[..], expected[..42]);
+    let okm: [u8; MAX_SHA256_LENGTH] = hkdf.expand_array(info);
+    assert_eq!(okm[..], expected[..]);
+}
+
+#[test]
+fn test_expand_key() {
+    struct Key;
+    impl KeySizeUser for Key {
+        type KeySize = U32;
+    }
+
+    let hkdf = Hkdf::<Sha256>::new(Some(b"mysalt"), b"some ikm here");
+    let info = COMPONENTS[0];
+    let mut expected = [0u8; 32];
+    hkdf.expand(info, &mut expected).unwrap();
+
+    let key = hkdf.expand_key::<Key>(info);
+    assert_eq!(key[..], expected[..]);
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn test_zeroizing() {
+    struct Key;
+    impl KeySizeUser for Key {
+        type KeySize = U32;
+    }
+
+    let (prk, hkdf) = Hkdf::<Sha256>::extract(Some(b"mysalt"), b"some ikm here");
+    let (zeroizing_prk, _) = Hkdf::<Sha256>::extract_zeroizing(Some(b"mysalt"), b"some ikm here");
+    assert_eq!(*zeroizing_prk, prk);
+
+    let mut extract = HkdfExtract::<Sha256>::new(Some(b"mysalt"));
+    extract.input_ikm(b"some ikm here");
+    let (zeroizing_prk, _) = extract.finalize_zeroizing();
+    assert_eq!(*zeroizing_prk, prk);
+
+    let info = COMPONENTS[0];
+    let key: [u8; 42] = hkdf.expand_array(info);
+    assert_eq!(*hkdf.expand_array_zeroizing::<42>(info), key);
+    assert_eq!(
+        *hkdf.expand_key_zeroizing::<Key>(info),
+        hkdf.expand_key::<Key>(info)
+    );
+}
+
 #[test]
 fn test_debug_impls() {
     fn needs_debug<T: core::fmt::Debug>() {}
     needs_debug::<Hkdf<Sha256>>();
     needs_debug::<HkdfExtract<Sha256>>();
+    needs_debug::<ExpandReader<'_, Hmac<Sha256>>>();
 }
@@ -0,0 +1,268 @@
+//! TLS 1.3 key derivation tests using the traces from
+//! <https://www.rfc-editor.org/rfc/rfc8448>.
+#![cfg(feature = "tls13")]
+
+use hex_literal::hex;
+use hkdf::{
+    Hkdf,
+    tls13::{
+        ClientFinishedHash, ClientHelloHash, DTLS13_LABEL_PREFIX, EarlySecret, ImportedIdentity,
+        PskKind, ServerFinishedHash, ServerHelloHash, import_psk, resumption_psk,
+    },
+};
+use sha2::{Digest, Sha256};
+
+/// RFC 8448 Section 3, "Simple 1-RTT Handshake".
+const HANDSHAKE_SECRET: [u8; 32] =
+    hex!("1dc826e93606aa6fdc0aadc12f741b01046aa6b99f691ed221a9f0ca043fbeac");
+const CLIENT_HS_TRAFFIC: [u8; 32] =
+    hex!("b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21");
+const SERVER_HS_TRAFFIC: [u8; 32] =
+    hex!("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38");
+const RES_MASTER: [u8; 32] =
+    hex!("7df235f2031d2a051287d02b0241b0bfdaf86cc856231f2d5aba46c434ec196c");
+
+#[test]
+fn rfc8448_derive_secret() {
+    let early = Hkdf::<Sha256>::from_prk(&hex!(
+        "33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a"
+    ))
+    .unwrap();
+    let derived = early
+        .derive_secret(b"derived", &Sha256::digest(b""))
+        .unwrap();
+    assert_eq!(
+        derived[..],
+        hex!("6f2615a108c702c5678f54fc9dbab69716c076189c48250cebeac3576c3611ba")
+    );
+
+    let hs = Hkdf::<Sha256>::from_prk(&HANDSHAKE_SECRET).unwrap();
+    let transcript = hex!("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8");
+    assert_eq!(
+        hs.derive_secret(b"c hs traffic", &transcript).unwrap()[..],
+        CLIENT_HS_TRAFFIC
+    );
+    assert_eq!(
+        hs.derive_secret(b"s hs traffic", &transcript).unwrap()[..],
+        SERVER_HS_TRAFFIC
+    );
+}
+
+#[test]
+fn rfc8448_expand_label() {
+    let client = Hkdf::<Sha256>::from_prk(&CLIENT_HS_TRAFFIC).unwrap();
+    let mut key = [0u8; 16];
+    let mut iv = [0u8; 12];
+    let mut finished = [0u8; 32];
+    client.expand_label(b"key", &[], &mut key).unwrap();
+    client.expand_label(b"iv", &[], &mut iv).unwrap();
+    client
+        .expand_label(b"finished", &[], &mut finished)
+        .unwrap();
+    assert_eq!(key, hex!("dbfaa693d1762c5b666af5d950258d01"));
+    assert_eq!(iv, hex!("5bd3c71b836e0b76bb73265f"));
+    assert_eq!(
+        finished,
+        hex!("b80ad01015fb2f0bd65ff7d4da5d6bf83f84821d1f87fdc7d3c75b5a7b42d9c4")
+    );
+
+    let server = Hkdf::<Sha256>::from_prk(&SERVER_HS_TRAFFIC).unwrap();
+    server.expand_label(b"key", &[], &mut key).unwrap();
+    server.expand_label(b"iv", &[], &mut iv).unwrap();
+    assert_eq!(key, hex!("3fce516009c21727d0f2e4e86ee403bc"));
+    assert_eq!(iv, hex!("5d313eb2671276ee13000b30"));
+
+    let res = Hkdf::<Sha256>::from_prk(&RES_MASTER).unwrap();
+    let mut psk = [0u8; 32];
+    res.expand_label(b"resumption", &hex!("0000"), &mut psk)
+        .unwrap();
+    assert_eq!(
+        psk,
+        hex!("4ecd0eb6ec3b4d87f5d6028f922ca4c5851a277fd41311c9e62d2c9492e1c4f3")
+    );
+}
+
+#[test]
+fn dtls13_prefix() {
+    let hkdf = Hkdf::<Sha256>::from_prk(&CLIENT_HS_TRAFFIC).unwrap();
+    let mut okm = [0u8; 16];
+    hkdf.expand_label_with_prefix(DTLS13_LABEL_PREFIX, b"key", &[], &mut okm)
+        .unwrap();
+
+    let mut expected = [0u8; 16];
+    let info = [&[0, 16, 9][..], b"dtls13key", &[0]].concat();
+    hkdf.expand(&info, &mut expected).unwrap();
+    assert_eq!(okm, expected);
+}
+
+#[test]
+fn invalid_lengths() {
+    let hkdf = Hkdf::<Sha256>::from_prk(&CLIENT_HS_TRAFFIC).unwrap();
+    let mut okm = [0u8; 16];
+    assert!(hkdf.expand_label(b"", &[], &mut okm).is_err());
+    assert!(hkdf.expand_label(&[b'a'; 250], &[], &mut okm).is_err());
+    assert!(hkdf.expand_label(b"key", &[0; 256], &mut okm).is_err());
+    assert!(hkdf.expand_label(&[b'a'; 249], &[0; 255], &mut okm).is_ok());
+
+    let mut okm = vec![0u8; 255 * 32 + 1];
+    assert!(hkdf.expand_label(b"key", &[], &mut okm).is_err());
+    let mut okm = vec![0u8; 1 << 16];
+    assert!(hkdf.expand_label(b"key", &[], &mut okm).is_err());
+}
+
+/// RFC 8448 Section 3, "Simple 1-RTT Handshake".
+#[test]
+fn rfc8448_key_schedule_1rtt() {
+    let early = EarlySecret::<Sha256>::new(None);
+    let hs = early.into_handshake_secret(Some(&hex!(
+        "8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d"
+    )));
+    let hash = ServerHelloHash::after_server_hello(
+        hex!("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8").into(),
+    );
+    let client = hs.client_handshake_traffic_secret(&hash);
+    let server = hs.server_handshake_traffic_secret(&hash);
+    assert_eq!(client.as_bytes()[..], CLIENT_HS_TRAFFIC);
+    assert_eq!(server.as_bytes()[..], SERVER_HS_TRAFFIC);
+
+    let mut key = [0u8; 16];
+    let mut iv = [0u8; 12];
+    server.traffic_key(&mut key).unwrap();
+    server.traffic_iv(&mut iv).unwrap();
+    assert_eq!(key, hex!("3fce516009c21727d0f2e4e86ee403bc"));
+    assert_eq!(iv, hex!("5d313eb2671276ee13000b30"));
+    assert_eq!(
+        client.finished_key()[..],
+        hex!("b80ad01015fb2f0bd65ff7d4da5d6bf83f84821d1f87fdc7d3c75b5a7b42d9c4")
+    );
+
+    let psk = resumption_psk::<Sha256>(&RES_MASTER.into(), &hex!("0000")).unwrap();
+    assert_eq!(
+        psk[..],
+        hex!("4ecd0eb6ec3b4d87f5d6028f922ca4c5851a277fd41311c9e62d2c9492e1c4f3")
+    );
+}
+
+/// RFC 8448 Section 4, "Resumed 0-RTT Handshake".
+#[test]
+fn rfc8448_key_schedule_resumed_0rtt() {
+    let psk = hex!("4ecd0eb6ec3b4d87f5d6028f922ca4c5851a277fd41311c9e62d2c9492e1c4f3");
+    let early = EarlySecret::<Sha256>::new(Some(&psk));
+
+    let binder_key = early.binder_key(PskKind::Resumption);
+    let hkdf = Hkdf::<Sha256>::from_prk(&hex!(
+        "9b2188e9b2fc6d64d71dc329900e20bb41915000f678aa839cbb797cb7d8332c"
+    ))
+    .unwrap();
+    assert_eq!(
+        binder_key.as_bytes()[..],
+        hkdf.derive_secret(b"res binder", &Sha256::digest(b""))
+            .unwrap()[..]
+    );
+
+    let hash = ClientHelloHash::after_client_hello(
+        hex!("08ad0fa05d7c7233b1775ba2ff9f4c5b8b59276b7f227f13a976245f5d960913").into(),
+    );
+    assert_eq!(
+        early.client_early_traffic_secret(&hash).as_bytes()[..],
+        hex!("3fbbe6a60deb66c30a32795aba0eff7eaa10105586e7be5c09678d63b6caab62")
+    );
+    assert_eq!(
+        early.early_exporter_master_secret(&hash)[..],
+        hex!("b2026866610937d7423e5be90862ccf24c0e6091186d34f812089ff5be2ef7df")
+    );
+
+    let hs = early.into_handshake_secret(Some(&hex!(
+        "f44194756ff9ec9d25180635d66ea6824c6ab3bf179977be37f723570e7ccb2e"
+    )));
+    let hash = ServerHelloHash::after_server_hello(
+        hex!("f736cb34fe25e701551bee6fd24c1cc7102a7daf9405cb15d97aafe16f757d03").into(),
+    );
+    let client_hs = hs.client_handshake_traffic_secret(&hash);
+    assert_eq!(
+        client_hs.as_bytes()[..],
+        hex!("2faac08f851d35fea3604fcb4de82dc62c9b164a70974d0462e27f1ab278700f")
+    );
+    assert_eq!(
+        client_hs.finished_key()[..],
+        hex!("5ace394c26980d581243f627d1150ae27e37fa52364e0a7f20ac686d09cd0e8e")
+    );
+    assert_eq!(
+        hs.server_handshake_traffic_secret(&hash).as_bytes()[..],
+        hex!("fe927ae271312e8bf0275b581c54eef020450dc4ecffaa05a1a35d27518e7803")
+    );
+
+    let master = hs.into_master_secret();
+    let hash = ServerFinishedHash::after_server_finished(
+        hex!("b0aeffc46a2cfe33114e6fd7d51f9f04b1ca3c497dab08934a774a9d9ad7dbf3").into(),
+    );
+    let client_ap = master.client_application_traffic_secret(&hash);
+    assert_eq!(
+        client_ap.as_bytes()[..],
+        hex!("2abbf2b8e381d23dbebe1dd2a7d16a8bf484cb4950d23fb7fb7fa8547062d9a1")
+    );
+    assert_eq!(
+        master.server_application_traffic_secret(&hash).as_bytes()[..],
+        hex!("cc21f1bf8feb7dd5fa505bd9c4b468a9984d554a993dc49e6d285598fb672691")
+    );
+    assert_eq!(
+        master.exporter_master_secret(&hash)[..],
+        hex!("3fd93d4ffddc98e64b14dd107aedf8ee4add23f4510f58a4592d0b201bee56b4")
+    );
+    let hash = ClientFinishedHash::after_client_finished(
+        hex!("c3c122e0bd907a4a3ff6112d8fd53dbf89c773d9552e8b6b9d56d361b3a97bf6").into(),
+    );
+    assert_eq!(
+        master.resumption_master_secret(&hash)[..],
+        hex!("5e95bdf1f89005ea2e9aa0ba85e728e3c19c5fe0c699e3f5bee59faebd0b5406")
+    );
+
+    let next = client_ap.next_generation();
+    let mut expected = [0u8; 32];
+    Hkdf::<Sha256>::from_prk(client_ap.as_bytes())
+        .unwrap()
+        .expand_label(b"traffic upd", &[], &mut expected)
+        .unwrap();
+    assert_eq!(next.as_bytes()[..], expected);
+}
+
+#[test]
+fn rfc9258_import_psk() {
+    let epsk = [0x42; 32];
+    let identity = ImportedIdentity {
+        external_identity: b"psk identity",
+        context: b"",
+        target_protocol: 0x0304,
+        target_kdf: 0x0001,
+    };
+    let mut ipsk = [0u8; 32];
+    import_psk::<Sha256>(&epsk, &identity, &mut ipsk).unwrap();
+
+    let serialized = [
+        &[0, 12][..],
+        b"psk identity",
+        &[0, 0],
+        &[0x03, 0x04],
+        &[0x00, 0x01],
+    ]
+    .concat();
+    let (_, epskx) = Hkdf::<Sha256>::extract(Some(&[0; 32]), &epsk);
+    let mut expected = [0u8; 32];
+    epskx
+        .expand_label(b"derived psk", &Sha256::digest(&serialized), &mut expected)
+        .unwrap();
+    assert_eq!(ipsk, expected);
+
+    let empty_identity = ImportedIdentity {
+        external_identity: b"",
+        ..identity
+    };
+    assert!(import_psk::<Sha256>(&epsk, &empty_identity, &mut ipsk).is_err());
+
+    // The imported PSK uses its own binder label.
+    let early = EarlySecret::<Sha256>::new(Some(&ipsk));
+    assert_ne!(
+        early.binder_key(PskKind::Imported).as_bytes(),
+        early.binder_key(PskKind::External).as_bytes()
+    );
+}
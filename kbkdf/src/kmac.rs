@@ -0,0 +1,217 @@
+//! KBKDF with KMAC, see [NIST SP 800-108r1 Section 4.4](https://doi.org/10.6028/NIST.SP.800-108r1-upd1).
+//!
+//! The key is derived with a single call of an extendable-output PRF:
+//!
+//! ```text
+//! KDF(K, Label, Context, L) = KMAC#(K, Context, L, Label)
+//! ```
+
+use crate::Params;
+use core::marker::PhantomData;
+use digest::{array::Array, common::KeySizeUser};
+
+#[cfg(feature = "kmac")]
+use {
+    core::fmt,
+    digest::{
+        ExtendableOutput, Update, XofReader,
+        common::BlockSizeUser,
+        consts::{U0, U136, U168},
+        typenum::Unsigned,
+    },
+    sha3::block_api::{Sha3HasherCore, Sha3ReaderCore},
+};
+
+#[cfg(feature = "zeroize")]
+use digest::zeroize::Zeroizing;
+
+/// Pseudorandom function with output of arbitrary length, e.g. KMAC.
+///
+/// Unlike for a [`Mac`][digest::Mac], the output length is an input of the PRF, so outputs
+/// of different lengths are unrelated.
+pub trait XofPrf {
+    /// Creates a new PRF instance with the key `key` and the customization string
+    /// `customization`.
+    fn new(key: &[u8], customization: &[u8]) -> Self;
+
+    /// Updates the PRF state with `data`.
+    fn update(&mut self, data: &[u8]);
+
+    /// Fills `out` with `out.len()` bytes of PRF output.
+    fn finalize_into(self, out: &mut [u8]);
+}
+
+/// KBKDF with KMAC.
+///
+/// `kin` is the key of the PRF, `label` its customization string and `context` its input.
+/// The PRF needs no counter and its output length is not bounded, so the other parameters
+/// are ignored:
+///
+#[cfg_attr(feature = "kmac", doc = "```")]
+#[cfg_attr(not(feature = "kmac"), doc = "```ignore")]
+/// use kbkdf::{Kmac, Kmac256, Params};
+///
+/// struct MyKey;
+///
+/// impl kbkdf::digest::common::KeySizeUser for MyKey {
+///     type KeySize = kbkdf::digest::consts::U32;
+/// }
+///
+/// let kmac = Kmac::<Kmac256, MyKey>::default();
+/// let params = Params::builder(b"secret")
+///     .with_label(b"label")
+///     .with_context(b"context")
+///     .build();
+/// let key = kmac.derive(params);
+///
+/// let mut long_key = [0u8; 1000];
+/// kmac.derive_into(params, &mut long_key);
+/// ```
+#[derive(Debug)]
+pub struct Kmac<Prf, K> {
+    _marker: PhantomData<(Prf, K)>,
+}
+
+impl<Prf, K> Default for Kmac<Prf, K> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Prf: XofPrf, K: KeySizeUser> Kmac<Prf, K> {
+    /// Derives `key` from `kin` and other parameters.
+    #[must_use]
+    pub fn derive(&self, params: Params<'_, '_, '_>) -> Array<u8, K::KeySize> {
+        let mut output = Array::<u8, K::KeySize>::default();
+        self.derive_into(params, &mut output);
+        output
+    }
+
+    /// Derives `key` from `kin` and other parameters, wrapped in [`Zeroizing`].
+    #[cfg(feature = "zeroize")]
+    #[must_use]
+    pub fn derive_zeroizing(&self, params: Params<'_, '_, '_>) -> Zeroizing<Array<u8, K::KeySize>> {
+        let mut output = Zeroizing::new(Array::<u8, K::KeySize>::default());
+        self.derive_into(params, &mut output);
+        output
+    }
+
+    /// Derives `out.len()` bytes from `kin` and other parameters into `out`.
+    ///
+    /// Unlike [`derive`][Self::derive], the length `L` of the derived key is computed from
+    /// the length of `out` at runtime, `K` is not used.
+    pub fn derive_into(&self, params: Params<'_, '_, '_>, out: &mut [u8]) {
+        let mut prf = Prf::new(params.kin, params.label);
+        prf.update(params.context);
+        prf.finalize_into(out);
+    }
+}
+
+/// `left_encode(x)` or `right_encode(x)` of NIST SP 800-185 Section 2.3.1.
+#[cfg(feature = "kmac")]
+fn encode(x: u128, right: bool, buf: &mut [u8; 17]) -> &[u8] {
+    let n = (1..16)
+        .find(|n: &u8| x >> (8 * u32::from(*n)) == 0)
+        .unwrap_or(16);
+    let len = usize::from(n);
+    let bytes = &x.to_be_bytes()[16 - len..];
+    if right {
+        buf[..len].copy_from_slice(bytes);
+        buf[len] = n;
+    } else {
+        buf[0] = n;
+        buf[1..=len].copy_from_slice(bytes);
+    }
+    &buf[..=len]
+}
+
+/// Length of `len` bytes in bits.
+#[cfg(feature = "kmac")]
+fn bit_len(len: usize) -> u128 {
+    len as u128 * 8
+}
+
+/// Padding of cSHAKE with a non-empty function name or customization string.
+#[cfg(feature = "kmac")]
+const CSHAKE_PAD: u8 = 0x04;
+
+#[cfg(feature = "kmac")]
+digest::buffer_xof!(
+    /// cSHAKE128 of NIST SP 800-185 Section 3, before absorbing its function name and
+    /// customization string.
+    struct CShake128(Sha3HasherCore<U168, U0, CSHAKE_PAD>);
+    impl: BlockSizeUser Clone Default Update;
+    /// cSHAKE128 reader.
+    struct CShake128Reader(Sha3ReaderCore<U168>);
+    impl: ;
+);
+
+#[cfg(feature = "kmac")]
+digest::buffer_xof!(
+    /// cSHAKE256 of NIST SP 800-185 Section 3, before absorbing its function name and
+    /// customization string.
+    struct CShake256(Sha3HasherCore<U136, U0, CSHAKE_PAD>);
+    impl: BlockSizeUser Clone Default Update;
+    /// cSHAKE256 reader.
+    struct CShake256Reader(Sha3ReaderCore<U136>);
+    impl: ;
+);
+
+/// Absorbs `bytepad(encode_string(strings[0]) || ... , rate)` of NIST SP 800-185
+/// Section 2.3.3.
+#[cfg(feature = "kmac")]
+fn absorb_bytepad<C: Update + BlockSizeUser>(cshake: &mut C, strings: &[&[u8]]) {
+    let rate = C::BlockSize::USIZE;
+    let mut buf = [0u8; 17];
+    let rate_encoding = encode(rate as u128, false, &mut buf);
+    cshake.update(rate_encoding);
+    let mut len = rate_encoding.len();
+    for string in strings {
+        let len_encoding = encode(bit_len(string.len()), false, &mut buf);
+        cshake.update(len_encoding);
+        cshake.update(string);
+        len += len_encoding.len() + string.len();
+    }
+    cshake.update(&[0; 168][..(rate - len % rate) % rate]);
+}
+
+macro_rules! kmac {
+    ($name:ident, $cshake:ident, $doc:literal) => {
+        #[doc = $doc]
+        #[cfg(feature = "kmac")]
+        #[derive(Clone)]
+        pub struct $name($cshake);
+
+        #[cfg(feature = "kmac")]
+        impl XofPrf for $name {
+            fn new(key: &[u8], customization: &[u8]) -> Self {
+                let mut cshake = $cshake::default();
+                absorb_bytepad(&mut cshake, &[b"KMAC", customization]);
+                absorb_bytepad(&mut cshake, &[key]);
+                Self(cshake)
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            fn finalize_into(mut self, out: &mut [u8]) {
+                let mut buf = [0u8; 17];
+                self.0.update(encode(bit_len(out.len()), true, &mut buf));
+                self.0.finalize_xof().read(out);
+            }
+        }
+
+        #[cfg(feature = "kmac")]
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+kmac!(Kmac128, CShake128, "KMAC128 of NIST SP 800-185 Section 4.");
+kmac!(Kmac256, CShake256, "KMAC256 of NIST SP 800-185 Section 4.");
@@ -0,0 +1,200 @@
+//! Layout of the PRF input data, modeled on the data parameters of PKCS #11 v3.0
+//! `CK_SP800_108_KDF_PARAMS`.
+
+use crate::Error;
+use digest::Mac;
+
+/// Format of a counter (`CK_SP800_108_COUNTER_FORMAT`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CounterFormat {
+    /// Whether the counter is encoded in little-endian instead of big-endian byte order.
+    pub little_endian: bool,
+    /// Width of the counter in bits, one of 8, 16, 24 or 32.
+    pub width: u32,
+}
+
+impl CounterFormat {
+    /// Big-endian counter of `width` bits.
+    #[must_use]
+    pub const fn big_endian(width: u32) -> Self {
+        Self {
+            little_endian: false,
+            width,
+        }
+    }
+}
+
+/// Method used to compute the DKM length (`CK_SP800_108_DKM_LENGTH_METHOD`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DkmLengthMethod {
+    /// Length of the derived key (`CK_SP800_108_DKM_LENGTH_SUM_OF_KEYS`).
+    SumOfKeys,
+    /// Length of all PRF outputs, i.e. the length of the derived key rounded up to a
+    /// multiple of the PRF output length (`CK_SP800_108_DKM_LENGTH_SUM_OF_SEGMENTS`).
+    SumOfSegments,
+}
+
+/// Format of the length `L` of the derived keying material in bits
+/// (`CK_SP800_108_DKM_LENGTH_FORMAT`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DkmLengthFormat {
+    /// Method used to compute the length.
+    pub method: DkmLengthMethod,
+    /// Whether the length is encoded in little-endian instead of big-endian byte order.
+    pub little_endian: bool,
+    /// Width of the length in bits, a multiple of 8 from 8 to 64.
+    pub width: u32,
+}
+
+impl DkmLengthFormat {
+    /// Big-endian length of `width` bits, computed with `method`.
+    #[must_use]
+    pub const fn big_endian(method: DkmLengthMethod, width: u32) -> Self {
+        Self {
+            method,
+            little_endian: false,
+            width,
+        }
+    }
+}
+
+/// Data parameter of the PRF input (`CK_PRF_DATA_PARAM`).
+///
+/// The PRF input of every iteration is the concatenation of the data parameters of the
+/// layout, in order. In Double-Pipeline Mode, `A(0)` is the concatenation of all data
+/// parameters except the iteration variable and the counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataParam<'a> {
+    /// The iteration variable (`CK_SP800_108_ITERATION_VARIABLE`).
+    ///
+    /// This is the counter `i` in Counter Mode, which requires its format, and `K(i-1)`
+    /// in Feedback Mode or `A(i)` in Double-Pipeline Mode, which require `None`.
+    IterationVariable(Option<CounterFormat>),
+    /// The counter `i` in Feedback and Double-Pipeline Mode
+    /// (`CK_SP800_108_OPTIONAL_COUNTER`).
+    Counter(CounterFormat),
+    /// The length of the derived keying material (`CK_SP800_108_DKM_LENGTH`).
+    DkmLength(DkmLengthFormat),
+    /// Raw bytes (`CK_SP800_108_BYTE_ARRAY`), e.g. a label or a context.
+    ByteArray(&'a [u8]),
+}
+
+/// Values of the DKM length in bits for both methods.
+pub(crate) struct DkmLength {
+    pub(crate) sum_of_keys: u64,
+    pub(crate) sum_of_segments: u64,
+}
+
+impl DkmLength {
+    fn get(&self, method: DkmLengthMethod) -> u64 {
+        match method {
+            DkmLengthMethod::SumOfKeys => self.sum_of_keys,
+            DkmLengthMethod::SumOfSegments => self.sum_of_segments,
+        }
+    }
+}
+
+/// Check that `layout` is valid for the mode and that `n` iterations and the DKM length
+/// can be encoded.
+pub(crate) fn validate(
+    layout: &[DataParam<'_>],
+    counter_mode: bool,
+    n: u32,
+    dkm_length: &DkmLength,
+) -> Result<(), Error> {
+    let (mut iteration_variables, mut counters, mut dkm_lengths) = (0, 0, 0);
+    for param in layout {
+        let counter = match param {
+            DataParam::IterationVariable(format) => {
+                iteration_variables += 1;
+                if format.is_some() != counter_mode {
+                    return Err(Error::InvalidLayout);
+                }
+                *format
+            }
+            DataParam::Counter(format) => {
+                counters += 1;
+                Some(*format)
+            }
+            DataParam::DkmLength(format) => {
+                dkm_lengths += 1;
+                if format.width % 8 != 0 || !(8..=64).contains(&format.width) {
+                    return Err(Error::InvalidLayout);
+                }
+                if format.width < 64 && dkm_length.get(format.method) >> format.width != 0 {
+                    return Err(Error::InvalidRequestSize);
+                }
+                None
+            }
+            DataParam::ByteArray(_) => None,
+        };
+        if let Some(format) = counter {
+            if !matches!(format.width, 8 | 16 | 24 | 32) {
+                return Err(Error::InvalidLayout);
+            }
+            if u64::from(n) >= 1 << format.width {
+                return Err(Error::InvalidRequestSize);
+            }
+        }
+    }
+
+    let max_counters = if counter_mode { 0 } else { 1 };
+    if iteration_variables != 1 || counters > max_counters || dkm_lengths > 1 {
+        return Err(Error::InvalidLayout);
+    }
+    Ok(())
+}
+
+/// Update the Prf with the `width` bits of `value`.
+fn input_int<Prf: Mac>(h: &mut Prf, value: u64, width: u32, little_endian: bool) {
+    let len = (width / 8) as usize;
+    if little_endian {
+        h.update(&value.to_le_bytes()[..len]);
+    } else {
+        h.update(&value.to_be_bytes()[8 - len..]);
+    }
+}
+
+/// Update the Prf with the data parameter, if it does not depend on the iteration.
+fn input_fixed<Prf: Mac>(h: &mut Prf, param: &DataParam<'_>, dkm_length: &DkmLength) {
+    match param {
+        DataParam::IterationVariable(_) | DataParam::Counter(_) => {}
+        DataParam::DkmLength(format) => input_int(
+            h,
+            dkm_length.get(format.method),
+            format.width,
+            format.little_endian,
+        ),
+        DataParam::ByteArray(bytes) => h.update(bytes),
+    }
+}
+
+/// Update the Prf with `A(0)` of the Double-Pipeline Mode.
+pub(crate) fn input_iv<Prf: Mac>(h: &mut Prf, layout: &[DataParam<'_>], dkm_length: &DkmLength) {
+    for param in layout {
+        input_fixed(h, param, dkm_length);
+    }
+}
+
+/// Update the Prf with the input of iteration `counter`.
+pub(crate) fn input_block<Prf: Mac>(
+    h: &mut Prf,
+    layout: &[DataParam<'_>],
+    counter: u32,
+    iteration: Option<&[u8]>,
+    dkm_length: &DkmLength,
+) {
+    for param in layout {
+        match param {
+            DataParam::IterationVariable(Some(format)) | DataParam::Counter(format) => {
+                input_int(h, counter.into(), format.width, format.little_endian);
+            }
+            DataParam::IterationVariable(None) => {
+                if let Some(iteration) = iteration {
+                    h.update(iteration);
+                }
+            }
+            _ => input_fixed(h, param, dkm_length),
+        }
+    }
+}
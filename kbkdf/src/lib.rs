@@ -8,20 +8,50 @@
 
 use core::{fmt, marker::PhantomData, ops::Mul};
 use digest::{
-    KeyInit, Mac,
+    InvalidLength, KeyInit, Mac,
     array::{Array, ArraySize, typenum::Unsigned},
     common::KeySizeUser,
     consts::{U8, U32},
     typenum::op,
 };
 
+#[cfg(feature = "zeroize")]
+use digest::zeroize::{Zeroize, Zeroizing};
+
+mod kmac;
+mod layout;
 pub mod sealed;
 
+pub use digest;
+pub use kmac::{Kmac, XofPrf};
+pub use layout::{CounterFormat, DataParam, DkmLengthFormat, DkmLengthMethod};
+
+#[cfg(feature = "kmac")]
+pub use kmac::{Kmac128, Kmac256};
+
+use layout::DkmLength;
+
+#[cfg(feature = "derive")]
+pub use kdf_derive::DeriveKeys;
+
 /// KBKDF error type.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     /// Indicates that the requested length of the derived key is too large for the value of R specified.
     InvalidRequestSize,
+    /// Indicates that the label or the context is too long to be framed with a `u32` length.
+    InvalidFramingSize,
+    /// Indicates that the data parameters of a layout are not valid for the KBKDF mode.
+    InvalidLayout,
+    /// Indicates that L does not fit into the width of its encoding or that the width is
+    /// not supported.
+    InvalidLEncoding,
+    /// Indicates that the PRF does not support keys of the length of the key derivation key.
+    InvalidKeyLength,
+    /// Indicates that the parameters of a derivation with a [`KbkdfKey`] contain a key
+    /// derivation key.
+    UnexpectedKin,
 }
 
 impl fmt::Display for Error {
@@ -31,12 +61,113 @@ impl fmt::Display for Error {
                 f,
                 "Request output size is too large for the value of R specified"
             ),
+            Error::InvalidFramingSize => {
+                write!(f, "Label or context is too long for the framed encoding")
+            }
+            Error::InvalidLayout => {
+                write!(f, "Data parameters are not valid for the KBKDF mode")
+            }
+            Error::InvalidLEncoding => {
+                write!(
+                    f,
+                    "Length of the derived key does not fit into its encoding"
+                )
+            }
+            Error::InvalidKeyLength => {
+                write!(f, "Key derivation key has an invalid length for the PRF")
+            }
+            Error::UnexpectedKin => {
+                write!(
+                    f,
+                    "Key derivation key is given both as a key and in the parameters"
+                )
+            }
         }
     }
 }
 
 impl core::error::Error for Error {}
 
+/// Unit of the length L of the derived key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LUnit {
+    /// Length in bits, as specified by NIST SP 800-108r1.
+    #[default]
+    Bits,
+    /// Length in bytes.
+    Bytes,
+}
+
+/// Position of the length L of the derived key in the fixed input data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LPosition {
+    /// Before the label.
+    BeforeLabel,
+    /// Before the context, i.e. after the separator or the framed label.
+    BeforeContext,
+    /// After the context, as specified by NIST SP 800-108r1.
+    #[default]
+    AfterContext,
+}
+
+/// Encoding of the length L of the derived key.
+///
+/// The default encoding is a 4-byte big-endian bit count after the context. For example,
+/// SCP03 uses a 2-byte bit count before the context:
+///
+/// ```
+/// use kbkdf::{LEncoding, LPosition};
+///
+/// let l_encoding = LEncoding {
+///     width: 2,
+///     position: LPosition::BeforeContext,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LEncoding {
+    /// Width of the encoded length in bytes, from 1 to 8.
+    pub width: usize,
+    /// Unit of the length.
+    pub unit: LUnit,
+    /// Whether the length is encoded in little-endian instead of big-endian byte order.
+    pub little_endian: bool,
+    /// Position of the length in the fixed input data.
+    pub position: LPosition,
+}
+
+impl Default for LEncoding {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            unit: LUnit::Bits,
+            little_endian: false,
+            position: LPosition::AfterContext,
+        }
+    }
+}
+
+impl LEncoding {
+    /// Encode the length of a derived key of `len` bytes into `buf`.
+    fn encode(self, len: usize, buf: &mut [u8; 8]) -> Result<&[u8], Error> {
+        let len = u64::try_from(len).map_err(|_| Error::InvalidLEncoding)?;
+        let l = match self.unit {
+            LUnit::Bits => len.checked_mul(8).ok_or(Error::InvalidLEncoding)?,
+            LUnit::Bytes => len,
+        };
+        if !(1..=8).contains(&self.width) || (self.width < 8 && l >> (8 * self.width) != 0) {
+            return Err(Error::InvalidLEncoding);
+        }
+        if self.little_endian {
+            *buf = l.to_le_bytes();
+            Ok(&buf[..self.width])
+        } else {
+            *buf = l.to_be_bytes();
+            Ok(&buf[8 - self.width..])
+        }
+    }
+}
+
 /// Parameters used for KBKDF.
 ///
 /// For more details, read the official specification: [NIST SP 800-108r1](https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-108r1.pdf).
@@ -56,11 +187,19 @@ pub struct Params<'k, 'l, 'c> {
     /// optionally, a nonce known by the parties who derive the keys.
     pub context: &'c [u8],
     /// A flag indicating whether to update the Prf with the requested key length.
+    ///
+    /// The length is encoded according to [`l_encoding`][Self::l_encoding].
     pub use_l: bool,
     /// A flag indicating whether to separate the label from the context with a NULL byte.
+    ///
+    /// Ignored by framed parameters, see [`Params::framed_builder`].
     pub use_separator: bool,
     /// A flag indicating whether to update the Prf with the iteration counter.
     pub use_counter: bool,
+    /// Encoding of L, used if [`use_l`][Self::use_l] is set.
+    pub l_encoding: LEncoding,
+    /// Whether the label and the context are framed, only set by [`FramedParamsBuilder`].
+    framed: bool,
 }
 
 impl<'k, 'l, 'c> Params<'k, 'l, 'c> {
@@ -74,9 +213,29 @@ impl<'k, 'l, 'c> Params<'k, 'l, 'c> {
             use_l: true,
             use_separator: true,
             use_counter: true,
+            l_encoding: LEncoding::default(),
+            framed: false,
         };
         ParamsBuilder(params)
     }
+
+    /// Create a new builder for [`Params`] which prefix the label and the context with
+    /// their lengths instead of separating them with a NULL byte.
+    ///
+    /// The fixed input data is encoded as
+    /// `u32be(len(label)) || label || u32be(len(context)) || context`, with `L` at its
+    /// position if [`use_l`][Self::use_l] is set.
+    /// Unlike the separator, this encoding is injective: two different `(label, context)`
+    /// pairs never produce the same PRF input, even if the label contains NULL bytes.
+    #[must_use]
+    pub fn framed_builder(kin: &'k [u8]) -> FramedParamsBuilder<'k, 'l, 'c> {
+        let params = Params {
+            use_separator: false,
+            framed: true,
+            ..Params::builder(kin).build()
+        };
+        FramedParamsBuilder(params)
+    }
 }
 
 /// Parameters builders for [`Params`].
@@ -124,6 +283,62 @@ impl<'k, 'l, 'c> ParamsBuilder<'k, 'l, 'c> {
         self.0.use_counter = use_counter;
         self
     }
+
+    /// Set the width, unit, byte order and position of the length of the Prf
+    #[must_use]
+    pub fn with_l_encoding(mut self, l_encoding: LEncoding) -> Self {
+        self.0.l_encoding = l_encoding;
+        self
+    }
+}
+
+/// Builder for framed [`Params`], see [`Params::framed_builder`].
+///
+/// Unlike [`ParamsBuilder`], there is no separator to choose.
+#[derive(Clone, Debug)]
+pub struct FramedParamsBuilder<'k, 'l, 'c>(Params<'k, 'l, 'c>);
+
+impl<'k, 'l, 'c> FramedParamsBuilder<'k, 'l, 'c> {
+    /// Return the built [`Params`]
+    #[must_use]
+    pub fn build(self) -> Params<'k, 'l, 'c> {
+        self.0
+    }
+
+    /// Set the label for the parameters
+    #[must_use]
+    pub fn with_label(mut self, label: &'l [u8]) -> Self {
+        self.0.label = label;
+        self
+    }
+
+    /// Set the context for the parameters
+    #[must_use]
+    pub fn with_context(mut self, context: &'c [u8]) -> Self {
+        self.0.context = context;
+        self
+    }
+
+    /// During the iterations, append the length of the Prf
+    #[must_use]
+    pub fn use_l(mut self, use_l: bool) -> Self {
+        self.0.use_l = use_l;
+        self
+    }
+
+    /// During the iterations, update the Prf with the iteration counter
+    #[must_use]
+    pub fn use_counter(mut self, use_counter: bool) -> Self {
+        self.0.use_counter = use_counter;
+        self
+    }
+
+    /// Set the width, unit, byte order and position of the length of the Prf
+    #[must_use]
+    pub fn with_l_encoding(mut self, l_encoding: LEncoding) -> Self {
+        self.0.l_encoding = l_encoding;
+        self
+    }
 }
 
 // Helper structure along with [`KbkdfUser`] to compute values of L and H.
@@ -153,6 +368,129 @@ where
     type H = op!(PrfOutputLen * U8);
 }
 
+/// Update the Prf with the label, the context and, if given, the encoded L of the fixed
+/// input data.
+fn input_fixed_data<Prf: Mac>(h: &mut Prf, params: &Params<'_, '_, '_>, l: Option<&[u8]>) {
+    let input_l = |h: &mut Prf, position| {
+        if let Some(l) = l.filter(|_| params.l_encoding.position == position) {
+            h.update(l);
+        }
+    };
+
+    input_l(h, LPosition::BeforeLabel);
+    if params.framed {
+        let input_framed = |h: &mut Prf, component: &[u8]| {
+            let len = u32::try_from(component.len()).expect("length is checked");
+            h.update(&len.to_be_bytes());
+            h.update(component);
+        };
+        input_framed(h, params.label);
+        input_l(h, LPosition::BeforeContext);
+        input_framed(h, params.context);
+    } else {
+        h.update(params.label);
+        if params.use_separator {
+            h.update(&[0]);
+        }
+        input_l(h, LPosition::BeforeContext);
+        h.update(params.context);
+    }
+    input_l(h, LPosition::AfterContext);
+}
+
+/// Fill `output` with the outputs of the PRF iterations of a KBKDF mode.
+///
+/// `prf` is keyed with the key derivation key and cloned for every invocation.
+/// `input_iv` updates the Prf with `A(0)` of the Double-Pipeline Mode and `input_block`
+/// with the input of iteration `i`, given the iteration variable `K(i-1)` of the Feedback
+/// Mode or `A(i)` of the Double-Pipeline Mode.
+fn derive_blocks<Prf: Mac + Clone>(
+    prf: &Prf,
+    mut ki: Option<Array<u8, Prf::OutputSize>>,
+    feedback: bool,
+    double_pipeline: bool,
+    output: &mut [u8],
+    input_iv: impl Fn(&mut Prf),
+    input_block: impl Fn(&mut Prf, u32, Option<&[u8]>),
+) {
+    let mut a: Option<Array<u8, Prf::OutputSize>> = None;
+
+    for (counter, block) in (1..).zip(output.chunks_mut(Prf::OutputSize::USIZE)) {
+        if double_pipeline {
+            let mut h = prf.clone();
+            match &a {
+                Some(a) => h.update(a),
+                None => input_iv(&mut h),
+            }
+            a = Some(h.finalize().into_bytes());
+        }
+
+        let mut h = prf.clone();
+        let iteration = if double_pipeline {
+            a.as_ref()
+        } else if feedback {
+            ki.as_ref()
+        } else {
+            None
+        };
+        input_block(&mut h, counter, iteration.map(Array::as_slice));
+        let buf = h.finalize().into_bytes();
+        block.copy_from_slice(&buf[..block.len()]);
+        ki = Some(buf);
+    }
+
+    #[cfg(feature = "zeroize")]
+    {
+        a.zeroize();
+        ki.zeroize();
+    }
+}
+
+/// Key derivation key of a KBKDF, holding the keyed PRF.
+///
+/// Keying the PRF can be expensive, e.g. the key expansion and subkey generation of
+/// CMAC-AES. A [`KbkdfKey`] keys the PRF once, to derive many keys from the same key
+/// derivation key, given with parameters without key derivation key:
+///
+/// ```
+/// use hmac::Hmac;
+/// use kbkdf::{Counter, Kbkdf, KbkdfKey, Params};
+/// use sha2::Sha256;
+///
+/// type HmacSha256 = Hmac<Sha256>;
+/// let counter = Counter::<HmacSha256, HmacSha256>::default();
+/// let key = KbkdfKey::<HmacSha256>::new(b"secret").unwrap();
+/// for context in [b"first", b"other"] {
+///     let derived = counter
+///         .derive_with_key(&key, Params::builder(&[]).with_context(context).build())
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct KbkdfKey<Prf> {
+    prf: Prf,
+}
+
+impl<Prf: KeyInit> KbkdfKey<Prf> {
+    /// Keys the PRF with the key derivation key `kin`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidLength`] if the PRF does not support keys of the length of `kin`.
+    pub fn new(kin: &[u8]) -> Result<Self, InvalidLength> {
+        Prf::new_from_slice(kin).map(|prf| Self { prf })
+    }
+
+    fn from_kin(kin: &[u8]) -> Result<Self, Error> {
+        Self::new(kin).map_err(|_| Error::InvalidKeyLength)
+    }
+}
+
+impl<Prf> fmt::Debug for KbkdfKey<Prf> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KbkdfKey").finish_non_exhaustive()
+    }
+}
+
 /// [`Kbkdf`] is a trait representing a mode of KBKDF.
 /// It takes multiple arguments:
 ///  - Prf - the Pseudorandom Function to derive keys from
@@ -161,7 +499,7 @@ where
 ///    as an integer in the interval [1, 2r − 1].
 pub trait Kbkdf<Prf, K, R: sealed::R>
 where
-    Prf: Mac + KeyInit,
+    Prf: Mac + KeyInit + Clone,
     K: KeySizeUser,
     K::KeySize: ArraySize + Mul<U8>,
     <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -171,85 +509,209 @@ where
     /// Derives `key` from `kin` and other parameters.
     ///
     /// # Errors
-    /// Returns [`Error::InvalidRequestSize`] if too many PRF iterations would be needed.
+    /// Returns [`Error::InvalidKeyLength`] if the PRF does not support keys of the length of
+    /// `kin`, [`Error::InvalidRequestSize`] if too many PRF iterations would be needed and
+    /// [`Error::InvalidLEncoding`] if L does not fit into its encoding.
     fn derive(&self, params: Params<'_, '_, '_>) -> Result<Array<u8, K::KeySize>, Error> {
-        // n - An integer whose value is the number of iterations of the PRF needed to generate L
-        // bits of keying material
-        let n: u32 = <KbkdfCore<K::KeySize, Prf::OutputSize> as KbkdfUser>::L::U32
-            .div_ceil(<KbkdfCore<K::KeySize, Prf::OutputSize> as KbkdfUser>::H::U32);
+        let key = KbkdfKey::from_kin(params.kin)?;
+        self.derive_with_key(&key, Params { kin: &[], ..params })
+    }
 
-        if n as usize > 2usize.pow(R::U32) - 1 {
-            return Err(Error::InvalidRequestSize);
-        }
+    /// Derives `key` from `kin` and other parameters, wrapped in [`Zeroizing`].
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidKeyLength`] if the PRF does not support keys of the length of
+    /// `kin`, [`Error::InvalidRequestSize`] if too many PRF iterations would be needed and
+    /// [`Error::InvalidLEncoding`] if L does not fit into its encoding.
+    #[cfg(feature = "zeroize")]
+    fn derive_zeroizing(
+        &self,
+        params: Params<'_, '_, '_>,
+    ) -> Result<Zeroizing<Array<u8, K::KeySize>>, Error> {
+        let mut output = Zeroizing::new(Array::<u8, K::KeySize>::default());
+        self.derive_into(params, &mut output)?;
+        Ok(output)
+    }
 
+    /// Derives `key` from the key derivation key `key` and other parameters.
+    ///
+    /// The key derivation key is given by `key` only: `params` must be built with an empty
+    /// `kin`, e.g. by `Params::builder(&[])`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnexpectedKin`] if `params.kin` is not empty,
+    /// [`Error::InvalidRequestSize`] if too many PRF iterations would be needed and
+    /// [`Error::InvalidLEncoding`] if L does not fit into its encoding.
+    fn derive_with_key(
+        &self,
+        key: &KbkdfKey<Prf>,
+        params: Params<'_, '_, '_>,
+    ) -> Result<Array<u8, K::KeySize>, Error> {
         let mut output = Array::<u8, K::KeySize>::default();
-        let mut builder = output.as_mut_slice();
+        self.derive_into_with_key(key, params, &mut output)?;
+        Ok(output)
+    }
 
-        let mut ki = None;
-        self.input_iv(&mut ki);
-        let mut a = {
-            let mut h = Prf::new_from_slice(params.kin).expect("should be the right size");
-            h.update(params.label);
-            if params.use_separator {
-                h.update(&[0]);
-            }
-            h.update(params.context);
-            h.finalize().into_bytes()
-        };
+    /// Derives `out.len()` bytes from `kin` and other parameters into `out`.
+    ///
+    /// Unlike [`derive`][Self::derive], the length `L` of the derived key is computed from
+    /// the length of `out` at runtime, `K` is not used:
+    ///
+    /// ```
+    /// use hmac::Hmac;
+    /// use kbkdf::{Counter, Kbkdf, Params};
+    /// use sha2::Sha256;
+    ///
+    /// type HmacSha256 = Hmac<Sha256>;
+    /// let counter = Counter::<HmacSha256, HmacSha256>::default();
+    /// let key_len = 42;
+    /// let mut key = [0u8; 64];
+    /// counter
+    ///     .derive_into(Params::builder(b"secret").build(), &mut key[..key_len])
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidKeyLength`] if the PRF does not support keys of the length of
+    /// `kin`, [`Error::InvalidRequestSize`] if too many PRF iterations would be needed and
+    /// [`Error::InvalidLEncoding`] if L does not fit into its encoding.
+    fn derive_into(&self, params: Params<'_, '_, '_>, out: &mut [u8]) -> Result<(), Error> {
+        let key = KbkdfKey::from_kin(params.kin)?;
+        self.derive_into_with_key(&key, Params { kin: &[], ..params }, out)
+    }
 
-        for counter in 1..=n {
-            if counter > 1 {
-                a = {
-                    let mut h = Prf::new_from_slice(params.kin).expect("should be the right size");
-                    h.update(a.as_slice());
-                    h.finalize().into_bytes()
-                };
-            }
+    /// Derives `out.len()` bytes from the key derivation key `key` and other parameters into
+    /// `out`.
+    ///
+    /// The key derivation key is given by `key` only: `params` must be built with an empty
+    /// `kin`, e.g. by `Params::builder(&[])`.
+    ///
+    /// # Errors
+    /// Returns [`Error::UnexpectedKin`] if `params.kin` is not empty,
+    /// [`Error::InvalidRequestSize`] if too many PRF iterations would be needed and
+    /// [`Error::InvalidLEncoding`] if L does not fit into its encoding.
+    fn derive_into_with_key(
+        &self,
+        key: &KbkdfKey<Prf>,
+        params: Params<'_, '_, '_>,
+        out: &mut [u8],
+    ) -> Result<(), Error> {
+        if !params.kin.is_empty() {
+            return Err(Error::UnexpectedKin);
+        }
 
-            let mut h = Prf::new_from_slice(params.kin).expect("should be the right size");
+        // n - An integer whose value is the number of iterations of the PRF needed to generate L
+        // bits of keying material. L itself is only bounded by its encoding.
+        let n = out.len().div_ceil(Prf::OutputSize::USIZE);
 
-            if Self::FEEDBACK_KI {
-                if let Some(ki) = ki {
-                    h.update(ki.as_slice());
-                }
-            }
+        if u64::try_from(n).map_err(|_| Error::InvalidRequestSize)? > (1u64 << R::U32) - 1 {
+            return Err(Error::InvalidRequestSize);
+        }
 
-            if Self::DOUBLE_PIPELINE {
-                h.update(a.as_slice());
-            }
-            if params.use_counter {
-                // counter encoded as big endian u32
-                // Type parameter R encodes how large the value is to be (either U8, U16, U24, or U32)
-                //
-                // counter = 1u32 ([0, 0, 0, 1])
-                //                     \-------/
-                //                      R = u24
-                h.update(&counter.to_be_bytes()[(4 - R::USIZE / 8)..]);
-            }
+        if params.framed
+            && (u32::try_from(params.label.len()).is_err()
+                || u32::try_from(params.context.len()).is_err())
+        {
+            return Err(Error::InvalidFramingSize);
+        }
 
-            // Fixed input data
-            h.update(params.label);
-            if params.use_separator {
-                h.update(&[0]);
-            }
-            h.update(params.context);
-            if params.use_l {
-                h.update(
-                    &(<KbkdfCore<K::KeySize, Prf::OutputSize> as KbkdfUser>::L::U32).to_be_bytes()
-                        [..],
-                );
-            }
+        let mut l_buf = [0u8; 8];
+        let l_encoded = if params.use_l {
+            Some(params.l_encoding.encode(out.len(), &mut l_buf)?)
+        } else {
+            None
+        };
 
-            let buf = h.finalize().into_bytes();
-            ki = Some(buf.clone());
+        let mut ki = None;
+        self.input_iv(&mut ki);
+        derive_blocks(
+            &key.prf,
+            ki,
+            Self::FEEDBACK_KI,
+            Self::DOUBLE_PIPELINE,
+            out,
+            |h| input_fixed_data(h, &params, None),
+            |h, counter, iteration| {
+                if let Some(iteration) = iteration {
+                    h.update(iteration);
+                }
+                if params.use_counter {
+                    // counter encoded as big endian u32
+                    // Type parameter R encodes how large the value is to be (either U8, U16, U24, or U32)
+                    //
+                    // counter = 1u32 ([0, 0, 0, 1])
+                    //                     \-------/
+                    //                      R = u24
+                    h.update(&counter.to_be_bytes()[(4 - R::USIZE / 8)..]);
+                }
 
-            let remaining = usize::min(buf.len(), builder.len());
+                // Fixed input data
+                input_fixed_data(h, &params, l_encoded);
+            },
+        );
 
-            builder[..remaining].copy_from_slice(&buf[..remaining]);
-            builder = &mut builder[remaining..];
-        }
+        Ok(())
+    }
 
-        assert_eq!(builder.len(), 0, "output has uninitialized bytes");
+    /// Derives `key` from `kin` with the PRF input of every iteration laid out by the data
+    /// parameters of `layout`, e.g. with the counter after the fixed input data:
+    ///
+    /// ```
+    /// use hmac::Hmac;
+    /// use kbkdf::{Counter, CounterFormat, DataParam, Kbkdf};
+    /// use sha2::Sha256;
+    ///
+    /// type HmacSha256 = Hmac<Sha256>;
+    /// let counter = Counter::<HmacSha256, HmacSha256>::default();
+    /// let key = counter
+    ///     .derive_with_layout(
+    ///         b"secret",
+    ///         &[
+    ///             DataParam::ByteArray(b"fixed input data"),
+    ///             DataParam::IterationVariable(Some(CounterFormat::big_endian(32))),
+    ///         ],
+    ///     )
+    ///     .unwrap();
+    /// ```
+    ///
+    /// The layout must contain exactly one [`DataParam::IterationVariable`], at most one
+    /// [`DataParam::DkmLength`] and, in Feedback and Double-Pipeline Mode, at most one
+    /// [`DataParam::Counter`]. The widths of the counters are given by the layout, `R` is
+    /// not used.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidLayout`] if `layout` is not valid for the mode,
+    /// [`Error::InvalidRequestSize`] if the number of PRF iterations or the DKM length
+    /// does not fit into its width and [`Error::InvalidKeyLength`] if the PRF does not
+    /// support keys of the length of `kin`.
+    fn derive_with_layout(
+        &self,
+        kin: &[u8],
+        layout: &[DataParam<'_>],
+    ) -> Result<Array<u8, K::KeySize>, Error> {
+        let l = <KbkdfCore<K::KeySize, Prf::OutputSize> as KbkdfUser>::L::U32;
+        let h = <KbkdfCore<K::KeySize, Prf::OutputSize> as KbkdfUser>::H::U32;
+        let n = l.div_ceil(h);
+        let dkm_length = DkmLength {
+            sum_of_keys: l.into(),
+            sum_of_segments: u64::from(n) * u64::from(h),
+        };
+        let counter_mode = !Self::FEEDBACK_KI && !Self::DOUBLE_PIPELINE;
+        layout::validate(layout, counter_mode, n, &dkm_length)?;
+        let key = KbkdfKey::<Prf>::from_kin(kin)?;
+
+        let mut output = Array::<u8, K::KeySize>::default();
+        let mut ki = None;
+        self.input_iv(&mut ki);
+        derive_blocks(
+            &key.prf,
+            ki,
+            Self::FEEDBACK_KI,
+            Self::DOUBLE_PIPELINE,
+            &mut output,
+            |h| layout::input_iv(h, layout, &dkm_length),
+            |h, counter, iteration| layout::input_block(h, layout, counter, iteration, &dkm_length),
+        );
 
         Ok(output)
     }
@@ -282,7 +744,7 @@ impl<Prf, K, R> Default for Counter<Prf, K, R> {
 
 impl<Prf, K, R> Kbkdf<Prf, K, R> for Counter<Prf, K, R>
 where
-    Prf: Mac + KeyInit,
+    Prf: Mac + KeyInit + Clone,
     K: KeySizeUser,
     K::KeySize: ArraySize + Mul<U8>,
     <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -318,7 +780,7 @@ where
 
 impl<Prf, K, R> Kbkdf<Prf, K, R> for Feedback<'_, Prf, K, R>
 where
-    Prf: Mac + KeyInit,
+    Prf: Mac + KeyInit + Clone,
     K: KeySizeUser,
     K::KeySize: ArraySize + Mul<U8>,
     <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -357,7 +819,7 @@ where
 
 impl<Prf, K, R> Kbkdf<Prf, K, R> for DoublePipeline<Prf, K, R>
 where
-    Prf: Mac + KeyInit,
+    Prf: Mac + KeyInit + Clone,
     K: KeySizeUser,
     K::KeySize: ArraySize + Mul<U8>,
     <K::KeySize as Mul<U8>>::Output: Unsigned,
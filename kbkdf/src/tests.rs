@@ -1,4 +1,9 @@
-use super::{Array, Counter, DoublePipeline, Feedback, Kbkdf, Params};
+extern crate std;
+
+use super::{
+    Array, Counter, CounterFormat, DataParam, DkmLengthFormat, DkmLengthMethod, DoublePipeline,
+    Error, Feedback, Kbkdf, KbkdfKey, LEncoding, LPosition, LUnit, Params,
+};
 use core::convert::TryFrom;
 use digest::{common::KeySizeUser, consts::*};
 use hex_literal::hex;
@@ -229,3 +234,458 @@ fn test_static_values_double_pipeline() {
         );
     }
 }
+
+#[test]
+fn test_framing() {
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    let counter = Counter::<HmacSha256, HmacSha256>::default();
+    let params = |label, context, framed| {
+        if framed {
+            Params::framed_builder(b"key derivation key")
+                .with_label(label)
+                .with_context(context)
+                .build()
+        } else {
+            Params::builder(b"key derivation key")
+                .with_label(label)
+                .with_context(context)
+                .build()
+        }
+    };
+
+    // The framed fixed input data is equal to an unframed label without separator
+    let framed = counter.derive(params(b"label", b"context", true)).unwrap();
+    let unframed = counter.derive(
+        Params::builder(b"key derivation key")
+            .with_label(b"\x00\x00\x00\x05label\x00\x00\x00\x07context")
+            .use_separator(false)
+            .build(),
+    );
+    assert_eq!(Ok(framed), unframed);
+
+    // The separator is chosen by the builder, setting it has no effect on framed parameters
+    let mut separated = params(b"label", b"context", true);
+    separated.use_separator = true;
+    assert_eq!(counter.derive(separated), Ok(framed));
+
+    // A NULL byte in the label makes the separator ambiguous, but not the framing
+    let a = counter.derive(params(b"a\x00", b"b", false)).unwrap();
+    let b = counter.derive(params(b"a", b"\x00b", false)).unwrap();
+    assert_eq!(a, b);
+    let a = counter.derive(params(b"a\x00", b"b", true)).unwrap();
+    let b = counter.derive(params(b"a", b"\x00b", true)).unwrap();
+    assert_ne!(a, b);
+
+    let feedback = Feedback::<HmacSha256, HmacSha256>::new(None);
+    let framed = feedback.derive(params(b"label", b"context", true)).unwrap();
+    let unframed = feedback
+        .derive(params(b"label", b"context", false))
+        .unwrap();
+    assert_ne!(framed, unframed);
+}
+
+#[test]
+fn test_layout() {
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    struct MockOutput;
+
+    impl KeySizeUser for MockOutput {
+        type KeySize = U20;
+    }
+
+    let kin = b"key derivation key";
+    let params = |use_l| {
+        Params::builder(kin)
+            .with_label(b"label")
+            .with_context(b"context")
+            .use_l(use_l)
+            .build()
+    };
+    let counter = CounterFormat::big_endian(32);
+    let l = DataParam::DkmLength(DkmLengthFormat::big_endian(DkmLengthMethod::SumOfKeys, 32));
+    let fixed_data = [
+        DataParam::ByteArray(b"label"),
+        DataParam::ByteArray(&[0]),
+        DataParam::ByteArray(b"context"),
+    ];
+
+    // The default parameters are equal to the SP 800-108 layouts
+    let kbkdf = Counter::<HmacSha256, HmacSha256>::default();
+    let layout = [
+        &[DataParam::IterationVariable(Some(counter))],
+        &fixed_data[..],
+        &[l],
+    ]
+    .concat();
+    assert_eq!(
+        kbkdf.derive_with_layout(kin, &layout),
+        kbkdf.derive(params(true))
+    );
+
+    let kbkdf = Feedback::<HmacSha256, HmacSha256>::new(None);
+    let iteration = [
+        DataParam::IterationVariable(None),
+        DataParam::Counter(counter),
+    ];
+    let layout = [&iteration[..], &fixed_data[..], &[l]].concat();
+    assert_eq!(
+        kbkdf.derive_with_layout(kin, &layout),
+        kbkdf.derive(params(true))
+    );
+
+    let kbkdf = DoublePipeline::<HmacSha256, HmacSha256>::default();
+    let layout = [&iteration[..], &fixed_data[..]].concat();
+    assert_eq!(
+        kbkdf.derive_with_layout(kin, &layout),
+        kbkdf.derive(params(false))
+    );
+
+    // DKM length methods and encodings
+    let kbkdf = Counter::<HmacSha256, MockOutput>::default();
+    let derive = |dkm_length: &[DataParam<'_>]| {
+        let layout = [&[DataParam::IterationVariable(Some(counter))], dkm_length].concat();
+        kbkdf.derive_with_layout(kin, &layout)
+    };
+    let dkm_length = |method, little_endian, width| {
+        DataParam::DkmLength(DkmLengthFormat {
+            method,
+            little_endian,
+            width,
+        })
+    };
+    assert_eq!(
+        derive(&[dkm_length(DkmLengthMethod::SumOfKeys, false, 16)]),
+        derive(&[DataParam::ByteArray(&[0x00, 0xa0])])
+    );
+    assert_eq!(
+        derive(&[dkm_length(DkmLengthMethod::SumOfSegments, true, 24)]),
+        derive(&[DataParam::ByteArray(&[0x00, 0x01, 0x00])])
+    );
+    assert_eq!(
+        derive(&[dkm_length(DkmLengthMethod::SumOfKeys, false, 8)]),
+        derive(&[DataParam::ByteArray(&[0xa0])])
+    );
+    assert_eq!(
+        derive(&[dkm_length(DkmLengthMethod::SumOfSegments, false, 8)]),
+        Err(Error::InvalidRequestSize)
+    );
+    assert_eq!(
+        derive(&[dkm_length(DkmLengthMethod::SumOfKeys, false, 12)]),
+        Err(Error::InvalidLayout)
+    );
+    assert_eq!(derive(&[l, l]), Err(Error::InvalidLayout));
+
+    let little_endian = CounterFormat {
+        little_endian: true,
+        width: 16,
+    };
+    assert_eq!(
+        kbkdf.derive_with_layout(kin, &[DataParam::IterationVariable(Some(little_endian))]),
+        kbkdf.derive_with_layout(
+            kin,
+            &[
+                DataParam::IterationVariable(Some(CounterFormat::big_endian(8))),
+                DataParam::ByteArray(&[0]),
+            ]
+        )
+    );
+
+    // Invalid layouts
+    let invalid_counter_layouts: &[&[DataParam<'_>]] = &[
+        &[],
+        &[DataParam::IterationVariable(None)],
+        &[
+            DataParam::IterationVariable(Some(counter)),
+            DataParam::IterationVariable(Some(counter)),
+        ],
+        &[
+            DataParam::IterationVariable(Some(counter)),
+            DataParam::Counter(counter),
+        ],
+        &[DataParam::IterationVariable(Some(
+            CounterFormat::big_endian(12),
+        ))],
+    ];
+    for layout in invalid_counter_layouts {
+        assert_eq!(
+            kbkdf.derive_with_layout(kin, layout),
+            Err(Error::InvalidLayout)
+        );
+    }
+    let kbkdf = Feedback::<HmacSha256, MockOutput>::new(None);
+    let invalid_feedback_layouts: &[&[DataParam<'_>]] = &[
+        &[DataParam::Counter(counter)],
+        &[DataParam::IterationVariable(Some(counter))],
+        &[
+            DataParam::IterationVariable(None),
+            DataParam::Counter(counter),
+            DataParam::Counter(counter),
+        ],
+    ];
+    for layout in invalid_feedback_layouts {
+        assert_eq!(
+            kbkdf.derive_with_layout(kin, layout),
+            Err(Error::InvalidLayout)
+        );
+    }
+}
+
+#[test]
+fn test_derive_into() {
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    let params = Params::builder(b"key derivation key")
+        .with_label(b"label")
+        .with_context(b"context")
+        .build();
+
+    // The derived key depends on its length through L
+    let counter = Counter::<HmacSha256, HmacSha256>::default();
+    let key = counter.derive(params).unwrap();
+    let mut out = [0u8; 64];
+    counter.derive_into(params, &mut out).unwrap();
+    assert_eq!(key[..], out);
+    let mut shorter = [0u8; 63];
+    counter.derive_into(params, &mut shorter).unwrap();
+    assert_ne!(shorter, out[..63]);
+
+    let feedback = Feedback::<HmacSha256, HmacSha256>::new(None);
+    feedback.derive_into(params, &mut out).unwrap();
+    assert_eq!(feedback.derive(params).unwrap()[..], out);
+
+    let double_pipeline = DoublePipeline::<HmacSha256, HmacSha256>::default();
+    double_pipeline.derive_into(params, &mut out).unwrap();
+    assert_eq!(double_pipeline.derive(params).unwrap()[..], out);
+
+    // The iteration count is checked against R
+    let counter = Counter::<HmacSha256, HmacSha256, U8>::default();
+    let mut out = [0u8; 255 * 32 + 1];
+    assert_eq!(
+        counter.derive_into(params, &mut out),
+        Err(Error::InvalidRequestSize)
+    );
+    assert_eq!(counter.derive_into(params, &mut out[..255 * 32]), Ok(()));
+    assert_eq!(counter.derive_into(params, &mut []), Ok(()));
+}
+
+#[test]
+fn test_l_encoding() {
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    let counter = Counter::<HmacSha256, HmacSha256>::default();
+    let derive = |label: &[u8], context: &[u8], use_l, l_encoding| {
+        let mut out = [0u8; 64];
+        counter
+            .derive_into(
+                Params::builder(b"key derivation key")
+                    .with_label(label)
+                    .with_context(context)
+                    .use_l(use_l)
+                    .with_l_encoding(l_encoding)
+                    .build(),
+                &mut out,
+            )
+            .map(|()| out)
+    };
+    let encoding = |width, unit, little_endian, position| LEncoding {
+        width,
+        unit,
+        little_endian,
+        position,
+    };
+    let default = LEncoding::default();
+
+    assert_eq!(
+        derive(b"label", b"context", true, default),
+        derive(b"label", b"context\x00\x00\x02\x00", false, default)
+    );
+    assert_eq!(
+        derive(
+            b"label",
+            b"context",
+            true,
+            encoding(2, LUnit::Bits, false, LPosition::AfterContext)
+        ),
+        derive(b"label", b"context\x02\x00", false, default)
+    );
+    assert_eq!(
+        derive(
+            b"label",
+            b"context",
+            true,
+            encoding(8, LUnit::Bytes, true, LPosition::AfterContext)
+        ),
+        derive(
+            b"label",
+            b"context\x40\x00\x00\x00\x00\x00\x00\x00",
+            false,
+            default
+        )
+    );
+    assert_eq!(
+        derive(
+            b"label",
+            b"context",
+            true,
+            encoding(1, LUnit::Bytes, false, LPosition::BeforeLabel)
+        ),
+        derive(b"\x40label", b"context", false, default)
+    );
+    assert_eq!(
+        derive(
+            b"label",
+            b"context",
+            true,
+            encoding(2, LUnit::Bits, false, LPosition::BeforeContext)
+        ),
+        derive(b"label", b"\x02\x00context", false, default)
+    );
+
+    // L is placed between the framed label and the framed context
+    let mut out = [0u8; 64];
+    counter
+        .derive_into(
+            Params::framed_builder(b"key derivation key")
+                .with_label(b"label")
+                .with_context(b"context")
+                .with_l_encoding(encoding(2, LUnit::Bits, false, LPosition::BeforeContext))
+                .build(),
+            &mut out,
+        )
+        .unwrap();
+    assert_eq!(
+        Ok(out),
+        derive(
+            b"\x00\x00\x00\x05label\x02\x00\x00\x00",
+            b"\x07context",
+            false,
+            default
+        )
+    );
+
+    // L must fit into the width
+    for l_encoding in [
+        encoding(1, LUnit::Bits, false, LPosition::AfterContext),
+        encoding(0, LUnit::Bytes, false, LPosition::AfterContext),
+        encoding(9, LUnit::Bytes, false, LPosition::AfterContext),
+    ] {
+        assert_eq!(
+            derive(b"label", b"context", true, l_encoding),
+            Err(Error::InvalidLEncoding)
+        );
+        assert!(derive(b"label", b"context", false, l_encoding).is_ok());
+    }
+    assert!(
+        derive(
+            b"label",
+            b"context",
+            true,
+            encoding(1, LUnit::Bytes, false, LPosition::AfterContext)
+        )
+        .is_ok()
+    );
+    // Only the encoding of L limits its value: a bit count beyond `u32::MAX` is rejected by
+    // the default 4-byte encoding, not by the length of the output
+    let mut out = std::vec![0u8; (1 << 29) + 1];
+    assert_eq!(
+        counter.derive_into(Params::builder(b"key derivation key").build(), &mut out),
+        Err(Error::InvalidLEncoding)
+    );
+}
+
+#[test]
+fn test_kbkdf_key() {
+    type CmacAes128 = cmac::Cmac<aes::Aes128>;
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    let kin = hex!("000102030405060708090a0b0c0d0e0f");
+    let params = |context| {
+        Params::builder(&kin)
+            .with_label(b"label")
+            .with_context(context)
+            .build()
+    };
+
+    // The key is reused across derivations, the parameters carry no key derivation key
+    let key = KbkdfKey::<CmacAes128>::new(&kin).unwrap();
+    let counter = Counter::<CmacAes128, CmacAes128>::default();
+    for context in [&b"first"[..], b"second", b""] {
+        let keyless = Params::builder(&[])
+            .with_label(b"label")
+            .with_context(context)
+            .build();
+        assert_eq!(
+            counter.derive_with_key(&key, keyless),
+            counter.derive(params(context))
+        );
+    }
+
+    let keyless = |context| Params {
+        kin: &[],
+        ..params(context)
+    };
+    let mut out = [0u8; 100];
+    let mut expected = [0u8; 100];
+    DoublePipeline::<CmacAes128, CmacAes128>::default()
+        .derive_into_with_key(&key, keyless(b"context"), &mut out)
+        .unwrap();
+    DoublePipeline::<CmacAes128, CmacAes128>::default()
+        .derive_into(params(b"context"), &mut expected)
+        .unwrap();
+    assert_eq!(out, expected);
+
+    let key = KbkdfKey::<HmacSha256>::new(&kin).unwrap();
+    let iv = Array::from([0x11; 32]);
+    let feedback = Feedback::<HmacSha256, HmacSha256>::new(Some(&iv));
+    assert_eq!(
+        feedback.derive_with_key(&key, keyless(b"context")),
+        feedback.derive(params(b"context"))
+    );
+
+    // A key derivation key in the parameters is an error, even if it is the same key
+    assert_eq!(
+        feedback.derive_with_key(&key, params(b"context")),
+        Err(Error::UnexpectedKin)
+    );
+    assert_eq!(
+        feedback.derive_into_with_key(&key, params(b"context"), &mut out),
+        Err(Error::UnexpectedKin)
+    );
+
+    assert!(KbkdfKey::<CmacAes128>::new(&kin[..15]).is_err());
+
+    // A key derivation key of an invalid length is an error, not a panic
+    let short_kin = Params::builder(&kin[..15]).build();
+    assert_eq!(counter.derive(short_kin), Err(Error::InvalidKeyLength));
+    assert_eq!(
+        counter.derive_into(short_kin, &mut out),
+        Err(Error::InvalidKeyLength)
+    );
+    assert_eq!(
+        counter.derive_with_layout(
+            &kin[..15],
+            &[DataParam::IterationVariable(Some(
+                CounterFormat::big_endian(32)
+            ))]
+        ),
+        Err(Error::InvalidKeyLength)
+    );
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn test_derive_zeroizing() {
+    type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+    let params = Params::builder(b"secret")
+        .with_label(b"label")
+        .with_context(b"context")
+        .build();
+    let counter = Counter::<HmacSha256, HmacSha256>::default();
+    assert_eq!(
+        *counter.derive_zeroizing(params).unwrap(),
+        counter.derive(params).unwrap()
+    );
+}
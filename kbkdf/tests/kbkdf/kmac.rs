@@ -0,0 +1,254 @@
+//! KMAC tests.
+//!
+//! `kmac_samples` checks the PRF against the KMAC samples of NIST SP 800-185. The known
+//! answers of `derive`, `derive_long` and `openssl_vectors` were generated with the KBKDF
+//! implementation of OpenSSL 3.5, which implements KDF-KMAC of NIST SP 800-108r1, without
+//! `hexsalt` for an empty label:
+//!
+//! ```text
+//! openssl kdf -binary -keylen <L> -kdfopt mac:<KMAC128|KMAC256> -kdfopt hexkey:<kin> \
+//!     -kdfopt hexsalt:<label> -kdfopt hexinfo:<context> KBKDF
+//! ```
+#![cfg(feature = "kmac")]
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use digest::{
+    array::ArraySize,
+    common::KeySizeUser,
+    consts::{U32, U42, U64},
+};
+use hex_literal::hex;
+use kbkdf::{Kmac, Kmac128, Kmac256, Params, XofPrf};
+
+struct MockOutput<Size>(Size);
+
+impl<Size: ArraySize> KeySizeUser for MockOutput<Size> {
+    type KeySize = Size;
+}
+
+const KEY: [u8; 32] = hex!("404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f");
+const CUSTOMIZATION: &[u8] = b"My Tagged Application";
+
+fn data_200() -> [u8; 200] {
+    core::array::from_fn(|i| u8::try_from(i).unwrap())
+}
+
+fn kmac<Prf: XofPrf, const N: usize>(data: &[u8], customization: &[u8]) -> [u8; N] {
+    let mut prf = Prf::new(&KEY, customization);
+    prf.update(data);
+    let mut out = [0u8; N];
+    prf.finalize_into(&mut out);
+    out
+}
+
+#[test]
+fn kmac_samples() {
+    let data = hex!("00010203");
+    assert_eq!(
+        kmac::<Kmac128, 32>(&data, b""),
+        hex!("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e")
+    );
+    assert_eq!(
+        kmac::<Kmac128, 32>(&data, CUSTOMIZATION),
+        hex!("3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5")
+    );
+    assert_eq!(
+        kmac::<Kmac128, 32>(&data_200(), CUSTOMIZATION),
+        hex!("1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230")
+    );
+    assert_eq!(
+        kmac::<Kmac256, 64>(&data, CUSTOMIZATION),
+        hex!(
+            "20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7"
+            "f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd"
+        )
+    );
+    assert_eq!(
+        kmac::<Kmac256, 64>(&data_200(), b""),
+        hex!(
+            "75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691"
+            "589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69"
+        )
+    );
+
+    // Updates are concatenated
+    let mut prf = Kmac128::new(&KEY, CUSTOMIZATION);
+    prf.update(&data_200()[..100]);
+    prf.update(&data_200()[100..]);
+    let mut out = [0u8; 32];
+    prf.finalize_into(&mut out);
+    assert_eq!(out, kmac::<Kmac128, 32>(&data_200(), CUSTOMIZATION));
+}
+
+fn derive_into<Prf: XofPrf, const N: usize>(label: &[u8], context: &[u8]) -> [u8; N] {
+    let mut out = [0u8; N];
+    Kmac::<Prf, MockOutput<U32>>::default().derive_into(
+        Params::builder(&KEY)
+            .with_label(label)
+            .with_context(context)
+            .build(),
+        &mut out,
+    );
+    out
+}
+
+#[test]
+fn derive() {
+    // OpenSSL with `hexkey:KEY`, label "label" and context "context"
+    let params = Params::builder(&KEY)
+        .with_label(b"label")
+        .with_context(b"context")
+        .build();
+    assert_eq!(
+        Kmac::<Kmac128, MockOutput<U32>>::default().derive(params)[..],
+        hex!("74be949c12da9f64857ade5ce20504095dbb0c19dcb2bd25034de04f046b35d9")
+    );
+    assert_eq!(
+        Kmac::<Kmac256, MockOutput<U32>>::default().derive(params)[..],
+        hex!("3f3cbc9726dc58ab39c9a312cdcaff8958c2617c892ba273cedd1556aef7d8f6")
+    );
+    assert_eq!(
+        Kmac::<Kmac128, MockOutput<U42>>::default().derive(params)[..],
+        hex!(
+            "efa6233ed3f3a70938a5bc1b79cdd7b4a841321164fa9364d3b86ae9b4e24d6a"
+            "b1bfca7c12003caf211d"
+        )
+    );
+    assert_eq!(
+        Kmac::<Kmac256, MockOutput<U42>>::default().derive(params)[..],
+        hex!(
+            "8743fe6e76f8d99a20d1e4867d7b03cd807f51e27934280fb95e8ea1353ee257"
+            "3c636a0fba99997be3ac"
+        )
+    );
+
+    // The derived key depends on its length, and not on the other parameters
+    assert_eq!(
+        derive_into::<Kmac128, 42>(b"label", b"context"),
+        kmac::<Kmac128, 42>(b"context", b"label")
+    );
+    assert_ne!(
+        derive_into::<Kmac128, 42>(b"label", b"context")[..32],
+        derive_into::<Kmac128, 32>(b"label", b"context")
+    );
+    let mut out = [0u8; 32];
+    Kmac::<Kmac128, MockOutput<U32>>::default().derive_into(
+        Params::builder(&KEY)
+            .with_label(b"label")
+            .with_context(b"context")
+            .use_l(false)
+            .use_separator(false)
+            .use_counter(false)
+            .build(),
+        &mut out,
+    );
+    assert_eq!(out, derive_into::<Kmac128, 32>(b"label", b"context"));
+}
+
+#[test]
+fn derive_long() {
+    // OpenSSL with `hexkey:KEY`, an empty label and the 200 bytes `00 01 .. c7` as context
+    assert_eq!(
+        derive_into::<Kmac128, 200>(b"", &data_200()),
+        hex!(
+            "d0bfb056d4a4929fdf5b8e3b79bf0a09f7405458438a7924375514c953ee3f7a"
+            "e2d295876144d33cb1d7feb9f1d966bc6af9922437e18f2ab9dd2a05969ee90b"
+            "53840273101143e2cdbeb6838fafa73df50154f8bfdb80452f4e56371233d9be"
+            "d54da1af5ec069d0bfdd515c6635a56e2136b1bf9a39bcf126e8d399af2cfad3"
+            "02e64b6b5efaabce345f4717d231269b5c2f7f8741cb8a39528c46427b109100"
+            "f1989efc0b705b7064c2e81f5921795992eccc623b2e9f06185218e18e46eb1b"
+            "486ccb68dce52a00"
+        )
+    );
+    assert_eq!(
+        derive_into::<Kmac256, 200>(b"", &data_200()),
+        hex!(
+            "99946da6953bce3cee0c25b10114ae058c9ddb69c709e726eb7cae6ee35964e2"
+            "e91d522ba471e8912335abaf6c8e5180bea7e7b50eff48f60711f56860e42904"
+            "0631e1262f24543bbecf2d4b48227cfdb3b9e7cdf56419f4a5ae39a9f25edc44"
+            "1d3fa511f89338f4851ab9dfb481bef37d9b50599d2118e78d09570572268e6c"
+            "c24308cff00793315a7a04e5c6375939efc56c20cb9bd14bfa33e154016812d4"
+            "e65457f1bded81b41210b2f07b5f36c1856f5f80d622a0d7d33609d6c89239a5"
+            "47199ebb4c66797f"
+        )
+    );
+}
+
+const KIN: [u8; 32] = hex!("6525b1216cbacee9b1128783f10289681154eced1cf8cad16ca6f213b066751b");
+const LABEL: [u8; 16] = hex!("5afc2d77d6498d8fd52772560aad8c73");
+const CONTEXT: [u8; 20] = hex!("d21d3347d9d3f802db1eefc06be46dd764c49a28");
+const LONG_INPUT: [u8; 200] = hex!(
+    "f215a42eb7cecc98a9044a52a30de5f91b08f774faee40c039d56fe4d2f2acb3"
+    "2a9e43a6861ae874b76924a415dee0ce502ee434e9ab46198775c2722f3aa18e"
+    "3095bf009dde2c21665fd8f8e69eef3fd2476f2b7209f0866f0622f04126e7cb"
+    "76e28958a29f07ce38411fdc2017d286009cae0aa13fae0af41df8640a6c3f6a"
+    "50fc716dd9def4b95f43633273ba14f093335d1d6e1ef728b04c2ff31a18ce02"
+    "063efccd1a957a7921554bd9a77c9acf7760527d82042286e5cf1d8a55edc6f7"
+    "46e6e8c340cbef69"
+);
+
+#[test]
+fn openssl_vectors() {
+    let params = Params::builder(&KIN)
+        .with_label(&LABEL)
+        .with_context(&CONTEXT)
+        .build();
+    assert_eq!(
+        Kmac::<Kmac128, MockOutput<U32>>::default().derive(params)[..],
+        hex!("1685333eb8d54c31a40ddf460fe910e66d1d8df732a8833ed12dbcadf13b2dce")
+    );
+    assert_eq!(
+        Kmac::<Kmac256, MockOutput<U64>>::default().derive(params)[..],
+        hex!(
+            "fffebf25276d59c03b1aeb78579c3985a7b793f8eda50cea444c153b69e9a327"
+            "e6d1bf4fc0bdb4d25586443a660322fcbae4cbd709f6db5a7f99ff5cbc45a0ec"
+        )
+    );
+
+    // Runtime lengths and inputs longer than the rate
+    let mut out = [0u8; 300];
+    Kmac::<Kmac128, MockOutput<U32>>::default().derive_into(
+        Params::builder(&LABEL)
+            .with_label(&LONG_INPUT)
+            .with_context(&CONTEXT)
+            .build(),
+        &mut out,
+    );
+    assert_eq!(
+        out,
+        hex!(
+            "c433a5f37f526f687a2ad53a31e1775d6ee92965de47a35f66a6d57fa45f7c9e"
+            "23f36afb5f79bd12dc73260300f0ebb2ecba41f97c6b6e290b2fc7df8ee9e74c"
+            "5a16af86363649265c228fab7d15c76a78da821dfd6e708dc685994e31ed7499"
+            "54c7082f1e1b782a794220f82b22ba3a14bd751a0a16f69548ebf86741d5f20c"
+            "c94881e55fc2a84f367bf524e327bb045ffd430c89fce1d52c57a1dfe379b3d2"
+            "793e19f396c9921fbe544007264c581f390c45ea48fe307f385f38a121c6c88a"
+            "f0e696baddc36478989474b4d8a5c81265262895b3531f4a738361a8e31db210"
+            "f072720783ef49a964769c0e58740935635ee546c7a00c7790de3b2f83a08c6d"
+            "2b738971b77b116a4de1dc5d2b8956b3a4312ce404cefee47376dbf79dfee8de"
+            "934feaf652ecaa45994290c3"
+        )
+    );
+    Kmac::<Kmac256, MockOutput<U32>>::default().derive_into(
+        Params::builder(&KIN)
+            .with_label(&CONTEXT)
+            .with_context(&LONG_INPUT)
+            .build(),
+        &mut out,
+    );
+    assert_eq!(
+        out,
+        hex!(
+            "c0c3613add8a105368951e4f46d1901ee96c5ce7799f2cebd207990f2bb34d06"
+            "99e034f124cdf4be076c3435d324ff705fe08400cb3a69f4970d25a9a6f8ba13"
+            "30a78cb1d68940019ba744e846599cc8f6329e5d61e3ffd155b85bf1dc5d9651"
+            "e680003ce4089147fb7e4c3fee13fc1d598df040a6f62fa525988b972fe47302"
+            "49ff56044641ca6ba363ddb8c4a7726da5e48fe9aa56791e2f7918dd653828a4"
+            "6aff6df5fa58b175504131900200263e7bf4a06f735c3321929d362c8869ecdf"
+            "642059083e24e37f0267b9dcd44af7fa1a0086c19efab482589dc516c403816d"
+            "7bede74247125d963930ef59296e4ced3390c64139890d95ecf15f91fc436314"
+            "f5703b41b992d6ab0d4733feecb3335f74ca40ed83d933e6f4454c1ed3ccfda1"
+            "2227456726ee587cefa368fd"
+        )
+    );
+}
@@ -1,5 +1,6 @@
 //! KBKDF tests.
 
+mod kmac;
 mod parser;
 
 type HmacSha1 = hmac::Hmac<sha1::Sha1>;
@@ -3,7 +3,7 @@
 #![allow(clippy::unwrap_used, reason = "tests")]
 
 use digest::consts::*;
-use kbkdf::{Kbkdf, Params};
+use kbkdf::{CounterFormat, DataParam, Kbkdf, Params};
 
 use core::{convert::TryInto, ops::Mul};
 use digest::{
@@ -72,8 +72,41 @@ impl CounterLocation {
         }
     }
 
-    fn is_supported(&self) -> bool {
-        matches!(self, Self::Before | Self::AfterIter)
+    /// Data parameters of the PRF input with the counter at this location.
+    ///
+    /// In Counter Mode, the counter is the iteration variable. Otherwise, the iteration
+    /// variable is followed by the fixed input data and the counter is optional.
+    fn layout<'a>(
+        self,
+        counter_mode: bool,
+        counter: Option<CounterFormat>,
+        fixed_data: &'a [u8],
+        after_counter: &'a [u8],
+    ) -> Vec<DataParam<'a>> {
+        let fixed_data = Some(DataParam::ByteArray(fixed_data));
+        let layout = if counter_mode {
+            let counter = Some(DataParam::IterationVariable(counter));
+            match self {
+                Self::Before => [counter, fixed_data, None],
+                Self::Middle => [
+                    fixed_data,
+                    counter,
+                    Some(DataParam::ByteArray(after_counter)),
+                ],
+                Self::After => [fixed_data, counter, None],
+                _ => panic!("Invalid counter location in counter mode: {self:?}"),
+            }
+        } else {
+            let iteration = Some(DataParam::IterationVariable(None));
+            let counter = counter.map(DataParam::Counter);
+            match self {
+                Self::BeforeIter => [counter, iteration, fixed_data],
+                Self::AfterIter => [iteration, counter, fixed_data],
+                Self::After => [iteration, fixed_data, counter],
+                _ => panic!("Invalid counter location: {self:?}"),
+            }
+        };
+        layout.into_iter().flatten().collect()
     }
 }
 
@@ -108,7 +141,7 @@ trait TestData {
     /// Executes KBKDF key derivation.
     fn test_kbkdf<Prf, K, R>(&self, use_counter: bool)
     where
-        Prf: Mac + KeyInit,
+        Prf: Mac + KeyInit + Clone,
         K: KeySizeUser,
         K::KeySize: ArraySize + Mul<U8>,
         <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -118,6 +151,7 @@ trait TestData {
 }
 
 struct CounterTestData {
+    counter_location: CounterLocation,
     l: usize,
     ki: Vec<u8>,
     fixed_data: (Vec<u8>, Vec<u8>),
@@ -141,11 +175,13 @@ impl TestData for CounterTestData {
         let fixed_data = if let CounterLocation::Middle = counter_location {
             // Skip "DataBeforeCtrLen"
             data.next();
-            let before_counter = hex::decode(&data.next().unwrap()[19..]).unwrap();
+            // DataBeforeCtrData = ...
+            let before_counter = hex::decode(&data.next().unwrap()[20..]).unwrap();
 
             // Skip "DataAfterCtrLen"
             data.next();
-            let after_counter = hex::decode(&data.next().unwrap()[18..]).unwrap();
+            // DataAfterCtrData = ...
+            let after_counter = hex::decode(&data.next().unwrap()[19..]).unwrap();
 
             (before_counter, after_counter)
         } else {
@@ -158,6 +194,7 @@ impl TestData for CounterTestData {
         let ko = hex::decode(&data.next().unwrap()[5..]).unwrap();
 
         Self {
+            counter_location,
             l,
             ki,
             fixed_data,
@@ -167,7 +204,7 @@ impl TestData for CounterTestData {
 
     fn test_kbkdf<Prf, K, R>(&self, use_counter: bool)
     where
-        Prf: Mac + KeyInit,
+        Prf: Mac + KeyInit + Clone,
         K: KeySizeUser,
         K::KeySize: ArraySize + Mul<U8>,
         <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -179,23 +216,38 @@ impl TestData for CounterTestData {
 
         let (label, context) = &self.fixed_data;
 
-        let key = counter
-            .derive(
-                Params::builder(self.ki.as_slice())
-                    .use_l(false)
-                    .use_separator(false)
-                    .use_counter(use_counter)
-                    .with_label(label.as_slice())
-                    .with_context(context.as_slice())
-                    .build(),
-            )
-            .unwrap();
+        if self.counter_location == CounterLocation::Before {
+            let params = Params::builder(self.ki.as_slice())
+                .use_l(false)
+                .use_separator(false)
+                .use_counter(use_counter)
+                .with_label(label.as_slice())
+                .with_context(context.as_slice())
+                .build();
+            let key = counter.derive(params).unwrap();
+
+            assert_eq!(self.ko[..], key[..]);
+
+            let mut key = vec![0u8; self.ko.len()];
+            counter.derive_into(params, &mut key).unwrap();
+
+            assert_eq!(self.ko, key);
+        }
+
+        let layout = self.counter_location.layout(
+            true,
+            Some(CounterFormat::big_endian(R::U32)),
+            label,
+            context,
+        );
+        let key = counter.derive_with_layout(&self.ki, &layout).unwrap();
 
         assert_eq!(self.ko[..], key[..]);
     }
 }
 
 struct DoublePipelineTestData {
+    counter_location: CounterLocation,
     l: usize,
     ki: Vec<u8>,
     fixed_data: Vec<u8>,
@@ -203,7 +255,10 @@ struct DoublePipelineTestData {
 }
 
 impl TestData for DoublePipelineTestData {
-    fn read_test_data<'a>(mut data: impl Iterator<Item = &'a str>, _: CounterLocation) -> Self {
+    fn read_test_data<'a>(
+        mut data: impl Iterator<Item = &'a str>,
+        counter_location: CounterLocation,
+    ) -> Self {
         // L = ...
         let l = data.next().unwrap()[4..].parse().unwrap();
         // KI = ...
@@ -216,6 +271,7 @@ impl TestData for DoublePipelineTestData {
         let ko = hex::decode(&data.next().unwrap()[5..]).unwrap();
 
         Self {
+            counter_location,
             l,
             ki,
             fixed_data,
@@ -225,7 +281,7 @@ impl TestData for DoublePipelineTestData {
 
     fn test_kbkdf<Prf, K, R>(&self, use_counter: bool)
     where
-        Prf: Mac + KeyInit,
+        Prf: Mac + KeyInit + Clone,
         K: KeySizeUser,
         K::KeySize: ArraySize + Mul<U8>,
         <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -235,15 +291,31 @@ impl TestData for DoublePipelineTestData {
     {
         let double_pipeline = kbkdf::DoublePipeline::<Prf, K, R>::default();
 
+        if self.counter_location == CounterLocation::AfterIter {
+            let params = Params::builder(self.ki.as_slice())
+                .use_l(false)
+                .use_separator(false)
+                .use_counter(use_counter)
+                .with_label(self.fixed_data.as_slice())
+                .build();
+            let key = double_pipeline.derive(params).unwrap();
+
+            assert_eq!(self.ko[..], key[..]);
+
+            let mut key = vec![0u8; self.ko.len()];
+            double_pipeline.derive_into(params, &mut key).unwrap();
+
+            assert_eq!(self.ko, key);
+        }
+
+        let layout = self.counter_location.layout(
+            false,
+            use_counter.then(|| CounterFormat::big_endian(R::U32)),
+            &self.fixed_data,
+            &[],
+        );
         let key = double_pipeline
-            .derive(
-                Params::builder(self.ki.as_slice())
-                    .use_l(false)
-                    .use_separator(false)
-                    .use_counter(use_counter)
-                    .with_label(self.fixed_data.as_slice())
-                    .build(),
-            )
+            .derive_with_layout(&self.ki, &layout)
             .unwrap();
 
         assert_eq!(self.ko[..], key[..]);
@@ -255,6 +327,7 @@ impl TestData for DoublePipelineTestData {
 }
 
 struct FeedbackTestData {
+    counter_location: CounterLocation,
     l: usize,
     ki: Vec<u8>,
     iv: Vec<u8>,
@@ -263,7 +336,10 @@ struct FeedbackTestData {
 }
 
 impl TestData for FeedbackTestData {
-    fn read_test_data<'a>(mut data: impl Iterator<Item = &'a str>, _: CounterLocation) -> Self {
+    fn read_test_data<'a>(
+        mut data: impl Iterator<Item = &'a str>,
+        counter_location: CounterLocation,
+    ) -> Self {
         // L = ...
         let l = data.next().unwrap()[4..].parse().unwrap();
         // KI = ...
@@ -281,6 +357,7 @@ impl TestData for FeedbackTestData {
         let ko = hex::decode(&data.next().unwrap()[5..]).unwrap();
 
         Self {
+            counter_location,
             l,
             ki,
             iv,
@@ -291,7 +368,7 @@ impl TestData for FeedbackTestData {
 
     fn test_kbkdf<Prf, K, R>(&self, use_counter: bool)
     where
-        Prf: Mac + KeyInit,
+        Prf: Mac + KeyInit + Clone,
         K: KeySizeUser,
         K::KeySize: ArraySize + Mul<U8>,
         <K::KeySize as Mul<U8>>::Output: Unsigned,
@@ -306,16 +383,30 @@ impl TestData for FeedbackTestData {
         };
         let feedback = kbkdf::Feedback::<Prf, K, R>::new(iv);
 
-        let key = feedback
-            .derive(
-                Params::builder(self.ki.as_slice())
-                    .use_l(false)
-                    .use_separator(false)
-                    .use_counter(use_counter)
-                    .with_label(self.fixed_data.as_slice())
-                    .build(),
-            )
-            .unwrap();
+        if self.counter_location == CounterLocation::AfterIter {
+            let params = Params::builder(self.ki.as_slice())
+                .use_l(false)
+                .use_separator(false)
+                .use_counter(use_counter)
+                .with_label(self.fixed_data.as_slice())
+                .build();
+            let key = feedback.derive(params).unwrap();
+
+            assert_eq!(self.ko[..], key[..]);
+
+            let mut key = vec![0u8; self.ko.len()];
+            feedback.derive_into(params, &mut key).unwrap();
+
+            assert_eq!(self.ko, key);
+        }
+
+        let layout = self.counter_location.layout(
+            false,
+            use_counter.then(|| CounterFormat::big_endian(R::U32)),
+            &self.fixed_data,
+            &[],
+        );
+        let key = feedback.derive_with_layout(&self.ki, &layout).unwrap();
 
         assert_eq!(self.ko[..], key[..]);
     }
@@ -438,10 +529,10 @@ fn eval_test_vectors<T: TestData>(data: &str, use_counter: bool) -> Option<()> {
         } else {
             // Counter location and r-len are not needed and do not present in a test file.
             // We any use any values here, because they are ignored anyway
-            (CounterLocation::Before, Rlen::Bits8)
+            (CounterLocation::AfterIter, Rlen::Bits8)
         };
 
-        if !prf.is_supported() || !counter_location.is_supported() {
+        if !prf.is_supported() {
             // Skip unsupported configuration.
 
             line = loop {
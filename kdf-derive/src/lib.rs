@@ -0,0 +1,376 @@
+#![doc = include_str!("../README.md")]
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/RustCrypto/media/6ee8e381/logo.svg",
+    html_favicon_url = "https://raw.githubusercontent.com/RustCrypto/media/6ee8e381/logo.svg"
+)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Error, Expr, Fields, Ident, Lit, LitByteStr, Result, Type,
+    parse_macro_input, spanned::Spanned,
+};
+
+/// Derive methods splitting the output of a KDF into the fields of a struct.
+///
+/// The fields must be byte arrays (`[u8; N]`) or types implementing `KeyInit` (e.g. a
+/// block cipher), which are initialized with a key of their `KeySize`. The KDFs to
+/// generate methods for are selected with the `#[kdf(hkdf)]` and `#[kdf(kbkdf)]` struct
+/// attributes:
+///
+/// - `hkdf`: `hkdf_expand(hkdf, info)` derives all fields with a single HKDF-Expand call
+///   over their total length and `hkdf_expand_labeled(hkdf)` derives every field with
+///   its own HKDF-Expand call, using the label of the field as `info`.
+/// - `kbkdf`: `kbkdf_derive(kbkdf, params)` derives all fields with a single KBKDF call.
+///   A `KeySizeUser` implementation with the total length as `KeySize` is generated for
+///   the struct, so the lengths of the array fields and the total length must be
+///   supported by `hybrid_array::AssocArraySize` and `ArraySize` respectively.
+///
+/// The label of a field is its name, unless set with `#[kdf(label = "...")]`. Byte string
+/// labels (`b"..."`) are supported as well.
+///
+/// The total length (or the length of each field for `hkdf_expand_labeled`) is checked
+/// against the maximum output length of the KDF at compile time.
+///
+/// ```
+/// use aes::Aes128;
+/// use hkdf::{DeriveKeys, Hkdf};
+/// use sha2::Sha256;
+///
+/// #[derive(DeriveKeys)]
+/// #[kdf(hkdf)]
+/// struct SessionKeys {
+///     cipher: Aes128,
+///     #[kdf(label = b"mac key")]
+///     mac_key: [u8; 32],
+///     iv: [u8; 16],
+/// }
+///
+/// let hk = Hkdf::<Sha256>::new(Some(b"salt"), b"input key material");
+/// let keys = SessionKeys::hkdf_expand(&hk, b"session keys");
+/// let keys = SessionKeys::hkdf_expand_labeled(&hk);
+/// ```
+///
+/// Deriving more than `255 * HashLen` bytes fails to compile:
+///
+/// ```compile_fail
+/// use hkdf::{DeriveKeys, Hkdf};
+/// use sha2::Sha256;
+///
+/// #[derive(DeriveKeys)]
+/// #[kdf(hkdf)]
+/// struct TooLong {
+///     a: [u8; 4096],
+///     b: [u8; 4096],
+/// }
+///
+/// let hk = Hkdf::<Sha256>::new(None, b"input key material");
+/// let keys = TooLong::hkdf_expand(&hk, b"info");
+/// ```
+///
+/// Deriving more than `(2^r - 1) * h` bits with KBKDF fails to compile as well:
+///
+/// ```compile_fail
+/// use hmac::Hmac;
+/// use kbkdf::{Counter, DeriveKeys, Params, digest::consts::U8};
+/// use sha2::Sha256;
+///
+/// #[derive(DeriveKeys)]
+/// #[kdf(kbkdf)]
+/// struct TooLong {
+///     a: [u8; 1024], b: [u8; 1024], c: [u8; 1024], d: [u8; 1024],
+///     e: [u8; 1024], f: [u8; 1024], g: [u8; 1024], h: [u8; 1024],
+/// }
+///
+/// let counter = Counter::<Hmac<Sha256>, TooLong, U8>::default();
+/// let keys = TooLong::kbkdf_derive(&counter, Params::builder(b"secret").build());
+/// ```
+#[proc_macro_derive(DeriveKeys, attributes(kdf))]
+pub fn derive_keys(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Field of the struct.
+struct Field {
+    ident: Ident,
+    kind: Kind,
+    label: LitByteStr,
+}
+
+/// Type of a field.
+enum Kind {
+    /// `[u8; N]` array.
+    Bytes(Expr),
+    /// Type implementing `KeyInit`.
+    Key(Type),
+}
+
+impl Field {
+    fn parse(field: &syn::Field) -> Result<Self> {
+        let ident = field
+            .ident
+            .clone()
+            .ok_or_else(|| Error::new(field.span(), "expected a named field"))?;
+        let kind = match &field.ty {
+            Type::Array(array) => match &*array.elem {
+                Type::Path(elem) if elem.path.is_ident("u8") => Kind::Bytes(array.len.clone()),
+                elem => return Err(Error::new(elem.span(), "expected an array of `u8`")),
+            },
+            ty => Kind::Key(ty.clone()),
+        };
+
+        let mut label = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("kdf"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("label") {
+                    label = Some(match meta.value()?.parse::<Lit>()? {
+                        Lit::Str(lit) => LitByteStr::new(lit.value().as_bytes(), lit.span()),
+                        Lit::ByteStr(lit) => lit,
+                        lit => return Err(Error::new(lit.span(), "expected a string literal")),
+                    });
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported field attribute, expected `label`"))
+                }
+            })?;
+        }
+        let label =
+            label.unwrap_or_else(|| LitByteStr::new(ident.to_string().as_bytes(), ident.span()));
+
+        Ok(Self { ident, kind, label })
+    }
+
+    /// Length of the field in bytes as a `usize` constant expression.
+    fn len(&self, digest: &TokenStream2) -> TokenStream2 {
+        match &self.kind {
+            Kind::Bytes(len) => quote!((#len)),
+            Kind::Key(ty) => quote! {
+                <<#ty as #digest::common::KeySizeUser>::KeySize as #digest::typenum::Unsigned>::USIZE
+            },
+        }
+    }
+
+    /// Length of the field in bytes as a type-level integer.
+    fn size(&self, digest: &TokenStream2) -> TokenStream2 {
+        match &self.kind {
+            Kind::Bytes(len) => quote!(<[u8; #len] as #digest::array::AssocArraySize>::Size),
+            Kind::Key(ty) => quote!(<#ty as #digest::common::KeySizeUser>::KeySize),
+        }
+    }
+
+    /// Construct the field from the slice `okm`.
+    fn init(&self, digest: &TokenStream2, okm: &TokenStream2) -> TokenStream2 {
+        match &self.kind {
+            Kind::Bytes(len) => quote! {
+                <[u8; #len]>::try_from(#okm).expect("field length is correct")
+            },
+            Kind::Key(ty) => quote! {
+                <#ty as #digest::KeyInit>::new_from_slice(#okm).expect("key size is correct")
+            },
+        }
+    }
+}
+
+/// KDFs to generate methods for.
+#[derive(Default)]
+struct Kdfs {
+    hkdf: bool,
+    kbkdf: bool,
+}
+
+impl Kdfs {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut kdfs = Self::default();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("kdf"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("hkdf") {
+                    kdfs.hkdf = true;
+                    Ok(())
+                } else if meta.path.is_ident("kbkdf") {
+                    kdfs.kbkdf = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported KDF, expected `hkdf` or `kbkdf`"))
+                }
+            })?;
+        }
+        if !kdfs.hkdf && !kdfs.kbkdf {
+            return Err(Error::new(
+                input.ident.span(),
+                "expected `#[kdf(hkdf)]` and/or `#[kdf(kbkdf)]` attribute",
+            ));
+        }
+        Ok(kdfs)
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "`DeriveKeys` does not support generic structs",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            fields => return Err(Error::new(fields.span(), "expected named fields")),
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`DeriveKeys` only supports structs",
+            ));
+        }
+    };
+    if fields.is_empty() {
+        return Err(Error::new(input.span(), "expected at least one field"));
+    }
+    let fields = fields
+        .iter()
+        .map(Field::parse)
+        .collect::<Result<Vec<_>>>()?;
+    let kdfs = Kdfs::parse(input)?;
+
+    let mut out = TokenStream2::new();
+    if kdfs.hkdf {
+        out.extend(expand_hkdf(&input.ident, &fields));
+    }
+    if kdfs.kbkdf {
+        out.extend(expand_kbkdf(&input.ident, &fields));
+    }
+    Ok(out)
+}
+
+/// Sum of the lengths of `fields` as a `usize` constant expression.
+fn total_len(fields: &[Field], digest: &TokenStream2) -> TokenStream2 {
+    let lens = fields.iter().map(|field| field.len(digest));
+    quote!(0 #(+ #lens)*)
+}
+
+/// Struct expression constructing `fields` from consecutive parts of the slice `okm`.
+fn split(fields: &[Field], digest: &TokenStream2, okm: &Ident) -> TokenStream2 {
+    let pos = format_ident!("__pos");
+    let inits = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let len = field.len(digest);
+        let value = field.init(digest, &quote!(&#okm[#pos..#pos + #len]));
+        quote! {
+            #ident: {
+                let value = #value;
+                #pos += #len;
+                value
+            }
+        }
+    });
+    quote! {{
+        let mut #pos = 0usize;
+        let keys = Self { #(#inits,)* };
+        debug_assert_eq!(#pos, #okm.len());
+        keys
+    }}
+}
+
+fn expand_hkdf(ident: &Ident, fields: &[Field]) -> TokenStream2 {
+    let digest = quote!(::hkdf::hmac::digest);
+    let total_len = total_len(fields, &digest);
+    let okm = format_ident!("okm");
+    let split = split(fields, &digest, &okm);
+    let labeled = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let label = &field.label;
+        match &field.kind {
+            Kind::Bytes(len) => quote! {
+                #ident: <[u8; #len]>::clone(&hkdf.expand_array::<{ #len }>(#label))
+            },
+            Kind::Key(ty) => quote! {
+                #ident: <#ty as #digest::KeyInit>::new(&hkdf.expand_key::<#ty>(#label))
+            },
+        }
+    });
+
+    quote! {
+        impl #ident {
+            /// Derive all keys with a single HKDF-Expand call over their total length.
+            #[must_use]
+            pub fn hkdf_expand<H: ::hkdf::HmacImpl>(
+                hkdf: &::hkdf::GenericHkdf<H>,
+                info: &[u8],
+            ) -> Self {
+                const LEN: usize = #total_len;
+                let #okm = hkdf.expand_array::<LEN>(info);
+                let #okm: &[u8] = &#okm[..];
+                #split
+            }
+
+            /// Derive every key with its own HKDF-Expand call, using its label as `info`.
+            #[must_use]
+            pub fn hkdf_expand_labeled<H: ::hkdf::HmacImpl>(hkdf: &::hkdf::GenericHkdf<H>) -> Self {
+                Self { #(#labeled,)* }
+            }
+        }
+    }
+}
+
+fn expand_kbkdf(ident: &Ident, fields: &[Field]) -> TokenStream2 {
+    let digest = quote!(::kbkdf::digest);
+    let total_len = total_len(fields, &digest);
+    let size = fields
+        .iter()
+        .map(|field| field.size(&digest))
+        .reduce(|sum, size| quote!(#digest::typenum::Sum<#sum, #size>))
+        .expect("struct has at least one field");
+    let okm = format_ident!("okm");
+    let split = split(fields, &digest, &okm);
+
+    quote! {
+        impl #digest::common::KeySizeUser for #ident {
+            type KeySize = #size;
+        }
+
+        impl #ident {
+            /// Derive all keys with a single KBKDF call over their total length.
+            ///
+            /// # Errors
+            /// Returns the error of [`Kbkdf::derive`][::kbkdf::Kbkdf::derive].
+            pub fn kbkdf_derive<Prf, R, K>(
+                kbkdf: &K,
+                params: ::kbkdf::Params<'_, '_, '_>,
+            ) -> ::core::result::Result<Self, ::kbkdf::Error>
+            where
+                K: ::kbkdf::Kbkdf<Prf, Self, R>,
+                R: ::kbkdf::sealed::R,
+                Prf: #digest::Mac + #digest::KeyInit + ::core::clone::Clone,
+                Prf::OutputSize: #digest::array::ArraySize
+                    + ::core::ops::Mul<#digest::consts::U8>,
+                <Prf::OutputSize as ::core::ops::Mul<#digest::consts::U8>>::Output:
+                    #digest::typenum::Unsigned,
+            {
+                const LEN: usize = #total_len;
+                const {
+                    assert!(
+                        (LEN.div_ceil(<Prf::OutputSize as #digest::typenum::Unsigned>::USIZE)
+                            as u64)
+                            < (1u64 << <R as #digest::typenum::Unsigned>::U32),
+                        "total key size exceeds the maximum KBKDF output length",
+                    );
+                }
+                let #okm = kbkdf.derive(params)?;
+                let #okm: &[u8] = &#okm[..];
+                ::core::result::Result::Ok(#split)
+            }
+        }
+    }
+}
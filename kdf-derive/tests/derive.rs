@@ -0,0 +1,114 @@
+//! `DeriveKeys` tests.
+#![allow(clippy::unwrap_used, reason = "tests")]
+
+use aes::{
+    Aes128,
+    cipher::{Array, BlockCipherEncrypt, KeyInit},
+};
+use hkdf::{DeriveKeys, Hkdf};
+use hmac::{
+    Hmac,
+    digest::{common::KeySizeUser, consts::U76},
+};
+use kbkdf::{Counter, Feedback, Kbkdf, Params};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(DeriveKeys)]
+#[kdf(hkdf, kbkdf)]
+struct Keys {
+    enc_key: [u8; 32],
+    #[kdf(label = "mac key")]
+    mac_key: [u8; 32],
+    #[kdf(label = b"iv\x00")]
+    iv: [u8; 12],
+}
+
+#[derive(DeriveKeys)]
+#[kdf(hkdf)]
+struct CipherKeys {
+    cipher: Aes128,
+    iv: [u8; 16],
+}
+
+/// Key of the total size of [`Keys`].
+struct KeysLen;
+
+impl KeySizeUser for KeysLen {
+    type KeySize = U76;
+}
+
+fn hkdf() -> Hkdf<Sha256> {
+    Hkdf::<Sha256>::new(Some(b"salt"), b"input key material")
+}
+
+fn expand<const N: usize>(info: &[u8]) -> [u8; N] {
+    let mut okm = [0u8; N];
+    hkdf().expand(info, &mut okm).unwrap();
+    okm
+}
+
+fn encrypt(cipher: &Aes128) -> Array<u8, aes::cipher::consts::U16> {
+    let mut block = Array::default();
+    cipher.encrypt_block(&mut block);
+    block
+}
+
+#[test]
+fn hkdf_expand() {
+    let okm = expand::<76>(b"info");
+
+    let keys = Keys::hkdf_expand(&hkdf(), b"info");
+    assert_eq!(keys.enc_key, okm[..32]);
+    assert_eq!(keys.mac_key, okm[32..64]);
+    assert_eq!(keys.iv, okm[64..]);
+}
+
+#[test]
+fn hkdf_expand_labeled() {
+    let keys = Keys::hkdf_expand_labeled(&hkdf());
+    assert_eq!(keys.enc_key, expand::<32>(b"enc_key"));
+    assert_eq!(keys.mac_key, expand::<32>(b"mac key"));
+    assert_eq!(keys.iv, expand::<12>(b"iv\x00"));
+}
+
+#[test]
+fn key_init_field() {
+    let okm = expand::<32>(b"info");
+    let keys = CipherKeys::hkdf_expand(&hkdf(), b"info");
+    let cipher = Aes128::new_from_slice(&okm[..16]).unwrap();
+    assert_eq!(encrypt(&keys.cipher), encrypt(&cipher));
+    assert_eq!(keys.iv, okm[16..]);
+
+    let keys = CipherKeys::hkdf_expand_labeled(&hkdf());
+    let cipher = Aes128::new_from_slice(&expand::<16>(b"cipher")).unwrap();
+    assert_eq!(encrypt(&keys.cipher), encrypt(&cipher));
+    assert_eq!(keys.iv, expand::<16>(b"iv"));
+}
+
+#[test]
+fn kbkdf_derive() {
+    let params = || {
+        Params::builder(b"key derivation key")
+            .with_label(b"label")
+            .with_context(b"context")
+            .build()
+    };
+
+    let okm = Counter::<HmacSha256, KeysLen>::default()
+        .derive(params())
+        .unwrap();
+    let keys = Keys::kbkdf_derive(&Counter::<HmacSha256, Keys>::default(), params()).unwrap();
+    assert_eq!(keys.enc_key, okm[..32]);
+    assert_eq!(keys.mac_key, okm[32..64]);
+    assert_eq!(keys.iv, okm[64..]);
+
+    let okm = Feedback::<HmacSha256, KeysLen>::new(None)
+        .derive(params())
+        .unwrap();
+    let keys = Keys::kbkdf_derive(&Feedback::<HmacSha256, Keys>::new(None), params()).unwrap();
+    assert_eq!(keys.enc_key, okm[..32]);
+    assert_eq!(keys.mac_key, okm[32..64]);
+    assert_eq!(keys.iv, okm[64..]);
+}
@@ -7,10 +7,30 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 use core::fmt;
-use digest::{Digest, FixedOutputReset, Update, array::typenum::Unsigned};
+use digest::{Digest, FixedOutputReset, Output, Update, array::typenum::Unsigned};
+
+#[cfg(feature = "zeroize")]
+use digest::zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Hash function whose state is wiped on drop if the `zeroize` feature is enabled.
+///
+/// The state of the hash function holds bytes of the secret after every round. With the
+/// `zeroize` feature, this trait is implemented only for hash functions implementing
+/// [`ZeroizeOnDrop`], e.g. the `sha2` hashes with their own `zeroize` feature. Without it,
+/// it is implemented for all types.
+pub trait WipeOnDrop {}
+
+#[cfg(feature = "zeroize")]
+impl<D: ZeroizeOnDrop> WipeOnDrop for D {}
+
+#[cfg(not(feature = "zeroize"))]
+impl<D> WipeOnDrop for D {}
 
 /// Derives `key` in-place from `secret` and `other_info`.
 ///
+/// With the `zeroize` feature, the hash state and the last hash output are wiped once `key`
+/// is derived, see [`WipeOnDrop`].
+///
 /// # Example
 /// ```rust
 /// use hex_literal::hex;
@@ -27,7 +47,7 @@ use digest::{Digest, FixedOutputReset, Update, array::typenum::Unsigned};
 /// - Returns [`Error::CounterOverflow`] if `key` is too large.
 pub fn derive_key_into<D>(secret: &[u8], other_info: &[u8], key: &mut [u8]) -> Result<(), Error>
 where
-    D: Digest + FixedOutputReset,
+    D: Digest + FixedOutputReset + WipeOnDrop,
 {
     if secret.is_empty() {
         return Err(Error::NoSecret);
@@ -43,16 +63,19 @@ where
     }
 
     let mut digest = D::new();
-    let mut counter: u32 = 1;
+    let mut block = Output::<D>::default();
 
-    for chunk in key.chunks_mut(D::OutputSize::USIZE) {
+    for (counter, chunk) in (1..=u32::MAX).zip(key.chunks_mut(D::OutputSize::USIZE)) {
         Update::update(&mut digest, &counter.to_be_bytes());
         Update::update(&mut digest, secret);
         Update::update(&mut digest, other_info);
-        chunk.copy_from_slice(&digest.finalize_reset()[..chunk.len()]);
-        counter += 1;
+        Digest::finalize_into_reset(&mut digest, &mut block);
+        chunk.copy_from_slice(&block[..chunk.len()]);
     }
 
+    #[cfg(feature = "zeroize")]
+    block.as_mut_slice().zeroize();
+
     Ok(())
 }
 
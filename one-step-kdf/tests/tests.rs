@@ -13,7 +13,7 @@ struct Fixture<'a> {
 
 fn test_key_derivation<D>(fixtures: &[Fixture])
 where
-    D: Digest + FixedOutputReset,
+    D: Digest + FixedOutputReset + one_step_kdf::WipeOnDrop,
 {
     for f in fixtures.iter() {
         let mut buf = [0u8; 256];
@@ -190,3 +190,14 @@ fn test_no_output() {
         Err(one_step_kdf::Error::NoOutput)
     );
 }
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn test_zeroize_on_drop() {
+    fn assert_zeroize_on_drop<D: digest::zeroize::ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<Sha256>();
+
+    let mut key = [0u8; 16];
+    one_step_kdf::derive_key_into::<Sha256>(b"secret", b"shared-info", &mut key).unwrap();
+    assert_ne!(key, [0u8; 16]);
+}